@@ -0,0 +1,282 @@
+//! Converts skybox images to six faces one above the other, the way the game loads them.
+//!
+//! Input is one image in any [`SkyboxLayout`], or a pattern of six face files with `{}` in
+//! place of face names, like `sky_{}.png`. Output is a PNG image or a KTX2 cubemap, without
+//! output the input is only checked. Errors tell which step of conversion failed, so that
+//! sky images can be checked before they are shipped in a resource pack.
+
+use std::path::Path;
+use std::process::ExitCode;
+use std::{env, fmt, fs};
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+use librecraft::skybox_image::{
+    FACE_NAMES, ImageError, ImageMeasurements, SkyboxLayout, convert_skybox, encode_ktx2_cubemap,
+    face_path, find_background,
+};
+
+/// Formats the stacked faces can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Png,
+    Ktx2,
+}
+
+/// Command line arguments.
+#[derive(Debug)]
+struct Options {
+    input: String,
+    output: Option<(String, OutputFormat)>,
+    layout: Option<SkyboxLayout>,
+}
+
+/// Error that stops conversion.
+#[derive(Debug)]
+enum ConvertError {
+    /// Arguments are wrong, usage is printed.
+    Usage(String),
+    /// File can't be read or written.
+    File { path: String, message: String },
+    /// Skybox image is incorrect, `step` tells what was being done with it.
+    Image {
+        path: String,
+        step: String,
+        error: ImageError,
+    },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(message) => write!(f, "{}", message),
+            Self::File { path, message } => write!(f, "{}: {}", path, message),
+            Self::Image { path, step, error } => {
+                write!(f, "{}: {} while {}", path, error, step)
+            },
+        }
+    }
+}
+
+fn usage() -> String {
+    let layouts = SkyboxLayout::ALL.map(|layout| layout.name()).join(", ");
+    format!(
+        concat!(
+            "Usage: skybox-convert <input> [output.png|output.ktx2] [--layout <layout>]\n",
+            "Input can be a pattern of face files, like sky_{{}}.png, with {{}} replaced by {}.\n",
+            "Layouts: {}. If not given, layout is told by aspect ratio."
+        ),
+        FACE_NAMES.join(", "),
+        layouts
+    )
+}
+
+fn parse_args(args: &[String]) -> Result<Options, ConvertError> {
+    let mut paths = Vec::new();
+    let mut layout = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--layout" => {
+                let name = args.next().map(String::as_str).unwrap_or_default();
+                layout = Some(SkyboxLayout::from_name(name).ok_or_else(|| {
+                    ConvertError::Usage(format!("Unknown layout '{}'.\n{}", name, usage()))
+                })?);
+            },
+            "-h" | "--help" => return Err(ConvertError::Usage(usage())),
+            _ => paths.push(arg.clone()),
+        }
+    }
+
+    let (input, output) = match &paths[..] {
+        [input] => (input.clone(), None),
+        [input, output] => {
+            let format = match Path::new(output).extension().and_then(|ext| ext.to_str()) {
+                Some("png") => OutputFormat::Png,
+                Some("ktx2") => OutputFormat::Ktx2,
+                _ => {
+                    return Err(ConvertError::Usage(format!(
+                        "Output must be .png or .ktx2, got '{}'.",
+                        output
+                    )));
+                },
+            };
+            (input.clone(), Some((output.clone(), format)))
+        },
+        _ => return Err(ConvertError::Usage(usage())),
+    };
+    Ok(Options {
+        input,
+        output,
+        layout,
+    })
+}
+
+fn open(path: &str) -> Result<DynamicImage, ConvertError> {
+    image::open(path).map_err(|e| ConvertError::File {
+        path: path.to_owned(),
+        message: e.to_string(),
+    })
+}
+
+/// Checks that six face files are squares of the same size and stacks them.
+fn convert_faces(pattern: &str) -> Result<RgbaImage, ConvertError> {
+    let faces = FACE_NAMES
+        .iter()
+        .map(|face| open(&face_path(pattern, face)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let side = faces[0].width();
+    for (face, image) in FACE_NAMES.iter().zip(&faces) {
+        let (width, height) = image.dimensions();
+        if (width, height) != (side, side) {
+            return Err(ConvertError::Image {
+                path: face_path(pattern, face),
+                step: format!(
+                    "checking face size, it's {}x{} but faces must be {}x{}",
+                    width, height, side, side
+                ),
+                error: ImageError::NotAligned,
+            });
+        }
+    }
+    convert_skybox(&faces, Some(SkyboxLayout::Faces)).map_err(|error| ConvertError::Image {
+        path: pattern.to_owned(),
+        step: "stacking faces".to_owned(),
+        error,
+    })
+}
+
+/// Converts one image, going through the steps of its layout one by one.
+fn convert_image(path: &str, layout: Option<SkyboxLayout>) -> Result<RgbaImage, ConvertError> {
+    let source = open(path)?;
+    let (width, height) = source.dimensions();
+    let fail = |step: String| {
+        let path = path.to_owned();
+        move |error| ConvertError::Image { path, step, error }
+    };
+
+    let layout = layout
+        .or_else(|| SkyboxLayout::detect(width, height))
+        .ok_or(ImageError::UnknownLayout)
+        .map_err(fail(format!(
+            "detecting layout of {}x{} image",
+            width, height
+        )))?;
+    println!("{}: {} layout, {}x{}", path, layout.name(), width, height);
+
+    if layout != SkyboxLayout::HorizontalCross {
+        return convert_skybox(&[source], Some(layout))
+            .map_err(fail(format!("copying faces of {} layout", layout.name())));
+    }
+
+    // Cross can be surrounded by background, so it's measured first.
+    let rgba = DynamicImage::from(source.to_rgba8());
+    find_background(&rgba).map_err(fail(
+        "sampling background color around the cross".to_owned(),
+    ))?;
+    let measurements = ImageMeasurements::find_measurements(&rgba).map_err(fail(
+        "measuring edges of the cross against background".to_owned(),
+    ))?;
+    measurements
+        .to_rgba(&rgba)
+        .map_err(fail("copying faces of the cross".to_owned()))
+}
+
+fn run(options: &Options) -> Result<(), ConvertError> {
+    let stack = if options.input.contains("{}") || options.layout == Some(SkyboxLayout::Faces) {
+        convert_faces(&options.input)?
+    } else {
+        convert_image(&options.input, options.layout)?
+    };
+    let side = stack.width();
+
+    let Some((path, format)) = &options.output else {
+        println!(
+            "{}: correct skybox, faces are {}x{}",
+            options.input, side, side
+        );
+        return Ok(());
+    };
+    let written = match format {
+        OutputFormat::Png => stack.save(path).map_err(|e| e.to_string()),
+        OutputFormat::Ktx2 => {
+            fs::write(path, encode_ktx2_cubemap(&stack)).map_err(|e| e.to_string())
+        },
+    };
+    written.map_err(|message| ConvertError::File {
+        path: path.clone(),
+        message,
+    })?;
+    println!("{}: written, faces are {}x{}", path, side, side);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_args(&args).and_then(|options| run(&options)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, ConvertError> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    fn usage_error(args: &[&str]) -> String {
+        match parse(args) {
+            Err(ConvertError::Usage(message)) => message,
+            result => panic!("{args:?} gave {result:?} instead of usage"),
+        }
+    }
+
+    #[test]
+    fn layout_and_output_are_parsed() {
+        let options = parse(&["sky.png"]).unwrap();
+        assert_eq!(options.input, "sky.png");
+        assert!(options.output.is_none());
+        assert_eq!(options.layout, None);
+
+        let options = parse(&["--layout", "grid", "sky.png", "sky.ktx2"]).unwrap();
+        assert_eq!(options.layout, Some(SkyboxLayout::Grid));
+        assert_eq!(
+            options.output,
+            Some(("sky.ktx2".to_owned(), OutputFormat::Ktx2))
+        );
+
+        let options = parse(&["sky_{}.png", "sky.png", "--layout", "faces"]).unwrap();
+        assert_eq!(options.input, "sky_{}.png");
+        assert_eq!(options.layout, Some(SkyboxLayout::Faces));
+        assert_eq!(
+            options.output,
+            Some(("sky.png".to_owned(), OutputFormat::Png))
+        );
+    }
+
+    #[test]
+    fn wrong_args_print_usage() {
+        assert!(
+            usage_error(&["sky.png", "--layout", "diamond"])
+                .starts_with("Unknown layout 'diamond'.")
+        );
+        assert!(usage_error(&["sky.png", "--layout"]).starts_with("Unknown layout ''."));
+        assert_eq!(
+            usage_error(&["sky.png", "sky.jpg"]),
+            "Output must be .png or .ktx2, got 'sky.jpg'."
+        );
+        assert_eq!(
+            usage_error(&["sky.png", "sky"]),
+            "Output must be .png or .ktx2, got 'sky'."
+        );
+        assert_eq!(usage_error(&[]), usage());
+        assert_eq!(usage_error(&["a.png", "b.png", "c.png"]), usage());
+        assert_eq!(usage_error(&["sky.png", "--help"]), usage());
+    }
+}
@@ -0,0 +1,215 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::input::mouse::AccumulatedMouseMotion;
+use bevy::prelude::*;
+use librecraft_shared::chunk::BlockPos;
+use librecraft_shared::physics::{BlockCollider, MovementInput, PlayerPhysics};
+
+use crate::game::player::{Player, PlayerDataUpdated};
+use crate::game::world::ChunkManager;
+use crate::settings::Settings;
+
+/// Camera can't look further up or down than this, so that it never turns upside down.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// Keys that move the player.
+pub const KEY_FORWARD: KeyCode = KeyCode::KeyW;
+pub const KEY_BACKWARD: KeyCode = KeyCode::KeyS;
+pub const KEY_LEFT: KeyCode = KeyCode::KeyA;
+pub const KEY_RIGHT: KeyCode = KeyCode::KeyD;
+pub const KEY_JUMP: KeyCode = KeyCode::Space;
+pub const KEY_SNEAK: KeyCode = KeyCode::ShiftLeft;
+pub const KEY_SPRINT: KeyCode = KeyCode::ControlLeft;
+
+/// Longest time between two presses of jump that toggle flight, in seconds.
+const DOUBLE_TAP_TIME: f32 = 0.35;
+
+/// Where the player appears, on top of the highest block of this column.
+const SPAWN_POSITION: [f64; 3] = [0.5, 0., 0.5];
+
+/// Physics of the local player, stepped on [`FixedUpdate`].
+#[derive(Resource, Debug)]
+pub struct PlayerBody {
+    pub physics: PlayerPhysics,
+    /// Position before the last step, camera is interpolated from it to the current one.
+    pub previous_position: [f64; 3],
+    /// Whether the player was put on the ground. Physics waits for the spawn chunk before that.
+    pub spawned: bool,
+}
+
+impl Default for PlayerBody {
+    fn default() -> Self {
+        Self {
+            physics: PlayerPhysics::new(SPAWN_POSITION),
+            previous_position: SPAWN_POSITION,
+            spawned: false,
+        }
+    }
+}
+
+/// Degrees the camera turns per pixel of mouse motion, the same way as in Minecraft.
+pub fn degrees_per_pixel(sensitivity: f32) -> f32 {
+    let f = sensitivity.clamp(0., 1.) * 0.6 + 0.2;
+    f * f * f * 1.2
+}
+
+/// Value of an axis controlled by `positive` and `negative` keys.
+fn axis(keys: &ButtonInput<KeyCode>, positive: KeyCode, negative: KeyCode) -> f32 {
+    keys.pressed(positive) as i8 as f32 - keys.pressed(negative) as i8 as f32
+}
+
+/// Turns the camera by mouse motion.
+pub fn look_around(
+    motion: Res<AccumulatedMouseMotion>,
+    settings: Res<Settings>,
+    mut camera_q: Query<&mut Transform, With<Camera3d>>,
+) {
+    if motion.delta == Vec2::ZERO {
+        return;
+    }
+
+    let Ok(mut transform) = camera_q.single_mut() else {
+        return;
+    };
+
+    let scale = degrees_per_pixel(settings.mouse_sensitivity).to_radians();
+    let dy = if settings.invert_y {
+        -motion.delta.y
+    } else {
+        motion.delta.y
+    };
+    let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+    let yaw = yaw - motion.delta.x * scale;
+    let pitch = (pitch - dy * scale).clamp(-MAX_PITCH, MAX_PITCH);
+    transform.rotation = Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.);
+}
+
+/// Writes flight state of `body` into player's abilities, so that it's saved.
+fn write_flying(
+    body: &PlayerBody,
+    player: &mut Player,
+    updated_writer: &mut EventWriter<PlayerDataUpdated>,
+) {
+    let flying = body.physics.flying as i8;
+    if player.abilities.flying != flying {
+        player.abilities.flying = flying;
+        updated_writer.write(PlayerDataUpdated);
+    }
+}
+
+/// Toggles flight by pressing jump twice quickly, if the player may fly.
+pub fn toggle_flight(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut player: ResMut<Player>,
+    mut body: ResMut<PlayerBody>,
+    mut updated_writer: EventWriter<PlayerDataUpdated>,
+    mut last_press: Local<Option<f32>>,
+) {
+    if !keys.just_pressed(KEY_JUMP) || !body.spawned || body.physics.noclip || !player.may_fly() {
+        return;
+    }
+
+    let now = time.elapsed_secs();
+    if last_press.is_some_and(|last| now - last <= DOUBLE_TAP_TIME) {
+        *last_press = None;
+        body.physics.flying = !body.physics.flying;
+        debug!("Flying: {}", body.physics.flying);
+        write_flying(&body, &mut player, &mut updated_writer);
+    } else {
+        *last_press = Some(now);
+    }
+}
+
+/// Steps player physics with WASD movement, jumping by space, sneaking by shift and
+/// sprinting by control. Flying player goes up by jump and down by sneak, spectators fly
+/// through blocks.
+///
+/// Until the chunk under the player is loaded, it waits and then puts the player on top of it.
+pub fn step_player_physics(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    mut player: ResMut<Player>,
+    manager: Res<ChunkManager>,
+    camera_q: Query<&Transform, With<Camera3d>>,
+    mut body: ResMut<PlayerBody>,
+    mut updated_writer: EventWriter<PlayerDataUpdated>,
+) {
+    let [x, _, z] = body.physics.position;
+    let pos = BlockPos::new(x.floor() as i32, 0, z.floor() as i32);
+    if !body.spawned {
+        let Some(chunk) = manager.chunk(pos.chunk()) else {
+            return;
+        };
+        let (local_x, _, local_z) = pos.local();
+        body.physics.position[1] = chunk.height(local_x, local_z) as f64;
+        body.previous_position = body.physics.position;
+        body.physics.flying = player.abilities.flying != 0;
+        body.spawned = true;
+        return;
+    }
+    body.physics.noclip = player.is_spectator();
+
+    let yaw = camera_q
+        .single()
+        .map(|transform| transform.rotation.to_euler(EulerRot::YXZ).0)
+        .unwrap_or_default();
+    let input = MovementInput {
+        forward: axis(&keys, KEY_FORWARD, KEY_BACKWARD) as f64,
+        strafe: axis(&keys, KEY_RIGHT, KEY_LEFT) as f64,
+        yaw: yaw as f64,
+        jump: keys.pressed(KEY_JUMP),
+        sneak: keys.pressed(KEY_SNEAK),
+        sprint: keys.pressed(KEY_SPRINT),
+    };
+    // Nothing is below the world, so it stops the player like unloaded chunks.
+    let collider = BlockCollider(
+        |pos: BlockPos| {
+            if pos.y < 0 { None } else { manager.block(pos) }
+        },
+    );
+
+    body.previous_position = body.physics.position;
+    let (walk_speed, fly_speed) = (player.abilities.walk_speed, player.abilities.fly_speed);
+    body.physics.step(
+        &input,
+        walk_speed as f64,
+        fly_speed as f64,
+        time.delta_secs_f64(),
+        &collider,
+    );
+    write_flying(&body, &mut player, &mut updated_writer);
+}
+
+/// Puts the camera at player's eyes, interpolated between the last two physics steps.
+pub fn update_camera_position(
+    fixed_time: Res<Time<Fixed>>,
+    body: Res<PlayerBody>,
+    mut camera_q: Query<&mut Transform, With<Camera3d>>,
+) {
+    let Ok(mut transform) = camera_q.single_mut() else {
+        return;
+    };
+
+    let t = fixed_time.overstep_fraction_f64();
+    let [x, y, z] = [0, 1, 2].map(|i| {
+        let (previous, current) = (body.previous_position[i], body.physics.position[i]);
+        previous + (current - previous) * t
+    });
+    let eyes = Vec3::new(x as f32, (y + body.physics.eye_height()) as f32, z as f32);
+    if transform.translation != eyes {
+        transform.translation = eyes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitivity_matches_minecraft() {
+        assert!((degrees_per_pixel(0.5) - 0.15).abs() < 1e-6);
+        assert!(degrees_per_pixel(0.) < degrees_per_pixel(1.));
+        assert_eq!(degrees_per_pixel(2.), degrees_per_pixel(1.));
+    }
+}
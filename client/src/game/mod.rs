@@ -13,6 +13,8 @@ use crate::gui::{self, debug, hud, menu, GUIState};
 use crate::music;
 use crate::{consts, settings};
 
+/// Responsible for first-person camera and movement.
+pub mod controller;
 /// Responsible for player logic.
 pub mod player;
 /// Responsible for world logic.
@@ -49,6 +51,13 @@ impl<S: States> Plugin for GamePlugin<S> {
                     GameplaySet.run_if(in_state(GUIState::Closed)),
                 ),
             )
+            .configure_sets(
+                FixedUpdate,
+                (
+                    GameplaySet.run_if(in_state(self.state.clone())),
+                    GameplaySet.run_if(in_state(GUIState::Closed)),
+                ),
+            )
             .insert_state(debug_state)
             .configure_sets(
                 Update,
@@ -64,11 +73,25 @@ impl<S: States> Plugin for GamePlugin<S> {
                 assets::SKYBOX_TEST_PATH,
             ));
         }
+        app.add_plugins((
+            world::BlockModelPlugin,
+            world::BlockMaterialPlugin,
+            world::ParticleRenderPlugin,
+        ));
         app.init_resource::<settings::Settings>()
             .init_resource::<player::Player>()
+            .init_resource::<world::TargetedBlock>()
+            .init_resource::<controller::PlayerBody>()
             .add_event::<gui::GUIScaleChanged>()
             .add_event::<hud::HotbarSelectionChanged>()
             .add_event::<settings::SettingsUpdated>()
+            .add_event::<player::PlayerDataUpdated>()
+            .add_event::<world::ChunkLoaded>()
+            .add_event::<world::TimeUpdated>()
+            .add_event::<world::GameStateChanged>()
+            .add_event::<world::BlockBroken>()
+            .add_event::<world::ParticleEmitted>()
+            .add_event::<gui::command::CommandEntered>()
             .add_systems(
                 OnEnter(self.state.clone()),
                 (settings::setup_settings, player::setup_player_data).in_set(DataSet),
@@ -80,6 +103,15 @@ impl<S: States> Plugin for GamePlugin<S> {
                     menu::setup_pause_menu,
                     hud::setup_hotbar,
                     hud::setup_crosshair,
+                    world::setup_chunk_manager,
+                    world::setup_chunk_rendering,
+                    world::setup_block_interaction,
+                    world::setup_world_time,
+                    world::setup_clouds,
+                    world::setup_weather,
+                    world::setup_precipitation,
+                    world::setup_particles,
+                    gui::command::setup_command_line,
                 )
                     .after(DataSet),
             );
@@ -87,6 +119,14 @@ impl<S: States> Plugin for GamePlugin<S> {
         {
             app.add_systems(OnEnter(self.state.clone()), music::setup_soundtrack);
         }
+        #[cfg(feature = "fast-skybox")]
+        {
+            app.add_systems(
+                Update,
+                menu::preview_next_sky.run_if(in_state(self.state.clone())),
+            )
+            .add_systems(Update, skybox::switch_sky_mode.in_set(GameplaySet));
+        }
         app.add_systems(
             Update,
             (
@@ -95,18 +135,52 @@ impl<S: States> Plugin for GamePlugin<S> {
                 gui::handle_mouse,
                 settings::change_fullscreen,
                 settings::update_settings,
+                player::save_player_data,
                 settings::save_window_position,
                 settings::save_window_size,
                 menu::render_pause_menu,
+                gui::command::update_command_line,
             )
                 .run_if(in_state(self.state.clone())),
         )
+        .add_systems(
+            Update,
+            (
+                world::update_chunk_center,
+                world::unload_chunks,
+                world::queue_chunk_tasks,
+                world::receive_chunk_tasks,
+                world::queue_light_tasks,
+                world::receive_light_tasks,
+                world::queue_loaded_chunks,
+                world::toggle_meshing_mode.in_set(GameplaySet),
+                world::toggle_ambient_occlusion.in_set(GameplaySet),
+                world::build_block_shapes.run_if(resource_exists::<world::BlockStateHandles>),
+                world::build_block_textures.run_if(resource_exists::<world::BlockTextureLoader>),
+                world::build_colormaps.run_if(resource_exists::<world::ColormapLoader>),
+                world::remesh_chunks.run_if(
+                    resource_exists::<world::ChunkMaterials>
+                        .and(resource_exists::<world::BiomeColormaps>),
+                ),
+                world::receive_mesh_tasks.run_if(resource_exists::<world::ChunkMaterials>),
+                world::sort_translucent_faces,
+                world::cull_sections,
+                world::update_targeted_block,
+                world::draw_targeted_block_outline,
+                world::update_crack_overlay,
+            )
+                .chain()
+                .run_if(in_state(self.state.clone())),
+        )
         .add_systems(
             Update,
             (
                 debug::update_fps_text,
                 debug::update_display_text,
                 debug::update_focus_text,
+                debug::update_targeted_block_text,
+                debug::update_section_count_text,
+                debug::update_world_time_text,
             )
                 .in_set(DebugSet),
         )
@@ -123,6 +197,60 @@ impl<S: States> Plugin for GamePlugin<S> {
                 hud::update_crosshair,
             )
                 .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (
+                controller::look_around,
+                controller::toggle_flight,
+                controller::update_camera_position,
+            )
+                .before(world::update_chunk_center)
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (
+                world::receive_time_updates,
+                world::run_time_command,
+                world::advance_world_time.in_set(GameplaySet),
+                world::receive_weather_updates,
+                world::run_weather_command,
+                world::advance_weather.in_set(GameplaySet),
+                world::update_daylight,
+                world::build_cloud_map.run_if(resource_exists::<world::CloudMapLoader>),
+                world::toggle_cloud_mode.in_set(GameplaySet),
+                world::update_clouds,
+                world::update_fog,
+                world::update_precipitation,
+            )
+                .chain()
+                .run_if(in_state(self.state.clone())),
+        )
+        .add_systems(
+            FixedUpdate,
+            controller::step_player_physics.in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (world::break_block, world::place_block)
+                .after(world::update_targeted_block)
+                .before(world::update_crack_overlay)
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            (
+                world::receive_particles,
+                world::emit_block_debris.after(world::break_block),
+                world::emit_sprint_dust,
+                world::emit_water_splashes,
+                world::emit_rain_drops,
+                world::update_particles,
+            )
+                .chain()
+                .after(controller::update_camera_position)
+                .in_set(GameplaySet),
         );
 
         #[cfg(feature = "audio")]
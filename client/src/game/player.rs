@@ -1,15 +1,27 @@
-// use valence_nbt::serde::CompoundSerializer;
 use std::error::Error;
 use std::io::prelude::*;
+use std::path::{Path, PathBuf};
 
 use bevy::prelude::*;
+use flate2::Compression;
 use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use serde::{Deserialize, Serialize};
-use valence_nbt::from_binary;
+use valence_nbt::serde::CompoundSerializer;
+use valence_nbt::{Compound, from_binary, to_binary};
+
+/// Path to player data shipped with the game, read until the player has data of their own.
+pub const PLAYER_DATA_PATH: &str = "./assets/playerdata/player.dat";
+/// Name of the file player data is saved to, next to settings.
+pub const PLAYER_DATA_FILE: &str = "player.dat";
+/// Value of `playerGameType` in creative mode.
+pub const CREATIVE_GAME_TYPE: i32 = 1;
+/// Value of `playerGameType` in spectator mode.
+pub const SPECTATOR_GAME_TYPE: i32 = 3;
 
 /// Slot in inventory's storage.
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug, Default)]
-#[serde(rename_all(deserialize = "PascalCase"))]
+#[serde(rename_all = "PascalCase")]
 pub struct Slot {
     pub slot: i8,
     #[serde(rename = "id")]
@@ -20,7 +32,7 @@ pub struct Slot {
 
 /// Contains player's abilities.
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
-#[serde(rename_all(deserialize = "camelCase"))]
+#[serde(rename_all = "camelCase")]
 pub struct Abilities {
     pub walk_speed: f32,
     pub fly_speed: f32,
@@ -48,7 +60,7 @@ impl Default for Abilities {
 
 /// Contains player's nbt data.
 #[derive(Deserialize, Serialize, Clone, Resource, Debug)]
-#[serde(rename_all(deserialize = "PascalCase"))]
+#[serde(rename_all = "PascalCase")]
 pub struct Player {
     /// Data version of player's NBT.
     pub data_version: i32,
@@ -90,15 +102,57 @@ impl Default for Player {
             food_exhaustion_level: 0.,
             food_level: 0,
             food_saturation_level: 0.,
-            player_game_type: 1,
+            player_game_type: CREATIVE_GAME_TYPE,
         }
     }
 }
 
-/// Setups player data from assets folder.
-pub fn setup_player_data(mut player: ResMut<Player>) {
-    // Not in assets because loaded by fs.
-    match read_player_data("./assets/playerdata/player.dat", &mut player) {
+impl Player {
+    /// Whether player is in creative mode.
+    pub fn is_creative(&self) -> bool {
+        self.player_game_type == CREATIVE_GAME_TYPE
+    }
+
+    /// Whether blocks are broken instantly and placed without using up items.
+    pub fn is_instabuild(&self) -> bool {
+        self.is_creative() || self.abilities.instabuild != 0
+    }
+
+    /// Whether player is in spectator mode, flying through blocks.
+    pub fn is_spectator(&self) -> bool {
+        self.player_game_type == SPECTATOR_GAME_TYPE
+    }
+
+    /// Whether player can start flying.
+    pub fn may_fly(&self) -> bool {
+        self.abilities.may_fly != 0 || self.is_spectator()
+    }
+
+    /// Whether player can break and place blocks.
+    pub fn may_build(&self) -> bool {
+        self.abilities.may_build != 0
+    }
+}
+
+/// Path player data is saved to, in the user's config folder so that shipped data is never
+/// overwritten.
+#[derive(Resource, Default, Debug)]
+pub struct PlayerDataPath {
+    pub path: PathBuf,
+}
+
+/// Fired when player data was changed and should be saved.
+#[derive(Event, Debug)]
+pub struct PlayerDataUpdated;
+
+/// Setups player data saved by the player, or the one from assets folder if there is none.
+pub fn setup_player_data(mut player: ResMut<Player>, data_path: Res<PlayerDataPath>) {
+    let file = if data_path.path.is_file() {
+        data_path.path.as_path()
+    } else {
+        Path::new(PLAYER_DATA_PATH)
+    };
+    match read_player_data(file, &mut player) {
         Ok(()) => info!("Loaded player data: {:#?}", player),
         Err(e) => error!("Couldn't retrieve player data: {}", e),
     }
@@ -107,9 +161,16 @@ pub fn setup_player_data(mut player: ResMut<Player>) {
 /// Reads player data from `file` path, loads into `player`.
 ///
 /// Returns error if couldn't retrieve data.
-pub fn read_player_data(file: &str, player: &mut Player) -> Result<(), Box<dyn Error>> {
+pub fn read_player_data(file: &Path, player: &mut Player) -> Result<(), Box<dyn Error>> {
     debug!("Path to player data: {:?}", std::fs::canonicalize(file));
 
+    let compound = read_compound(file)?;
+    *player = Player::deserialize(compound)?;
+    Ok(())
+}
+
+/// Reads gzipped NBT compound from `file` path.
+pub fn read_compound(file: impl AsRef<Path>) -> Result<Compound, Box<dyn Error>> {
     let player_data = std::fs::read(file)?;
 
     let mut player_data_decoder = GzDecoder::new(&player_data[..]);
@@ -117,10 +178,45 @@ pub fn read_player_data(file: &str, player: &mut Player) -> Result<(), Box<dyn E
 
     let _ = player_data_decoder.read_to_end(&mut nbt_binary_data)?;
 
-    let (compound, _) = from_binary::<String>(&mut nbt_binary_data.as_slice()).unwrap();
-    *player = Player::deserialize(compound)?;
+    let (compound, _) = from_binary::<String>(&mut nbt_binary_data.as_slice())?;
+    Ok(compound)
+}
+
+/// Writes `player` into `file` path.
+///
+/// Tags of the existing file that [`Player`] doesn't know about, such as position, are kept,
+/// taken from the shipped data if it's the first save. Returns error if couldn't save data.
+pub fn write_player_data(file: &Path, player: &Player) -> Result<(), Box<dyn Error>> {
+    let mut compound = read_compound(file)
+        .or_else(|_| read_compound(PLAYER_DATA_PATH))
+        .unwrap_or_default();
+    compound.extend(player.serialize(CompoundSerializer)?);
 
-    // let compound_2 = player.serialize(CompoundSerializer).unwrap();
-    // info!("{:#?}", compound_2);
+    let mut nbt_binary_data: Vec<u8> = vec![];
+    to_binary(&compound, &mut nbt_binary_data, "")?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&nbt_binary_data)?;
+    if let Some(folder) = file.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    std::fs::write(file, encoder.finish()?)?;
     Ok(())
 }
+
+/// Saves player data when it was updated.
+pub fn save_player_data(
+    mut updated_reader: EventReader<PlayerDataUpdated>,
+    player: Res<Player>,
+    data_path: Res<PlayerDataPath>,
+) {
+    if updated_reader.is_empty() {
+        return;
+    }
+    updated_reader.clear();
+
+    match write_player_data(&data_path.path, &player) {
+        Ok(()) => debug!("Saved player data."),
+        Err(e) => error!("Couldn't save player data: {}", e),
+    }
+}
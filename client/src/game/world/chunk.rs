@@ -0,0 +1,592 @@
+use std::mem;
+use std::sync::Arc;
+
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::{BlockPos, CHUNK_HEIGHT, Chunk, ChunkPos};
+use librecraft_shared::light::{
+    LightKind, LightStorage, light_chunk, propagate_borders, update_light,
+};
+use librecraft_shared::worldgen::WorldGenerator;
+
+use crate::settings::Settings;
+
+/// Minimum render distance in chunks.
+pub const MIN_RENDER_DISTANCE: u32 = 2;
+/// Maximum render distance in chunks.
+pub const MAX_RENDER_DISTANCE: u32 = 32;
+/// How many chunks can be generated at the same time.
+const MAX_CHUNK_TASKS: usize = 16;
+
+/// Marker of chunk column entity, parent of its section meshes.
+#[derive(Component, Debug)]
+pub struct ChunkEntity {
+    pub pos: ChunkPos,
+}
+
+/// Fired when chunk data was inserted into [`ChunkManager`] and light was spread across its
+/// borders.
+#[derive(Event, Debug)]
+pub struct ChunkLoaded {
+    pub pos: ChunkPos,
+}
+
+/// Keeps chunks around the camera loaded.
+///
+/// Chunks are generated on [`AsyncComputeTaskPool`], closest to the camera first, then light is
+/// spread across their borders there too. Chunks out of render distance are despawned, modified
+/// ones are kept in memory to be loaded again.
+#[derive(Resource)]
+pub struct ChunkManager {
+    generator: Arc<WorldGenerator>,
+    /// Chunk that contains camera, `None` until camera is found.
+    center: Option<ChunkPos>,
+    render_distance: u32,
+    chunks: HashMap<ChunkPos, Chunk>,
+    entities: HashMap<ChunkPos, Entity>,
+    tasks: HashMap<ChunkPos, Task<Chunk>>,
+    /// Chunks changed since generation.
+    modified: HashSet<ChunkPos>,
+    /// Modified chunks that were unloaded.
+    stored: HashMap<ChunkPos, Chunk>,
+    /// Loaded chunks whose light wasn't spread across their borders yet.
+    unlit: Vec<ChunkPos>,
+    /// Tasks spreading light across borders of chunks, with count of edits when they started.
+    light_tasks: HashMap<ChunkPos, (u64, Task<ChunkRegion>)>,
+    /// How many times blocks were set, light tasks that started before an edit are discarded.
+    edits: u64,
+}
+
+impl ChunkManager {
+    pub fn new(seed: u64, render_distance: u32) -> Self {
+        Self {
+            generator: Arc::new(WorldGenerator::new(seed)),
+            center: None,
+            render_distance: render_distance.clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE),
+            chunks: HashMap::default(),
+            entities: HashMap::default(),
+            tasks: HashMap::default(),
+            modified: HashSet::default(),
+            stored: HashMap::default(),
+            unlit: Vec::new(),
+            light_tasks: HashMap::default(),
+            edits: 0,
+        }
+    }
+
+    pub fn center(&self) -> Option<ChunkPos> {
+        self.center
+    }
+
+    pub fn render_distance(&self) -> u32 {
+        self.render_distance
+    }
+
+    pub fn chunk(&self, pos: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&pos)
+    }
+
+    pub fn entity(&self, pos: ChunkPos) -> Option<Entity> {
+        self.entities.get(&pos).copied()
+    }
+
+    /// Positions of all loaded chunks.
+    pub fn loaded_positions(&self) -> impl Iterator<Item = ChunkPos> + '_ {
+        self.chunks.keys().copied()
+    }
+
+    pub fn loaded_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Block at world position, `None` if its chunk is not loaded. Above and below the world
+    /// there is air, like in chunks.
+    pub fn block(&self, pos: BlockPos) -> Option<Block> {
+        let (x, y, z) = pos.local();
+        self.chunks
+            .get(&pos.chunk())
+            .map(|chunk| chunk.get(x, y, z))
+    }
+
+    /// Sets block at world position, updates light around it and returns previous block.
+    ///
+    /// Returns `None` if chunk is not loaded or `pos` is out of the world, in which case nothing
+    /// is changed. Light can change in neighbouring chunks too.
+    pub fn set_block(&mut self, pos: BlockPos, block: Block) -> Option<Block> {
+        let (x, y, z) = pos.local();
+        if !(0..CHUNK_HEIGHT).contains(&y) {
+            return None;
+        }
+        let chunk = self.chunks.get_mut(&pos.chunk())?;
+        self.modified.insert(pos.chunk());
+        self.edits += 1;
+        let old = chunk.set(x, y, z, block);
+        update_light(self, pos);
+        Some(old)
+    }
+
+    /// Inserts `chunk` as loaded, without entity and light across its borders.
+    #[cfg(test)]
+    pub fn insert_chunk(&mut self, chunk: Chunk) {
+        self.chunks.insert(chunk.pos, chunk);
+    }
+
+    /// Whether chunk at `pos` is within render distance of the center.
+    pub fn in_range(&self, pos: ChunkPos) -> bool {
+        self.center
+            .is_some_and(|center| distance_squared(center, pos) <= self.radius_squared())
+    }
+
+    fn radius_squared(&self) -> i32 {
+        let r = self.render_distance as i32;
+        r * r
+    }
+}
+
+impl LightStorage for ChunkManager {
+    fn block(&self, pos: BlockPos) -> Option<Block> {
+        ChunkManager::block(self, pos)
+    }
+
+    fn light(&self, kind: LightKind, pos: BlockPos) -> Option<u8> {
+        let (x, y, z) = pos.local();
+        self.chunks
+            .get(&pos.chunk())
+            .map(|chunk| chunk.light(kind, x, y, z))
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPos, value: u8) {
+        let (x, y, z) = pos.local();
+        if let Some(chunk) = self.chunks.get_mut(&pos.chunk()) {
+            chunk.set_light(kind, x, y, z, value);
+        }
+    }
+}
+
+/// Copies of a chunk and chunks around it, light spreads across its borders in them on
+/// [`AsyncComputeTaskPool`].
+///
+/// Light fades within 15 blocks, so it never crosses more than one chunk from the borders.
+struct ChunkRegion {
+    center: ChunkPos,
+    /// Chunks in rows from north-west, `None` if not loaded.
+    chunks: [Option<Chunk>; 9],
+}
+
+impl ChunkRegion {
+    fn copy(manager: &ChunkManager, center: ChunkPos) -> Self {
+        Self {
+            center,
+            chunks: std::array::from_fn(|i| {
+                let pos = ChunkPos::new(center.x + i as i32 % 3 - 1, center.z + i as i32 / 3 - 1);
+                manager.chunks.get(&pos).cloned()
+            }),
+        }
+    }
+
+    /// Whether regions around `a` and `b` share chunks.
+    fn overlap(a: ChunkPos, b: ChunkPos) -> bool {
+        (a.x - b.x).abs() <= 2 && (a.z - b.z).abs() <= 2
+    }
+
+    fn chunk(&self, pos: ChunkPos) -> Option<&Chunk> {
+        let (dx, dz) = (pos.x - self.center.x + 1, pos.z - self.center.z + 1);
+        if !(0..3).contains(&dx) || !(0..3).contains(&dz) {
+            return None;
+        }
+        self.chunks[(dz * 3 + dx) as usize].as_ref()
+    }
+
+    fn chunk_mut(&mut self, pos: ChunkPos) -> Option<&mut Chunk> {
+        let (dx, dz) = (pos.x - self.center.x + 1, pos.z - self.center.z + 1);
+        if !(0..3).contains(&dx) || !(0..3).contains(&dz) {
+            return None;
+        }
+        self.chunks[(dz * 3 + dx) as usize].as_mut()
+    }
+}
+
+impl LightStorage for ChunkRegion {
+    fn block(&self, pos: BlockPos) -> Option<Block> {
+        let (x, y, z) = pos.local();
+        self.chunk(pos.chunk()).map(|chunk| chunk.get(x, y, z))
+    }
+
+    fn light(&self, kind: LightKind, pos: BlockPos) -> Option<u8> {
+        let (x, y, z) = pos.local();
+        self.chunk(pos.chunk())
+            .map(|chunk| chunk.light(kind, x, y, z))
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPos, value: u8) {
+        let (x, y, z) = pos.local();
+        if let Some(chunk) = self.chunk_mut(pos.chunk()) {
+            chunk.set_light(kind, x, y, z, value);
+        }
+    }
+}
+
+fn distance_squared(a: ChunkPos, b: ChunkPos) -> i32 {
+    let (dx, dz) = (a.x - b.x, a.z - b.z);
+    dx * dx + dz * dz
+}
+
+/// Creates [`ChunkManager`] from settings.
+pub fn setup_chunk_manager(mut commands: Commands, settings: Res<Settings>) {
+    commands.insert_resource(ChunkManager::new(settings.seed, settings.render_distance));
+    info!(
+        "World seed: {}, render distance: {} chunks.",
+        settings.seed, settings.render_distance
+    );
+}
+
+/// Tracks chunk that contains the camera and render distance from settings.
+pub fn update_chunk_center(
+    mut manager: ResMut<ChunkManager>,
+    settings: Res<Settings>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    if settings.is_changed() {
+        let render_distance = settings
+            .render_distance
+            .clamp(MIN_RENDER_DISTANCE, MAX_RENDER_DISTANCE);
+        if manager.render_distance != render_distance {
+            manager.render_distance = render_distance;
+            debug!("Render distance: {} chunks.", render_distance);
+        }
+    }
+
+    let Ok(transform) = camera_q.single() else {
+        warn_once!("No camera detected. Chunks won't be loaded.");
+        return;
+    };
+
+    let translation = transform.translation();
+    let center = ChunkPos::from_block(translation.x.floor() as i32, translation.z.floor() as i32);
+    if manager.center != Some(center) {
+        manager.center = Some(center);
+    }
+}
+
+/// Spawns generation tasks for missing chunks in render distance, closest first.
+pub fn queue_chunk_tasks(mut manager: ResMut<ChunkManager>) {
+    let Some(center) = manager.center else {
+        return;
+    };
+    if manager.tasks.len() >= MAX_CHUNK_TASKS {
+        return;
+    }
+
+    let r = manager.render_distance as i32;
+    let mut missing: Vec<ChunkPos> = Vec::new();
+    for dz in -r..=r {
+        for dx in -r..=r {
+            let pos = ChunkPos::new(center.x + dx, center.z + dz);
+            if manager.in_range(pos)
+                && !manager.chunks.contains_key(&pos)
+                && !manager.tasks.contains_key(&pos)
+            {
+                missing.push(pos);
+            }
+        }
+    }
+    missing.sort_unstable_by_key(|pos| distance_squared(center, *pos));
+
+    let pool = AsyncComputeTaskPool::get();
+    let free_count = MAX_CHUNK_TASKS - manager.tasks.len();
+    for pos in missing.into_iter().take(free_count) {
+        let task = match manager.stored.remove(&pos) {
+            Some(chunk) => {
+                manager.modified.insert(pos);
+                pool.spawn(async move { chunk })
+            },
+            None => {
+                let generator = manager.generator.clone();
+                pool.spawn(async move {
+                    let mut chunk = generator.generate_chunk(pos);
+                    light_chunk(&mut chunk);
+                    chunk
+                })
+            },
+        };
+        manager.tasks.insert(pos, task);
+    }
+}
+
+/// Collects finished chunks and spawns their entities, light is spread across their borders
+/// later by [`queue_light_tasks`].
+pub fn receive_chunk_tasks(mut commands: Commands, mut manager: ResMut<ChunkManager>) {
+    let mut finished: Vec<Chunk> = Vec::new();
+    manager
+        .tasks
+        .retain(|_, task| match block_on(poll_once(task)) {
+            Some(chunk) => {
+                finished.push(chunk);
+                false
+            },
+            None => true,
+        });
+
+    for chunk in finished {
+        let pos = chunk.pos;
+        let entity = commands
+            .spawn((
+                ChunkEntity { pos },
+                Name::new(format!("Chunk {} {}", pos.x, pos.z)),
+                Transform::from_xyz(pos.min_block_x() as f32, 0., pos.min_block_z() as f32),
+                Visibility::default(),
+            ))
+            .id();
+        manager.chunks.insert(pos, chunk);
+        manager.entities.insert(pos, entity);
+        manager.unlit.push(pos);
+    }
+}
+
+/// Spawns tasks that spread light across borders of loaded chunks and their neighbours.
+///
+/// Regions of tasks don't overlap, so that each chunk is written back by one task at a time.
+pub fn queue_light_tasks(mut manager: ResMut<ChunkManager>) {
+    let pool = AsyncComputeTaskPool::get();
+    for pos in mem::take(&mut manager.unlit) {
+        if !manager.chunks.contains_key(&pos) {
+            continue;
+        }
+        if manager
+            .light_tasks
+            .keys()
+            .any(|task| ChunkRegion::overlap(*task, pos))
+        {
+            manager.unlit.push(pos);
+            continue;
+        }
+
+        let mut region = ChunkRegion::copy(&manager, pos);
+        let task = pool.spawn(async move {
+            propagate_borders(&mut region, pos);
+            region
+        });
+        let edits = manager.edits;
+        manager.light_tasks.insert(pos, (edits, task));
+    }
+}
+
+/// Writes light of finished light tasks back into loaded chunks.
+///
+/// If a block was set meanwhile, the result would undo its light, so the chunk is queued again.
+pub fn receive_light_tasks(
+    mut manager: ResMut<ChunkManager>,
+    mut loaded_writer: EventWriter<ChunkLoaded>,
+) {
+    let mut finished: Vec<(u64, ChunkRegion)> = Vec::new();
+    manager
+        .light_tasks
+        .retain(|_, (edits, task)| match block_on(poll_once(task)) {
+            Some(region) => {
+                finished.push((*edits, region));
+                false
+            },
+            None => true,
+        });
+
+    for (edits, region) in finished {
+        let pos = region.center;
+        if !manager.chunks.contains_key(&pos) {
+            continue;
+        }
+        if edits != manager.edits {
+            manager.unlit.push(pos);
+            continue;
+        }
+
+        for chunk in region.chunks.into_iter().flatten() {
+            if let Some(loaded) = manager.chunks.get_mut(&chunk.pos) {
+                *loaded = chunk;
+            }
+        }
+        loaded_writer.write(ChunkLoaded { pos });
+    }
+}
+
+/// Despawns chunks out of render distance and cancels their tasks.
+pub fn unload_chunks(mut commands: Commands, mut manager: ResMut<ChunkManager>) {
+    let Some(center) = manager.center else {
+        return;
+    };
+    // One chunk of slack so chunks on the border don't reload on every step.
+    let r = manager.render_distance as i32 + 1;
+    let out_of_range = |pos: &ChunkPos| distance_squared(center, *pos) > r * r;
+
+    // Dropping a task cancels it.
+    manager.tasks.retain(|pos, _| !out_of_range(pos));
+    manager.light_tasks.retain(|pos, _| !out_of_range(pos));
+    manager.unlit.retain(|pos| !out_of_range(pos));
+
+    let unloaded: Vec<ChunkPos> = manager
+        .chunks
+        .keys()
+        .filter(|pos| out_of_range(pos))
+        .copied()
+        .collect();
+
+    for pos in unloaded {
+        if let Some(entity) = manager.entities.remove(&pos) {
+            commands.entity(entity).despawn();
+        }
+        if let Some(chunk) = manager.chunks.remove(&pos)
+            && manager.modified.remove(&pos)
+        {
+            manager.stored.insert(pos, chunk);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::tasks::TaskPool;
+
+    use super::*;
+
+    /// Chunks within render distance 2 of a chunk: 5 in its column and row, and 8 around it.
+    const LOADED_COUNT: usize = 13;
+
+    fn world(render_distance: u32) -> World {
+        AsyncComputeTaskPool::get_or_init(TaskPool::default);
+        let mut world = World::new();
+        world.insert_resource(Settings {
+            render_distance,
+            ..default()
+        });
+        world.insert_resource(ChunkManager::new(0, render_distance));
+        world.init_resource::<Events<ChunkLoaded>>();
+        world.spawn((Camera3d::default(), GlobalTransform::default()));
+        world
+    }
+
+    fn move_camera(world: &mut World, x: f32, z: f32) {
+        let mut camera_q = world.query_filtered::<&mut GlobalTransform, With<Camera3d>>();
+        *camera_q.single_mut(world).unwrap() = GlobalTransform::from_xyz(x, 100., z);
+    }
+
+    /// Runs chunk systems until chunks in render distance are loaded and lit.
+    fn load(world: &mut World) {
+        for _ in 0..10_000 {
+            world.run_system_once(update_chunk_center).unwrap();
+            world.run_system_once(unload_chunks).unwrap();
+            world.run_system_once(queue_chunk_tasks).unwrap();
+            world.run_system_once(receive_chunk_tasks).unwrap();
+            world.run_system_once(queue_light_tasks).unwrap();
+            world.run_system_once(receive_light_tasks).unwrap();
+
+            let manager = world.resource::<ChunkManager>();
+            if manager.tasks.is_empty()
+                && manager.light_tasks.is_empty()
+                && manager.unlit.is_empty()
+                && manager.loaded_count() > 0
+            {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("Chunks weren't loaded.");
+    }
+
+    #[test]
+    fn render_distance_is_clamped() {
+        assert_eq!(
+            ChunkManager::new(0, 0).render_distance(),
+            MIN_RENDER_DISTANCE
+        );
+        assert_eq!(
+            ChunkManager::new(0, 100).render_distance(),
+            MAX_RENDER_DISTANCE
+        );
+
+        let mut world = world(MIN_RENDER_DISTANCE);
+        world.resource_mut::<Settings>().render_distance = 1000;
+        world.run_system_once(update_chunk_center).unwrap();
+        assert_eq!(
+            world.resource::<ChunkManager>().render_distance(),
+            MAX_RENDER_DISTANCE
+        );
+    }
+
+    #[test]
+    fn chunks_in_render_distance_are_loaded() {
+        let mut world = world(MIN_RENDER_DISTANCE);
+        load(&mut world);
+
+        let manager = world.resource::<ChunkManager>();
+        assert_eq!(manager.center(), Some(ChunkPos::new(0, 0)));
+        assert_eq!(manager.loaded_count(), LOADED_COUNT);
+        assert!(
+            manager
+                .loaded_positions()
+                .all(|pos| manager.in_range(pos) && manager.entity(pos).is_some())
+        );
+        assert_eq!(world.resource::<Events<ChunkLoaded>>().len(), LOADED_COUNT);
+    }
+
+    #[test]
+    fn far_chunks_are_unloaded_and_modified_ones_kept() {
+        let mut world = world(MIN_RENDER_DISTANCE);
+        load(&mut world);
+        let pos = BlockPos::new(1, CHUNK_HEIGHT - 1, 1);
+        let mut manager = world.resource_mut::<ChunkManager>();
+        assert_eq!(manager.set_block(pos, Block::Stone), Some(Block::Air));
+        let entity = manager.entity(pos.chunk()).unwrap();
+
+        move_camera(&mut world, 160., 0.);
+        load(&mut world);
+        let manager = world.resource::<ChunkManager>();
+        assert_eq!(manager.center(), Some(ChunkPos::new(10, 0)));
+        assert_eq!(manager.loaded_count(), LOADED_COUNT);
+        assert!(manager.block(pos).is_none());
+        assert!(world.get_entity(entity).is_err());
+
+        move_camera(&mut world, 0., 0.);
+        load(&mut world);
+        assert_eq!(
+            world.resource::<ChunkManager>().block(pos),
+            Some(Block::Stone)
+        );
+    }
+
+    #[test]
+    fn light_is_spread_again_after_edit() {
+        let mut world = world(MIN_RENDER_DISTANCE);
+        load(&mut world);
+        let center = ChunkPos::new(0, 0);
+        world.resource_mut::<ChunkManager>().unlit.push(center);
+        world.run_system_once(queue_light_tasks).unwrap();
+
+        // The task copied chunks before the edit, its result is discarded.
+        let pos = BlockPos::new(1, CHUNK_HEIGHT - 1, 1);
+        world
+            .resource_mut::<ChunkManager>()
+            .set_block(pos, Block::Stone);
+        while !world.resource::<ChunkManager>().light_tasks.is_empty() {
+            world.run_system_once(receive_light_tasks).unwrap();
+            thread::sleep(Duration::from_millis(1));
+        }
+        let manager = world.resource::<ChunkManager>();
+        assert_eq!(manager.unlit, vec![center]);
+        assert_eq!(manager.block(pos), Some(Block::Stone));
+
+        load(&mut world);
+        assert_eq!(
+            world.resource::<ChunkManager>().block(pos),
+            Some(Block::Stone)
+        );
+    }
+}
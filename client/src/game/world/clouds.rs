@@ -0,0 +1,410 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_resource::TextureFormat;
+use librecraft_shared::chunk::SECTION_SIZE;
+use librecraft_shared::noise::Perlin;
+
+use crate::game::world::time::WorldTime;
+use crate::settings::{CloudMode, Settings, SettingsUpdated};
+
+/// Path of clouds texture in [`AssetServer`], opaque pixels are clouds.
+pub const CLOUDS_PATH: &str = "clouds.png";
+
+/// Size of cloud map generated from noise, in cells.
+const NOISE_MAP_SIZE: usize = 256;
+/// Noise above this value is a cloud, so that about a third of the sky is covered.
+const NOISE_THRESHOLD: f64 = 0.15;
+/// Size of a cloud cell in blocks.
+const CELL_SIZE: f32 = 12.;
+/// Thickness of fancy clouds in blocks.
+const CLOUD_THICKNESS: f32 = 4.;
+/// Clouds drift towards +X by this many blocks per tick, the same as in Minecraft.
+const CLOUD_SPEED: f64 = 0.03;
+/// Opacity of clouds close to the player.
+const CLOUD_ALPHA: f32 = 0.8;
+/// Part of cloud distance after which clouds start to fade out.
+const FADE_START: f32 = 0.6;
+
+/// Brightness of faces by direction they look to, so that fancy clouds look solid.
+const TOP_SHADE: f32 = 1.;
+const BOTTOM_SHADE: f32 = 0.7;
+const X_SHADE: f32 = 0.9;
+const Z_SHADE: f32 = 0.8;
+
+const DAY_CLOUD_COLOR: Srgba = Srgba::WHITE;
+const NIGHT_CLOUD_COLOR: Srgba = Srgba::rgb(0.07, 0.07, 0.1);
+
+/// Which cells of the sky have clouds, repeated in both directions.
+#[derive(Resource, Clone, Debug)]
+pub struct CloudMap {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+impl CloudMap {
+    /// Reads clouds from `image`, where pixels with alpha of at least a half are clouds.
+    /// `None` if it's empty or has unsupported format.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let converted;
+        let image = if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb {
+            image
+        } else {
+            converted = image.convert(TextureFormat::Rgba8UnormSrgb)?;
+            &converted
+        };
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let data = image.data.as_ref()?;
+        if width == 0 || height == 0 || data.len() < width * height * 4 {
+            return None;
+        }
+
+        let cells = data
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|p| p[3] >= 128)
+            .collect();
+        Some(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Generates clouds from Perlin noise with `seed`.
+    pub fn from_noise(seed: u64) -> Self {
+        let perlin = Perlin::new(seed);
+        let cells = (0..NOISE_MAP_SIZE * NOISE_MAP_SIZE)
+            .map(|i| {
+                let (x, z) = ((i % NOISE_MAP_SIZE) as f64, (i / NOISE_MAP_SIZE) as f64);
+                let noise = perlin.noise2(x / 16., z / 16.) + perlin.noise2(x / 5., z / 5.) * 0.4;
+                noise > NOISE_THRESHOLD
+            })
+            .collect();
+        Self {
+            width: NOISE_MAP_SIZE,
+            height: NOISE_MAP_SIZE,
+            cells,
+        }
+    }
+
+    /// Whether cell at `x` and `z` has a cloud, the map repeats outside of its size.
+    pub fn is_cloud(&self, x: i32, z: i32) -> bool {
+        let x = x.rem_euclid(self.width as i32) as usize;
+        let z = z.rem_euclid(self.height as i32) as usize;
+        self.cells[z * self.width + x]
+    }
+
+    /// Size of the map in blocks, clouds repeat after scrolling this far.
+    fn period(&self) -> f64 {
+        self.width as f64 * CELL_SIZE as f64
+    }
+}
+
+/// Clouds texture that is being loaded, before it becomes [`CloudMap`].
+#[derive(Resource, Debug)]
+pub struct CloudMapLoader {
+    texture: Handle<Image>,
+}
+
+/// Marker of the cloud layer mesh.
+#[derive(Component)]
+pub struct Clouds;
+
+/// Cell and settings the cloud mesh was built for, it's rebuilt when they change.
+#[derive(Component, Default, PartialEq, Debug)]
+pub struct CloudMesh {
+    center: Option<IVec2>,
+    mode: CloudMode,
+    distance: u32,
+}
+
+/// Opacity of a cloud vertex `distance` blocks away from the player, clouds fade out
+/// towards `max_distance`.
+fn cloud_alpha(distance: f32, max_distance: f32) -> f32 {
+    let fade = (distance / max_distance - FADE_START) / (1. - FADE_START);
+    CLOUD_ALPHA * (1. - fade.clamp(0., 1.))
+}
+
+/// Builds clouds of `map` around `center` cell within `distance` blocks, in blocks from the
+/// corner of the center cell.
+pub fn build_cloud_mesh(map: &CloudMap, mode: CloudMode, center: IVec2, distance: f32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    // Player is somewhere in the center cell.
+    let player = Vec2::splat(CELL_SIZE / 2.);
+    let mut quad = |corners: [Vec3; 4], normal: Vec3, shade: f32| {
+        let start = positions.len() as u32;
+        for corner in corners {
+            let alpha = cloud_alpha(corner.xz().distance(player), distance);
+            positions.push(corner.into());
+            normals.push(normal.into());
+            colors.push([shade, shade, shade, alpha]);
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+    };
+
+    let radius = (distance / CELL_SIZE).ceil() as i32;
+    for dz in -radius..=radius {
+        for dx in -radius..=radius {
+            let (x, z) = (center.x + dx, center.y + dz);
+            let (x0, z0) = (dx as f32 * CELL_SIZE, dz as f32 * CELL_SIZE);
+            let (x1, z1) = (x0 + CELL_SIZE, z0 + CELL_SIZE);
+            let nearest = player.clamp(Vec2::new(x0, z0), Vec2::new(x1, z1));
+            if !map.is_cloud(x, z) || nearest.distance(player) > distance {
+                continue;
+            }
+
+            let bottom = [
+                Vec3::new(x0, 0., z0),
+                Vec3::new(x1, 0., z0),
+                Vec3::new(x1, 0., z1),
+                Vec3::new(x0, 0., z1),
+            ];
+            if mode == CloudMode::Fast {
+                quad(bottom, Vec3::NEG_Y, TOP_SHADE);
+                continue;
+            }
+
+            let top = bottom.map(|corner| corner.with_y(CLOUD_THICKNESS));
+            quad(bottom, Vec3::NEG_Y, BOTTOM_SHADE);
+            quad([top[0], top[3], top[2], top[1]], Vec3::Y, TOP_SHADE);
+            // Sides between two clouds are hidden.
+            if !map.is_cloud(x - 1, z) {
+                quad([bottom[0], bottom[3], top[3], top[0]], Vec3::NEG_X, X_SHADE);
+            }
+            if !map.is_cloud(x + 1, z) {
+                quad([bottom[2], bottom[1], top[1], top[2]], Vec3::X, X_SHADE);
+            }
+            if !map.is_cloud(x, z - 1) {
+                quad([bottom[1], bottom[0], top[0], top[1]], Vec3::NEG_Z, Z_SHADE);
+            }
+            if !map.is_cloud(x, z + 1) {
+                quad([bottom[3], bottom[2], top[2], top[3]], Vec3::Z, Z_SHADE);
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Starts loading clouds texture and spawns hidden cloud layer.
+pub fn setup_clouds(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(CloudMapLoader {
+        texture: assets.load(CLOUDS_PATH),
+    });
+    commands.spawn((
+        Clouds,
+        CloudMesh::default(),
+        Mesh3d(meshes.add(Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        ))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            unlit: true,
+            alpha_mode: AlphaMode::Blend,
+            // Clouds are seen from below, above and inside.
+            cull_mode: None,
+            double_sided: true,
+            // Clouds are further than render distance, they fade out by themselves.
+            fog_enabled: false,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        NotShadowCaster,
+        NotShadowReceiver,
+    ));
+}
+
+/// Reads clouds texture once it's loaded, clouds are generated from noise if it's missing.
+pub fn build_cloud_map(
+    mut commands: Commands,
+    loader: Res<CloudMapLoader>,
+    assets: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+    settings: Res<Settings>,
+) {
+    let state = assets.load_state(loader.texture.id());
+    if !state.is_loaded() && !state.is_failed() {
+        return;
+    }
+
+    let map = images
+        .get(&loader.texture)
+        .and_then(CloudMap::from_image)
+        .unwrap_or_else(|| {
+            info!("Clouds texture {CLOUDS_PATH} can't be loaded, clouds are made from noise.");
+            CloudMap::from_noise(settings.seed)
+        });
+    commands.insert_resource(map);
+    commands.remove_resource::<CloudMapLoader>();
+}
+
+/// Switches cloud mode between off, fast and fancy by pressing F9.
+pub fn toggle_cloud_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut settings_writer: EventWriter<SettingsUpdated>,
+) {
+    if keys.just_pressed(KeyCode::F9) {
+        settings.clouds = settings.clouds.next();
+        debug!("Clouds: {:?}", settings.clouds);
+
+        settings_writer.write(SettingsUpdated {
+            settings: settings.clone(),
+        });
+    }
+}
+
+/// Keeps cloud layer above the player, scrolls it with world time and colors it by time
+/// of day.
+pub fn update_clouds(
+    settings: Res<Settings>,
+    world_time: Res<WorldTime>,
+    map: Option<Res<CloudMap>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+    mut clouds_q: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut CloudMesh,
+            &Mesh3d,
+            &MeshMaterial3d<StandardMaterial>,
+        ),
+        With<Clouds>,
+    >,
+) {
+    let Ok((mut transform, mut visibility, mut built, mesh, material)) = clouds_q.single_mut()
+    else {
+        return;
+    };
+    let (Some(map), Ok(camera)) = (map, camera_q.single()) else {
+        return;
+    };
+
+    let hidden = settings.clouds == CloudMode::Off;
+    visibility.set_if_neq(if hidden {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    });
+    if hidden {
+        return;
+    }
+
+    // Scroll is wrapped by the size of the map, so that it stays precise in old worlds.
+    let ticks = world_time.age as f64 + world_time.partial_tick;
+    let scroll = (ticks * CLOUD_SPEED).rem_euclid(map.period()) as f32;
+    let position = camera.translation();
+    let center = IVec2::new(
+        ((position.x - scroll) / CELL_SIZE).floor() as i32,
+        (position.z / CELL_SIZE).floor() as i32,
+    );
+
+    let wanted = CloudMesh {
+        center: Some(center),
+        mode: settings.clouds,
+        distance: settings.cloud_distance,
+    };
+    if *built != wanted || map.is_changed() {
+        let distance = (settings.cloud_distance as usize * SECTION_SIZE) as f32;
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            *mesh = build_cloud_mesh(&map, settings.clouds, center, distance);
+        }
+        *built = wanted;
+    }
+    transform.translation = Vec3::new(
+        center.x as f32 * CELL_SIZE + scroll,
+        settings.cloud_height,
+        center.y as f32 * CELL_SIZE,
+    );
+
+    if !world_time.is_changed() {
+        return;
+    }
+    if let Some(material) = materials.get_mut(&material.0) {
+        let color = NIGHT_CLOUD_COLOR.mix(&DAY_CLOUD_COLOR, world_time.sky_brightness());
+        material.base_color = color.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::mesh::VertexAttributeValues;
+
+    use super::*;
+
+    fn map(rows: &[&str]) -> CloudMap {
+        CloudMap {
+            width: rows[0].len(),
+            height: rows.len(),
+            cells: rows.concat().chars().map(|c| c == '#').collect(),
+        }
+    }
+
+    fn alphas(mesh: &Mesh) -> Vec<f32> {
+        match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) => colors.iter().map(|c| c[3]).collect(),
+            _ => panic!("Cloud mesh has no colors"),
+        }
+    }
+
+    #[test]
+    fn cloud_map_repeats() {
+        let map = map(&["#.", ".."]);
+        assert!(map.is_cloud(0, 0));
+        assert!(map.is_cloud(2, -2));
+        assert!(!map.is_cloud(1, 0));
+
+        let noise = CloudMap::from_noise(1);
+        let coverage = noise.cells.iter().filter(|c| **c).count() as f32 / noise.cells.len() as f32;
+        assert!((0.1..0.6).contains(&coverage), "coverage is {coverage}");
+    }
+
+    #[test]
+    fn fancy_clouds_hide_sides_between_cells() {
+        // Two clouds next to each other along X, in a map of clear sky.
+        let map = map(&["##....", "......", "......"]);
+        let distance = CELL_SIZE * 2.;
+        let flat = build_cloud_mesh(&map, CloudMode::Fast, IVec2::ZERO, distance);
+        let fancy = build_cloud_mesh(&map, CloudMode::Fancy, IVec2::ZERO, distance);
+
+        assert_eq!(flat.count_vertices(), 2 * 4);
+        // Top, bottom and three sides of each cloud.
+        assert_eq!(fancy.count_vertices(), 2 * 5 * 4);
+    }
+
+    #[test]
+    fn clouds_fade_with_distance() {
+        assert_eq!(cloud_alpha(0., 100.), CLOUD_ALPHA);
+        assert_eq!(cloud_alpha(100., 100.), 0.);
+        assert!(cloud_alpha(80., 100.) < CLOUD_ALPHA);
+
+        let map = map(&["#"]);
+        let mesh = build_cloud_mesh(&map, CloudMode::Fast, IVec2::ZERO, CELL_SIZE * 4.);
+        let alphas = alphas(&mesh);
+        assert!(alphas.contains(&CLOUD_ALPHA));
+        assert!(alphas.iter().any(|a| *a < CLOUD_ALPHA));
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::pbr::{DistanceFog, FogFalloff};
+use bevy::prelude::*;
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::{BlockPos, SECTION_SIZE};
+
+use crate::game::player::Player;
+use crate::game::world::chunk::ChunkManager;
+use crate::game::world::time::{SKYBOX_BRIGHTNESS, WorldTime};
+use crate::settings::Settings;
+
+/// Name of the nether dimension in player data.
+pub const NETHER_DIMENSION: &str = "minecraft:the_nether";
+/// Name of lava block. Lava isn't registered yet, camera in it will have lava fog once it is.
+const LAVA_BLOCK: &str = "minecraft:lava";
+
+/// Color of the horizon of procedural sky during the day and at night.
+pub const DAY_HORIZON: Srgba = Srgba::rgb(0.75, 0.85, 1.);
+pub const NIGHT_HORIZON: Srgba = Srgba::rgb(0.02, 0.02, 0.05);
+const WATER_FOG: Srgba = Srgba::rgb(0.02, 0.15, 0.4);
+const LAVA_FOG: Srgba = Srgba::rgb(0.6, 0.1, 0.);
+const NETHER_FOG: Srgba = Srgba::rgb(0.2, 0.03, 0.03);
+
+/// Density of exponential fog under water, so that about 50 blocks are visible.
+const WATER_FOG_DENSITY: f32 = 0.06;
+/// Distance seen through lava, in blocks.
+const LAVA_FOG_END: f32 = 2.;
+/// The nether is foggy at half of render distance, but no further than this.
+const NETHER_FOG_END: f32 = 96.;
+
+/// Color of the horizon of the skybox image, `None` while procedural sky is drawn.
+#[derive(Resource, Default, Debug)]
+pub struct SkyHorizon {
+    pub color: Option<Srgba>,
+}
+
+/// Color of the horizon of the sky that is drawn, at the time of day. Skybox image is dimmed the
+/// same way as the skybox.
+pub fn sky_horizon(image: Option<Srgba>, world_time: &WorldTime) -> Srgba {
+    match image {
+        Some(color) => {
+            let dim = world_time.skybox_brightness() / SKYBOX_BRIGHTNESS;
+            Srgba::rgb(color.red * dim, color.green * dim, color.blue * dim)
+        },
+        None => NIGHT_HORIZON.mix(&DAY_HORIZON, world_time.sky_brightness()),
+    }
+}
+
+/// What the camera looks through, each has its own fog.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FogEnvironment {
+    Air,
+    Water,
+    Lava,
+    Nether,
+}
+
+impl FogEnvironment {
+    /// Environment of the camera in `block` in `dimension`, liquids take precedence.
+    pub fn of(block: Option<Block>, dimension: &str) -> Self {
+        match block {
+            Some(Block::Water) => Self::Water,
+            Some(block) if block.name() == LAVA_BLOCK => Self::Lava,
+            _ if dimension == NETHER_DIMENSION => Self::Nether,
+            _ => Self::Air,
+        }
+    }
+}
+
+/// Fog in `environment`, `None` if there should be no fog.
+///
+/// Fog in the air ends at render distance, so that chunks fade in instead of popping up, and
+/// has the color of the sky's `horizon`. It can be turned off in settings, while fog in liquids
+/// is always on and darkens with `sky_brightness`.
+pub fn distance_fog(
+    environment: FogEnvironment,
+    settings: &Settings,
+    sky_brightness: f32,
+    horizon: Srgba,
+) -> Option<DistanceFog> {
+    let render_distance = (settings.render_distance as usize * SECTION_SIZE) as f32;
+    let (color, falloff) = match environment {
+        FogEnvironment::Air if !settings.fog => return None,
+        FogEnvironment::Air => (horizon, FogFalloff::Linear {
+            start: render_distance * settings.fog_start.clamp(0., 1.),
+            end: render_distance,
+        }),
+        FogEnvironment::Water => (
+            WATER_FOG * sky_brightness.max(0.2),
+            FogFalloff::Exponential {
+                density: WATER_FOG_DENSITY,
+            },
+        ),
+        FogEnvironment::Lava => (LAVA_FOG, FogFalloff::Linear {
+            start: 0.,
+            end: LAVA_FOG_END,
+        }),
+        FogEnvironment::Nether => {
+            let end = (render_distance / 2.).min(NETHER_FOG_END);
+            (NETHER_FOG, FogFalloff::Linear {
+                start: end * 0.05,
+                end,
+            })
+        },
+    };
+
+    Some(DistanceFog {
+        color: color.into(),
+        falloff,
+        ..default()
+    })
+}
+
+/// Whether fog falls off the same way, only falloffs made by [`distance_fog`] are compared.
+fn same_falloff(a: &FogFalloff, b: &FogFalloff) -> bool {
+    match (a, b) {
+        (
+            FogFalloff::Linear { start, end },
+            FogFalloff::Linear {
+                start: other_start,
+                end: other_end,
+            },
+        ) => start == other_start && end == other_end,
+        (FogFalloff::Exponential { density }, FogFalloff::Exponential { density: other }) => {
+            density == other
+        },
+        _ => false,
+    }
+}
+
+/// Puts fog on the camera by where it is, render distance, time of day and the sky. Fog is
+/// only written when it changes.
+pub fn update_fog(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    player: Res<Player>,
+    world_time: Res<WorldTime>,
+    manager: Res<ChunkManager>,
+    horizon: Option<Res<SkyHorizon>>,
+    mut camera_q: Query<(Entity, &GlobalTransform, Option<&mut DistanceFog>), With<Camera3d>>,
+) {
+    let Ok((camera, transform, current)) = camera_q.single_mut() else {
+        return;
+    };
+
+    let eye = transform.translation().floor().as_ivec3();
+    let block = manager.block(BlockPos::new(eye.x, eye.y, eye.z));
+    let environment = FogEnvironment::of(block, &player.dimension);
+    let horizon = sky_horizon(horizon.and_then(|horizon| horizon.color), &world_time);
+    match (
+        distance_fog(environment, &settings, world_time.sky_brightness(), horizon),
+        current,
+    ) {
+        (Some(fog), Some(mut current)) => {
+            if current.color != fog.color || !same_falloff(&current.falloff, &fog.falloff) {
+                current.color = fog.color;
+                current.falloff = fog.falloff;
+            }
+        },
+        (Some(fog), None) => {
+            commands.entity(camera).insert(fog);
+        },
+        (None, Some(_)) => {
+            commands.entity(camera).remove::<DistanceFog>();
+        },
+        (None, None) => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn liquids_have_their_own_fog() {
+        assert_eq!(
+            FogEnvironment::of(Some(Block::Air), ""),
+            FogEnvironment::Air
+        );
+        assert_eq!(
+            FogEnvironment::of(Some(Block::Water), NETHER_DIMENSION),
+            FogEnvironment::Water
+        );
+        assert_eq!(
+            FogEnvironment::of(None, NETHER_DIMENSION),
+            FogEnvironment::Nether
+        );
+    }
+
+    #[test]
+    fn fog_ends_at_render_distance() {
+        let mut settings = Settings {
+            render_distance: 8,
+            fog: true,
+            fog_start: 0.75,
+            ..default()
+        };
+        let fog = distance_fog(FogEnvironment::Air, &settings, 1., DAY_HORIZON).unwrap();
+        assert!(matches!(fog.falloff, FogFalloff::Linear { start, end }
+            if start == 96. && end == 128.));
+        assert_eq!(fog.color, Color::from(DAY_HORIZON));
+        assert!(same_falloff(
+            &fog.falloff,
+            &distance_fog(FogEnvironment::Air, &settings, 0., NIGHT_HORIZON)
+                .unwrap()
+                .falloff
+        ));
+
+        settings.fog = false;
+        assert!(distance_fog(FogEnvironment::Air, &settings, 1., DAY_HORIZON).is_none());
+        assert!(distance_fog(FogEnvironment::Water, &settings, 1., DAY_HORIZON).is_some());
+    }
+
+    #[test]
+    fn fog_takes_horizon_of_drawn_sky() {
+        let noon = WorldTime {
+            day_time: librecraft_shared::time::NOON,
+            ..default()
+        };
+        let image = Srgba::rgb(0.9, 0.5, 0.2);
+        assert_eq!(sky_horizon(Some(image), &noon), image);
+        assert_eq!(sky_horizon(None, &noon), DAY_HORIZON);
+
+        let night = WorldTime {
+            day_time: librecraft_shared::time::MIDNIGHT,
+            ..default()
+        };
+        assert!(sky_horizon(Some(image), &night).red < image.red);
+    }
+}
@@ -0,0 +1,377 @@
+use bevy::pbr::NotShadowCaster;
+use bevy::prelude::*;
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::{BlockPos, ChunkPos};
+use librecraft_shared::physics::{Aabb, block_collision_boxes};
+
+use super::chunk::ChunkManager;
+use super::render::RemeshQueue;
+use super::target::TargetedBlock;
+use crate::game::controller::PlayerBody;
+use crate::game::player::Player;
+use crate::gui::hud::Hotbar;
+
+/// Number of crack textures, from barely cracked to almost broken.
+pub const DESTROY_STAGES: usize = 10;
+/// Seconds it takes to break a block per point of its hardness, with bare hands.
+const BREAK_SECONDS_PER_HARDNESS: f32 = 1.5;
+/// Crack overlay is drawn slightly bigger than the block, so that it isn't hidden by its faces.
+const CRACK_OVERLAY_SIZE: f32 = 1.004;
+
+/// Path of crack texture of `stage` in [`AssetServer`].
+pub fn destroy_stage_path(stage: usize) -> String {
+    format!("destroy_stage_{stage}.png")
+}
+
+/// Progress of breaking a block in survival.
+#[derive(Resource, Default, Debug)]
+pub struct BlockBreaking {
+    /// Block being broken, `None` if nothing is.
+    pub pos: Option<BlockPos>,
+    /// Progress from 0 to 1, block breaks when it's reached.
+    pub progress: f32,
+}
+
+impl BlockBreaking {
+    /// Crack texture shown on the block, `None` if nothing is being broken.
+    pub fn stage(&self) -> Option<usize> {
+        self.pos
+            .map(|_| ((self.progress * DESTROY_STAGES as f32) as usize).min(DESTROY_STAGES - 1))
+    }
+
+    fn stop(&mut self) {
+        self.pos = None;
+        self.progress = 0.;
+    }
+}
+
+/// Seconds it takes to break `block` in survival, `None` if it can't be broken.
+pub fn break_duration(block: Block) -> Option<f32> {
+    block.hardness().map(|h| h * BREAK_SECONDS_PER_HARDNESS)
+}
+
+/// Whether placed block can take the place of `block`.
+pub fn is_replaceable(block: Block) -> bool {
+    block.is_air() || block.is_liquid()
+}
+
+/// Fired when the player breaks `block` at `pos`.
+#[derive(Event, Debug)]
+pub struct BlockBroken {
+    pub pos: BlockPos,
+    pub block: Block,
+}
+
+/// Whether `block` placed at `pos` would be inside of `player`'s box.
+pub fn intersects_player(block: Block, pos: BlockPos, player: &Aabb) -> bool {
+    let corner = [pos.x as f64, pos.y as f64, pos.z as f64];
+    block_collision_boxes(block)
+        .iter()
+        .any(|shape| shape.offset(corner).intersects(player))
+}
+
+/// Marker of the cube drawn over block that is being broken.
+#[derive(Component)]
+pub struct CrackOverlay;
+
+/// Materials of crack overlay, one per destroy stage.
+#[derive(Resource)]
+pub struct CrackMaterials(Vec<Handle<StandardMaterial>>);
+
+/// Setups block breaking state and spawns hidden crack overlay.
+pub fn setup_block_interaction(
+    mut commands: Commands,
+    assets: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let stages: Vec<_> = (0..DESTROY_STAGES)
+        .map(|stage| {
+            materials.add(StandardMaterial {
+                base_color_texture: Some(assets.load(destroy_stage_path(stage))),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })
+        })
+        .collect();
+
+    commands.spawn((
+        CrackOverlay,
+        Mesh3d(meshes.add(Cuboid::from_length(CRACK_OVERLAY_SIZE))),
+        MeshMaterial3d(stages[0].clone()),
+        NotShadowCaster,
+        Transform::default(),
+        Visibility::Hidden,
+    ));
+    commands.insert_resource(CrackMaterials(stages));
+    commands.init_resource::<BlockBreaking>();
+}
+
+/// Sets block at `pos` and queues chunks whose meshes or light could change.
+///
+/// Returns previous block, `None` if chunk is not loaded.
+fn set_block(
+    manager: &mut ChunkManager,
+    queue: &mut RemeshQueue,
+    pos: BlockPos,
+    block: Block,
+) -> Option<Block> {
+    let old = manager.set_block(pos, block)?;
+
+    // Light spreads up to 15 blocks, so it can change in any chunk around.
+    let center = pos.chunk();
+    for dx in -1..=1 {
+        for dz in -1..=1 {
+            let pos = ChunkPos::new(center.x + dx, center.z + dz);
+            if manager.chunk(pos).is_some() {
+                queue.insert(pos);
+            }
+        }
+    }
+    Some(old)
+}
+
+/// Breaks targeted block by holding left mouse button.
+///
+/// In creative blocks are broken at once on click, in survival it takes time depending on
+/// their hardness.
+pub fn break_block(
+    mouse: Res<ButtonInput<MouseButton>>,
+    time: Res<Time>,
+    targeted: Res<TargetedBlock>,
+    player: Res<Player>,
+    mut breaking: ResMut<BlockBreaking>,
+    mut manager: ResMut<ChunkManager>,
+    mut queue: ResMut<RemeshQueue>,
+    mut broken_writer: EventWriter<BlockBroken>,
+) {
+    let hit = targeted.0.filter(|_| player.may_build());
+    let (Some(hit), true) = (hit, mouse.pressed(MouseButton::Left)) else {
+        if breaking.pos.is_some() {
+            breaking.stop();
+        }
+        return;
+    };
+
+    if player.is_instabuild() {
+        if mouse.just_pressed(MouseButton::Left)
+            && set_block(&mut manager, &mut queue, hit.pos, Block::Air).is_some()
+        {
+            broken_writer.write(BlockBroken {
+                pos: hit.pos,
+                block: hit.block,
+            });
+        }
+        return;
+    }
+
+    let Some(duration) = break_duration(hit.block) else {
+        if breaking.pos.is_some() {
+            breaking.stop();
+        }
+        return;
+    };
+    if breaking.pos != Some(hit.pos) {
+        breaking.pos = Some(hit.pos);
+        breaking.progress = 0.;
+    }
+
+    breaking.progress += if duration > 0. {
+        time.delta_secs() / duration
+    } else {
+        1.
+    };
+    if breaking.progress >= 1. {
+        if set_block(&mut manager, &mut queue, hit.pos, Block::Air).is_some() {
+            broken_writer.write(BlockBroken {
+                pos: hit.pos,
+                block: hit.block,
+            });
+        }
+        breaking.stop();
+    }
+}
+
+/// Places block of the selected hotbar slot next to targeted block by right click.
+///
+/// Items are used up in survival.
+pub fn place_block(
+    mouse: Res<ButtonInput<MouseButton>>,
+    targeted: Res<TargetedBlock>,
+    mut player: ResMut<Player>,
+    body: Res<PlayerBody>,
+    hotbar_q: Query<&Hotbar>,
+    mut manager: ResMut<ChunkManager>,
+    mut queue: ResMut<RemeshQueue>,
+) {
+    if !mouse.just_pressed(MouseButton::Right) || !player.may_build() {
+        return;
+    }
+    let (Some(hit), Ok(hotbar)) = (targeted.0, hotbar_q.single()) else {
+        return;
+    };
+
+    let Some(index) = player
+        .inventory
+        .iter()
+        .position(|slot| slot.slot as u32 == hotbar.selected && slot.count > 0)
+    else {
+        return;
+    };
+    let Some(block) = Block::from_name(&player.inventory[index].id) else {
+        debug!("Item {} can't be placed.", player.inventory[index].id);
+        return;
+    };
+
+    let pos = hit.adjacent();
+    // Spectators go through blocks, others would get stuck in the placed one.
+    if !player.is_spectator() && intersects_player(block, pos, &body.physics.aabb()) {
+        return;
+    }
+    if !manager.block(pos).is_some_and(is_replaceable)
+        || set_block(&mut manager, &mut queue, pos, block).is_none()
+    {
+        return;
+    }
+
+    if !player.is_instabuild() {
+        let slot = &mut player.inventory[index];
+        slot.count -= 1;
+        if slot.count == 0 {
+            player.inventory.remove(index);
+        }
+    }
+}
+
+/// Moves crack overlay to the block being broken and shows its destroy stage.
+pub fn update_crack_overlay(
+    breaking: Res<BlockBreaking>,
+    materials: Res<CrackMaterials>,
+    mut overlay_q: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &mut MeshMaterial3d<StandardMaterial>,
+        ),
+        With<CrackOverlay>,
+    >,
+) {
+    if !breaking.is_changed() {
+        return;
+    }
+
+    for (mut transform, mut visibility, mut material) in overlay_q.iter_mut() {
+        let (Some(pos), Some(stage)) = (breaking.pos, breaking.stage()) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        transform.translation = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32) + 0.5;
+        *visibility = Visibility::Visible;
+        if material.0 != materials.0[stage] {
+            material.0 = materials.0[stage].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use librecraft_shared::chunk::{CHUNK_HEIGHT, Chunk};
+
+    use super::*;
+    use crate::game::player::{Abilities, Slot};
+    use crate::game::world::render::FaceDirection;
+    use crate::game::world::target::BlockHit;
+
+    #[test]
+    fn breaking_goes_through_every_stage() {
+        let mut breaking = BlockBreaking::default();
+        assert_eq!(breaking.stage(), None);
+
+        breaking.pos = Some(BlockPos::new(0, 0, 0));
+        assert_eq!(breaking.stage(), Some(0));
+        breaking.progress = 0.55;
+        assert_eq!(breaking.stage(), Some(5));
+        breaking.progress = 1.;
+        assert_eq!(breaking.stage(), Some(DESTROY_STAGES - 1));
+    }
+
+    #[test]
+    fn only_breakable_blocks_have_duration() {
+        assert_eq!(break_duration(Block::Stone), Some(2.25));
+        assert_eq!(break_duration(Block::Bedrock), None);
+        assert_eq!(break_duration(Block::Water), None);
+        assert!(is_replaceable(Block::Water));
+        assert!(!is_replaceable(Block::Glass));
+    }
+
+    #[test]
+    fn blocks_are_not_placed_inside_player() {
+        let player = Aabb::player([0.5, 1., 0.5]);
+        assert!(intersects_player(
+            Block::Stone,
+            BlockPos::new(0, 2, 0),
+            &player
+        ));
+        assert!(!intersects_player(
+            Block::Stone,
+            BlockPos::new(0, 0, 0),
+            &player
+        ));
+        assert!(!intersects_player(
+            Block::Stone,
+            BlockPos::new(1, 1, 0),
+            &player
+        ));
+        assert!(!intersects_player(
+            Block::Water,
+            BlockPos::new(0, 1, 0),
+            &player
+        ));
+    }
+
+    #[test]
+    fn blocks_are_not_placed_above_world() {
+        let top = BlockPos::new(1, CHUNK_HEIGHT - 1, 1);
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        let (x, y, z) = top.local();
+        chunk.set(x, y, z, Block::Stone);
+        let mut manager = ChunkManager::new(0, 2);
+        manager.insert_chunk(chunk);
+
+        let mut world = World::new();
+        let mut mouse = ButtonInput::<MouseButton>::default();
+        mouse.press(MouseButton::Right);
+        world.insert_resource(mouse);
+        world.insert_resource(TargetedBlock(Some(BlockHit {
+            pos: top,
+            block: Block::Stone,
+            face: FaceDirection::Top,
+            distance: 1.,
+        })));
+        world.insert_resource(Player {
+            // Survival, so that placed blocks are taken from the hotbar.
+            player_game_type: 0,
+            abilities: Abilities {
+                may_build: 1,
+                ..default()
+            },
+            inventory: vec![Slot {
+                slot: 0,
+                id: Block::Stone.name().to_owned(),
+                count: 5,
+            }],
+            ..default()
+        });
+        world.init_resource::<PlayerBody>();
+        world.insert_resource(manager);
+        world.init_resource::<RemeshQueue>();
+        world.spawn(Hotbar { selected: 0 });
+
+        world.run_system_once(place_block).unwrap();
+        assert_eq!(world.resource::<Player>().inventory[0].count, 5);
+        assert!(world.resource::<RemeshQueue>().is_empty());
+    }
+}
@@ -1,6 +1,33 @@
+/// Module that contains chunk streaming logic.
+pub mod chunk;
+/// Module that contains cloud layer logic.
+pub mod clouds;
+/// Module that contains distance fog logic.
+pub mod fog;
+/// Module that contains block breaking and placing logic.
+pub mod interaction;
+/// Module that contains particle logic.
+pub mod particles;
+/// Module that contains chunk rendering logic.
+pub mod render;
 #[cfg(feature = "fast-skybox")]
 /// Module that contains skybox logic.
 pub mod skybox;
+/// Module that contains block targeting logic.
+pub mod target;
+/// Module that contains day and night cycle logic.
+pub mod time;
+/// Module that contains weather and precipitation logic.
+pub mod weather;
 
+pub use chunk::*;
+pub use clouds::*;
+pub use fog::*;
+pub use interaction::*;
+pub use particles::*;
+pub use render::*;
 #[cfg(feature = "fast-skybox")]
 pub use skybox::*;
+pub use target::*;
+pub use time::*;
+pub use weather::*;
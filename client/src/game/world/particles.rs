@@ -0,0 +1,818 @@
+use std::f32::consts::TAU;
+use std::ops::Range;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::math::DVec3;
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::{BlockPos, ChunkPos};
+use librecraft_shared::light::LightKind;
+use librecraft_shared::noise::SplitMix64;
+use librecraft_shared::physics::{Aabb, BlockCollider, Collider, move_aabb};
+use librecraft_shared::time::TICKS_PER_SECOND;
+use librecraft_shared::weather::Precipitation;
+
+use crate::game::controller::PlayerBody;
+use crate::game::world::chunk::ChunkManager;
+use crate::game::world::interaction::BlockBroken;
+use crate::game::world::render::{
+    BiomeColormaps, BiomeTint, BlockShapes, BlockTextureArray, FaceDirection, ParticleInstance,
+    ParticleInstances, light_brightness,
+};
+use crate::game::world::time::WorldTime;
+use crate::game::world::weather::{PRECIPITATION_RADIUS, WorldWeather};
+use crate::settings::Settings;
+
+/// Most particles alive at once, new ones aren't spawned above it.
+pub const MAX_PARTICLES: usize = 4096;
+/// Particles further from the camera than this are not spawned, unless the server asks for
+/// long distance ones.
+pub const PARTICLE_DISTANCE: f32 = 32.;
+
+/// Half of the side of particle's collision box.
+const PARTICLE_RADIUS: f32 = 0.1;
+/// Velocity is multiplied by this every tick in the air, and horizontal velocity by
+/// [`GROUND_FRICTION`] on the ground.
+const AIR_DRAG: f32 = 0.98;
+const GROUND_FRICTION: f32 = 0.7;
+
+/// Side of one sprite of the particle atlas in pixels.
+const SPRITE_SIZE: u32 = 8;
+/// Broken block falls apart into this many pieces along each axis.
+const DEBRIS_GRID: usize = 4;
+/// Speed of debris flying away from the center of broken block, in blocks per second.
+const DEBRIS_SPEED: f32 = 3.;
+/// Players running faster than this raise dust, in blocks per second. Walking is slower.
+const SPRINT_DUST_SPEED: f64 = 5.;
+const DUST_PER_SECOND: f32 = 20.;
+/// Particles of a splash, more of them when falling faster.
+const SPLASH_PARTICLES: f32 = 16.;
+/// Rain drops hitting the ground around the camera per second in full rain.
+const RAIN_DROPS_PER_SECOND: f32 = 150.;
+
+/// Sprite of the particle atlas.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleSprite {
+    Dust = 0,
+    Splash = 1,
+    Drop = 2,
+}
+
+impl ParticleSprite {
+    pub const ALL: [ParticleSprite; 3] = [
+        ParticleSprite::Dust,
+        ParticleSprite::Splash,
+        ParticleSprite::Drop,
+    ];
+
+    /// Part of the atlas taken by this sprite, as `[min_u, min_v, max_u, max_v]`.
+    pub fn uvs(self) -> [f32; 4] {
+        let width = 1. / Self::ALL.len() as f32;
+        let min = self as usize as f32 * width;
+        [min, 0., min + width, 1.]
+    }
+
+    /// Color of the sprite's pixel at `x`, `y` from its top left corner.
+    fn pixel(self, x: u32, y: u32) -> [u8; 4] {
+        let center = (SPRITE_SIZE as f32 - 1.) / 2.;
+        let offset = Vec2::new(x as f32 - center, y as f32 - center);
+        let (inside, color) = match self {
+            Self::Dust => (offset.length() <= 3.5, [220, 220, 220, 255]),
+            Self::Splash => (offset.length() <= 2., [120, 160, 255, 255]),
+            Self::Drop => {
+                let inside = offset.x.abs() < 1. && offset.y.abs() < 2.;
+                (inside, [150, 180, 255, 255])
+            },
+        };
+        if inside { color } else { [0; 4] }
+    }
+}
+
+/// Generates the atlas of particle sprites, one next to another, as a texture array of one
+/// layer like block textures.
+pub fn particle_atlas() -> Image {
+    let width = SPRITE_SIZE * ParticleSprite::ALL.len() as u32;
+    let mut data = Vec::with_capacity((width * SPRITE_SIZE * 4) as usize);
+    for y in 0..SPRITE_SIZE {
+        for x in 0..width {
+            let sprite = ParticleSprite::ALL[(x / SPRITE_SIZE) as usize];
+            data.extend(sprite.pixel(x % SPRITE_SIZE, y));
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width,
+            height: SPRITE_SIZE,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+/// What a particle looks like and how it moves.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleKind {
+    /// Piece of a block, textured with it.
+    Block(Block),
+    Dust,
+    Splash,
+    Rain,
+}
+
+impl ParticleKind {
+    /// Kind of particle named `name` in the Particle packet. Block particles take their block
+    /// from the packet's data.
+    pub fn from_name(name: &str, block: Option<Block>) -> Option<Self> {
+        match name {
+            "minecraft:block" => block.map(Self::Block),
+            "minecraft:poof" => Some(Self::Dust),
+            "minecraft:splash" => Some(Self::Splash),
+            "minecraft:rain" => Some(Self::Rain),
+            _ => None,
+        }
+    }
+
+    /// Sprite of the atlas, `None` for block particles that show the block's texture.
+    pub fn sprite(self) -> Option<ParticleSprite> {
+        match self {
+            Self::Block(_) => None,
+            Self::Dust => Some(ParticleSprite::Dust),
+            Self::Splash => Some(ParticleSprite::Splash),
+            Self::Rain => Some(ParticleSprite::Drop),
+        }
+    }
+
+    /// Downward acceleration in blocks per second squared.
+    fn gravity(self) -> f32 {
+        match self {
+            Self::Block(_) => 16.,
+            Self::Dust => 0.,
+            Self::Splash | Self::Rain => 24.,
+        }
+    }
+
+    /// Seconds the particle lives.
+    fn lifetime(self) -> Range<f32> {
+        match self {
+            Self::Block(_) => 0.4..1.6,
+            Self::Dust => 0.3..0.8,
+            Self::Splash => 0.3..1.,
+            Self::Rain => 0.2..0.5,
+        }
+    }
+
+    /// Side of the billboard in blocks.
+    fn size(self) -> Range<f32> {
+        match self {
+            Self::Block(_) => 0.1..0.2,
+            Self::Dust => 0.2..0.4,
+            Self::Splash | Self::Rain => 0.1..0.15,
+        }
+    }
+}
+
+/// A billboard that falls and bounces off blocks.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Particle {
+    pub kind: ParticleKind,
+    /// Center of the billboard and of the collision box.
+    pub position: Vec3,
+    /// Velocity in blocks per second.
+    pub velocity: Vec3,
+    /// Seconds since it was spawned, it disappears at `lifetime`.
+    pub age: f32,
+    pub lifetime: f32,
+    pub size: f32,
+    /// Part of the texture shown, as `[min_u, min_v, max_u, max_v]`.
+    pub uvs: [f32; 4],
+    pub on_ground: bool,
+}
+
+impl Particle {
+    pub fn aabb(&self) -> Aabb {
+        let min = self.position - PARTICLE_RADIUS;
+        let max = self.position + PARTICLE_RADIUS;
+        Aabb::new(min.as_dvec3().to_array(), max.as_dvec3().to_array())
+    }
+
+    /// Advances the particle by `dt` seconds with gravity and collisions, returns whether it's
+    /// still alive.
+    pub fn step(&mut self, dt: f32, collider: &impl Collider) -> bool {
+        self.age += dt;
+        if self.age >= self.lifetime {
+            return false;
+        }
+
+        let ticks = dt * TICKS_PER_SECOND as f32;
+        self.velocity.y -= self.kind.gravity() * dt;
+        self.velocity *= AIR_DRAG.powf(ticks);
+        if self.on_ground {
+            let friction = GROUND_FRICTION.powf(ticks);
+            self.velocity.x *= friction;
+            self.velocity.z *= friction;
+        }
+
+        let delta = (self.velocity * dt).as_dvec3();
+        let moved = DVec3::from_array(move_aabb(&self.aabb(), delta.to_array(), collider));
+        self.position += moved.as_vec3();
+        for axis in 0..3 {
+            if moved[axis] != delta[axis] {
+                self.velocity[axis] = 0.;
+            }
+        }
+        self.on_ground = delta.y < 0. && moved.y != delta.y;
+        true
+    }
+
+    /// Instance of the particle quad showing `layer` of the texture array, multiplied by `color`.
+    pub fn instance(&self, layer: u32, color: Vec3) -> ParticleInstance {
+        ParticleInstance {
+            position_size: self.position.extend(self.size),
+            uvs: Vec4::from_array(self.uvs),
+            color_layer: color.extend(layer as f32),
+        }
+    }
+}
+
+/// Every living particle.
+///
+/// They are drawn as instances of one quad, in one draw call per texture however many there
+/// are. Instances are updated only in frames when particles changed.
+#[derive(Resource)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    rng: SplitMix64,
+    /// Whether particles were spawned, moved or died since [`Particles::take_changed`].
+    changed: bool,
+}
+
+impl Particles {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            particles: Vec::new(),
+            rng: SplitMix64::new(seed),
+            changed: false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Random number in `0..1`.
+    fn random(&mut self) -> f32 {
+        (self.rng.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn random_in(&mut self, range: Range<f32>) -> f32 {
+        range.start + self.random() * (range.end - range.start)
+    }
+
+    /// Random number of normal distribution with deviation of 1.
+    fn gaussian(&mut self) -> f32 {
+        let radius = (-2. * (1. - self.random()).ln()).sqrt();
+        radius * (TAU * self.random()).cos()
+    }
+
+    /// How many things happening `rate` times per second happen in `dt` seconds.
+    fn random_count(&mut self, rate: f32, dt: f32) -> usize {
+        let expected = rate * dt;
+        expected as usize + (self.random() < expected.fract()) as usize
+    }
+
+    /// Spawns a particle of `kind` with random lifetime and size. Block particles show a random
+    /// quarter of their texture.
+    pub fn spawn(&mut self, kind: ParticleKind, position: Vec3, velocity: Vec3) {
+        if self.particles.len() >= MAX_PARTICLES {
+            return;
+        }
+
+        let uvs = match kind.sprite() {
+            Some(sprite) => sprite.uvs(),
+            None => {
+                let (u, v) = (self.random() * 0.75, self.random() * 0.75);
+                [u, v, u + 0.25, v + 0.25]
+            },
+        };
+        let particle = Particle {
+            kind,
+            position,
+            velocity,
+            age: 0.,
+            lifetime: self.random_in(kind.lifetime()),
+            size: self.random_in(kind.size()),
+            uvs,
+            on_ground: false,
+        };
+        self.particles.push(particle);
+        self.changed = true;
+    }
+
+    /// Spawns `count` particles like the Particle packet does: spread around `position` by
+    /// `offset` and flying in random directions at up to `speed`. If `count` is 0, one particle
+    /// flies along `offset` instead. Speed is in blocks per tick.
+    pub fn emit(
+        &mut self,
+        kind: ParticleKind,
+        position: Vec3,
+        offset: Vec3,
+        speed: f32,
+        count: i32,
+    ) {
+        let speed = speed * TICKS_PER_SECOND as f32;
+        if count == 0 {
+            self.spawn(kind, position, offset * speed);
+            return;
+        }
+
+        for _ in 0..count {
+            let spread = Vec3::new(self.gaussian(), self.gaussian(), self.gaussian());
+            let direction = Vec3::new(self.gaussian(), self.gaussian(), self.gaussian());
+            self.spawn(kind, position + spread * offset, direction * speed);
+        }
+    }
+
+    /// Breaks `block` at `pos` into pieces that fly apart from its center.
+    pub fn emit_block_debris(&mut self, pos: BlockPos, block: Block) {
+        let corner = Vec3::new(pos.x as f32, pos.y as f32, pos.z as f32);
+        for i in 0..DEBRIS_GRID.pow(3) {
+            let cell = Vec3::new(
+                (i % DEBRIS_GRID) as f32,
+                (i / DEBRIS_GRID % DEBRIS_GRID) as f32,
+                (i / DEBRIS_GRID / DEBRIS_GRID) as f32,
+            );
+            let local = (cell + 0.5) / DEBRIS_GRID as f32;
+            let jitter = Vec3::new(self.gaussian(), self.gaussian(), self.gaussian()) * 0.5;
+            let velocity = ((local - 0.5) * 2. + jitter) * DEBRIS_SPEED + Vec3::Y * 2.;
+            self.spawn(ParticleKind::Block(block), corner + local, velocity);
+        }
+    }
+
+    /// Splashes water at `surface`, more for things that fall into it at `falling_speed`.
+    pub fn emit_splash(&mut self, surface: Vec3, falling_speed: f32) {
+        let count = (SPLASH_PARTICLES * (1. + falling_speed / 10.)) as usize;
+        for _ in 0..count {
+            let angle = self.random() * TAU;
+            let horizontal = Vec2::from_angle(angle) * self.random_in(0.5..2.);
+            let velocity = Vec3::new(horizontal.x, self.random_in(3.0..6.), horizontal.y);
+            let position = surface + Vec3::new(horizontal.x, 0., horizontal.y) * 0.2;
+            self.spawn(ParticleKind::Splash, position, velocity);
+        }
+    }
+
+    /// Advances every particle by `dt` seconds and removes the dead ones.
+    pub fn step(&mut self, dt: f32, collider: &impl Collider) {
+        let count = self.particles.len();
+        let mut moved = false;
+        self.particles.retain_mut(|particle| {
+            let position = particle.position;
+            let alive = particle.step(dt, collider);
+            moved |= particle.position != position;
+            alive
+        });
+        self.changed |= moved || self.particles.len() != count;
+    }
+
+    /// Whether particles changed since the last call, instances need to be updated then.
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+/// Particles drawn with one texture, their [`ParticleInstances`] are updated by
+/// [`update_particles`].
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleBatch {
+    /// Pieces of blocks, textured with the block texture array.
+    Blocks,
+    /// Sprites of the particle atlas.
+    Sprites,
+}
+
+impl ParticleBatch {
+    fn contains(self, kind: ParticleKind) -> bool {
+        match self {
+            Self::Blocks => kind.sprite().is_none(),
+            Self::Sprites => kind.sprite().is_some(),
+        }
+    }
+}
+
+/// Layer of the texture shown on pieces of `block`, and the biome color it's tinted with.
+///
+/// Their side is shown if it's not tinted. Blocks like leaves have only tinted faces, then the
+/// texture is greyscale and needs the tint.
+pub fn particle_texture(shapes: &BlockShapes, block: Block) -> (u32, Option<BiomeTint>) {
+    let faces = &shapes.get(block).faces;
+    let face = faces
+        .iter()
+        .find(|face| face.direction == FaceDirection::North && face.tint_index.is_none())
+        .or(faces.first());
+    match face {
+        Some(face) => (face.layer, face.tint_index.and(BiomeTint::of(block))),
+        None => (0, None),
+    }
+}
+
+/// Linear color of `tint` in the biome at `position`, white where no chunk is loaded.
+pub fn particle_tint(
+    manager: &ChunkManager,
+    colormaps: &BiomeColormaps,
+    tint: BiomeTint,
+    position: Vec3,
+) -> Vec3 {
+    let [x, y, z] = position.floor().as_ivec3().to_array();
+    let pos = BlockPos::new(x, y, z);
+    let Some(chunk) = manager.chunk(pos.chunk()) else {
+        return Vec3::ONE;
+    };
+
+    let (x, y, z) = pos.local();
+    Vec3::from_array(colormaps.color(tint, chunk.biome(x, y, z)))
+}
+
+/// Brightness of a particle at `position`, from block light and sky light dimmed by
+/// `sky_brightness`. Chunk meshes are lit by the sun instead, but particles are not shaded.
+pub fn particle_brightness(manager: &ChunkManager, position: Vec3, sky_brightness: f32) -> f32 {
+    let [x, y, z] = position.floor().as_ivec3().to_array();
+    let pos = BlockPos::new(x, y, z);
+    let Some(chunk) = manager.chunk(pos.chunk()) else {
+        return sky_brightness;
+    };
+
+    let (x, y, z) = pos.local();
+    let sky = chunk.light(LightKind::Sky, x, y, z) as f32 * sky_brightness;
+    light_brightness((sky.round() as u8).max(chunk.light(LightKind::Block, x, y, z)))
+}
+
+/// Setups particles and spawns their batches. Block particles get their texture once block
+/// textures are packed.
+pub fn setup_particles(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    commands.insert_resource(Particles::new(settings.seed));
+    commands.spawn((ParticleBatch::Sprites, ParticleInstances {
+        texture: images.add(particle_atlas()),
+        instances: Vec::new(),
+    }));
+    commands.spawn((ParticleBatch::Blocks, ParticleInstances::default()));
+}
+
+/// Fired with the server's Particle packet.
+#[derive(Event, Clone, Debug)]
+pub struct ParticleEmitted {
+    /// Particle type, like `minecraft:splash`.
+    pub name: String,
+    /// Block of `minecraft:block` particles, from the packet's data.
+    pub block: Option<Block>,
+    /// Whether particles are seen further than [`PARTICLE_DISTANCE`].
+    pub long_distance: bool,
+    pub position: [f64; 3],
+    /// Deviation of positions of particles around `position`, or direction of the only particle
+    /// if `count` is 0.
+    pub offset: [f32; 3],
+    /// Speed in blocks per tick.
+    pub speed: f32,
+    pub count: i32,
+}
+
+/// Spawns particles sent by the server.
+pub fn receive_particles(
+    mut particle_reader: EventReader<ParticleEmitted>,
+    mut particles: ResMut<Particles>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+
+    for ev in particle_reader.read() {
+        let Some(kind) = ParticleKind::from_name(&ev.name, ev.block) else {
+            debug!("Particle {} is not supported.", ev.name);
+            continue;
+        };
+        let position = DVec3::from_array(ev.position).as_vec3();
+        if !ev.long_distance && position.distance(camera.translation()) > PARTICLE_DISTANCE {
+            continue;
+        }
+        particles.emit(
+            kind,
+            position,
+            Vec3::from_array(ev.offset),
+            ev.speed,
+            ev.count,
+        );
+    }
+}
+
+/// Breaks blocks broken by the player into debris.
+pub fn emit_block_debris(
+    mut broken_reader: EventReader<BlockBroken>,
+    mut particles: ResMut<Particles>,
+) {
+    for ev in broken_reader.read() {
+        particles.emit_block_debris(ev.pos, ev.block);
+    }
+}
+
+/// Raises dust behind the player running on the ground.
+pub fn emit_sprint_dust(time: Res<Time>, body: Res<PlayerBody>, mut particles: ResMut<Particles>) {
+    let physics = &body.physics;
+    let [vx, _, vz] = physics.velocity;
+    if !body.spawned || !physics.on_ground || (vx * vx + vz * vz).sqrt() < SPRINT_DUST_SPEED {
+        return;
+    }
+
+    let feet = DVec3::from_array(physics.position).as_vec3();
+    let behind = -Vec3::new(vx as f32, 0., vz as f32) * 0.1;
+    for _ in 0..particles.random_count(DUST_PER_SECOND, time.delta_secs()) {
+        let spread = Vec3::new(particles.random() - 0.5, 0., particles.random() - 0.5) * 0.6;
+        let velocity = behind + Vec3::Y * particles.random_in(0.5..1.5);
+        particles.spawn(
+            ParticleKind::Dust,
+            feet + spread + Vec3::Y * PARTICLE_RADIUS,
+            velocity,
+        );
+    }
+}
+
+/// Splashes water when the player falls or walks into it.
+pub fn emit_water_splashes(
+    body: Res<PlayerBody>,
+    manager: Res<ChunkManager>,
+    mut particles: ResMut<Particles>,
+    mut was_in_water: Local<bool>,
+) {
+    let [x, y, z] = body.physics.position.map(|v| v.floor() as i32);
+    let in_water = body.spawned && manager.block(BlockPos::new(x, y, z)) == Some(Block::Water);
+    if in_water && !*was_in_water {
+        let [px, _, pz] = body.physics.position;
+        let surface = Vec3::new(px as f32, (y + 1) as f32, pz as f32);
+        particles.emit_splash(surface, -body.physics.velocity[1].min(0.) as f32);
+    }
+    *was_in_water = in_water;
+}
+
+/// Drops rain on the ground around the camera, it splashes where it falls into water.
+pub fn emit_rain_drops(
+    time: Res<Time>,
+    weather: Res<WorldWeather>,
+    manager: Res<ChunkManager>,
+    mut particles: ResMut<Particles>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+    if weather.rain_level <= 0. {
+        return;
+    }
+
+    let camera = camera.translation();
+    let rate = RAIN_DROPS_PER_SECOND * weather.rain_level;
+    let radius = PRECIPITATION_RADIUS as f32;
+    for _ in 0..particles.random_count(rate, time.delta_secs()) {
+        let x = camera.x + particles.random_in(-radius..radius);
+        let z = camera.z + particles.random_in(-radius..radius);
+        let (bx, bz) = (x.floor() as i32, z.floor() as i32);
+        let pos = ChunkPos::from_block(bx, bz);
+        let Some(chunk) = manager.chunk(pos) else {
+            continue;
+        };
+
+        let (lx, lz) = (
+            (bx - pos.min_block_x()) as usize,
+            (bz - pos.min_block_z()) as usize,
+        );
+        let height = chunk.height(lx, lz);
+        if (height as f32 - camera.y).abs() > radius
+            || Precipitation::at(chunk.biome(lx, height, lz), height) != Precipitation::Rain
+        {
+            continue;
+        }
+
+        let ground = Vec3::new(x, height as f32 + PARTICLE_RADIUS, z);
+        if manager.block(BlockPos::new(bx, height - 1, bz)) == Some(Block::Water) {
+            let velocity = Vec3::Y * particles.random_in(2.0..4.);
+            particles.spawn(ParticleKind::Splash, ground, velocity);
+        } else {
+            let velocity = Vec3::new(particles.random() - 0.5, 2., particles.random() - 0.5);
+            particles.spawn(ParticleKind::Rain, ground, velocity);
+        }
+    }
+}
+
+/// Moves particles and updates their instances when they changed.
+pub fn update_particles(
+    time: Res<Time>,
+    manager: Res<ChunkManager>,
+    world_time: Option<Res<WorldTime>>,
+    textures: Option<Res<BlockTextureArray>>,
+    shapes: Option<Res<BlockShapes>>,
+    colormaps: Option<Res<BiomeColormaps>>,
+    mut particles: ResMut<Particles>,
+    mut batch_q: Query<(&ParticleBatch, &mut ParticleInstances)>,
+) {
+    if !particles.is_empty() {
+        particles.step(time.delta_secs(), &BlockCollider(|pos| manager.block(pos)));
+    }
+    let textures_added = textures.as_ref().is_some_and(|t| t.is_added());
+    if !particles.take_changed() && !textures_added {
+        return;
+    }
+
+    let sky_brightness = world_time.map_or(1., |time| time.sky_brightness());
+    let default_colormaps = BiomeColormaps::default();
+    let colormaps = colormaps.as_deref().unwrap_or(&default_colormaps);
+    for (batch, mut instances) in batch_q.iter_mut() {
+        if *batch == ParticleBatch::Blocks {
+            let Some(textures) = &textures else {
+                continue;
+            };
+            if textures_added {
+                instances.texture = textures.image.clone();
+            }
+        }
+
+        instances.instances.clear();
+        for particle in particles.iter().filter(|p| batch.contains(p.kind)) {
+            let (layer, tint) = match (particle.kind, &shapes) {
+                (ParticleKind::Block(block), Some(shapes)) => particle_texture(shapes, block),
+                _ => (0, None),
+            };
+            let mut color = Vec3::splat(particle_brightness(
+                &manager,
+                particle.position,
+                sky_brightness,
+            ));
+            if let Some(tint) = tint {
+                color *= particle_tint(&manager, colormaps, tint, particle.position);
+            }
+            instances.instances.push(particle.instance(layer, color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use librecraft_shared::chunk::Chunk;
+
+    use super::*;
+    use crate::game::world::render::{BlockShape, Face};
+
+    /// Floor with top at `y = 1`.
+    fn floor(pos: BlockPos) -> Option<Block> {
+        Some(if pos.y < 1 { Block::Stone } else { Block::Air })
+    }
+
+    #[test]
+    fn particles_fall_and_land_on_blocks() {
+        let collider = BlockCollider(floor);
+        let mut particle = Particle {
+            kind: ParticleKind::Splash,
+            position: Vec3::new(0.5, 3., 0.5),
+            velocity: Vec3::X,
+            age: 0.,
+            lifetime: 1.,
+            size: 0.1,
+            uvs: ParticleSprite::Splash.uvs(),
+            on_ground: false,
+        };
+        for _ in 0..10 {
+            assert!(particle.step(0.05, &collider));
+        }
+        assert!(particle.on_ground);
+        assert!((particle.position.y - (1. + PARTICLE_RADIUS)).abs() < 1e-4);
+        assert_eq!(particle.velocity.y, 0.);
+        assert!(particle.position.x > 0.5);
+
+        let mut particles = Particles::new(0);
+        particles.spawn(ParticleKind::Splash, particle.position, Vec3::ZERO);
+        for _ in 0..100 {
+            particles.step(0.05, &collider);
+        }
+        assert!(particles.is_empty());
+    }
+
+    #[test]
+    fn packet_spreads_particles_or_sends_one_along_offset() {
+        let mut particles = Particles::new(0);
+        particles.emit(ParticleKind::Dust, Vec3::ZERO, Vec3::X, 0.5, 0);
+        assert_eq!(particles.len(), 1);
+        assert_eq!(particles.iter().next().unwrap().velocity, Vec3::X * 10.);
+
+        particles.emit(ParticleKind::Rain, Vec3::ZERO, Vec3::ZERO, 0., 20);
+        assert_eq!(particles.len(), 21);
+        assert!(
+            particles
+                .iter()
+                .all(|p| p.position == Vec3::ZERO || p.kind == ParticleKind::Dust)
+        );
+
+        assert_eq!(
+            ParticleKind::from_name("minecraft:block", Some(Block::Dirt)),
+            Some(ParticleKind::Block(Block::Dirt))
+        );
+        assert_eq!(ParticleKind::from_name("minecraft:block", None), None);
+    }
+
+    #[test]
+    fn resting_particles_are_not_changed() {
+        let collider = BlockCollider(floor);
+        let mut particles = Particles::new(0);
+        assert!(!particles.take_changed());
+        particles.spawn(ParticleKind::Dust, Vec3::new(0.5, 1.5, 0.5), Vec3::ZERO);
+        assert!(particles.take_changed());
+
+        // Dust doesn't fall, so it stays where it was spawned until it dies.
+        particles.step(0.05, &collider);
+        assert!(!particles.take_changed());
+        particles.step(1., &collider);
+        assert!(particles.is_empty());
+        assert!(particles.take_changed());
+    }
+
+    #[test]
+    fn debris_shows_quarters_of_block_texture() {
+        let mut particles = Particles::new(0);
+        particles.emit_block_debris(BlockPos::new(0, 0, 0), Block::Stone);
+        assert_eq!(particles.len(), 64);
+        for particle in particles.iter() {
+            let [min_u, min_v, max_u, max_v] = particle.uvs;
+            assert!(min_u >= 0. && max_u <= 1. && min_v >= 0. && max_v <= 1.);
+            assert!((max_u - min_u - 0.25).abs() < 1e-6);
+        }
+
+        let particle = particles.iter().next().unwrap();
+        let instance = particle.instance(3, Vec3::ONE);
+        assert_eq!(
+            instance.position_size,
+            particle.position.extend(particle.size)
+        );
+        assert_eq!(instance.uvs.to_array(), particle.uvs);
+        assert_eq!(instance.color_layer, Vec4::new(1., 1., 1., 3.));
+    }
+
+    #[test]
+    fn debris_of_blocks_without_untinted_faces_is_tinted() {
+        let mut shapes = BlockShapes::full_cubes();
+        assert_eq!(particle_texture(&shapes, Block::Stone).1, None);
+
+        let tinted = |direction| Face {
+            tint_index: Some(0),
+            ..Face::square(direction, "minecraft:block/oak_leaves")
+        };
+        let faces = FaceDirection::ALL.iter().map(|d| tinted(*d)).collect();
+        shapes.set(Block::Leaves, BlockShape { faces });
+        assert_eq!(
+            particle_texture(&shapes, Block::Leaves).1,
+            Some(BiomeTint::Foliage)
+        );
+
+        // Only the top of grass is tinted, its side is shown as it is.
+        let mut grass = BlockShape::full_cube(Block::Grass);
+        for face in grass.faces.iter_mut() {
+            if face.direction == FaceDirection::Top {
+                *face = tinted(FaceDirection::Top);
+            }
+        }
+        shapes.set(Block::Grass, grass);
+        assert_eq!(particle_texture(&shapes, Block::Grass).1, None);
+
+        let mut manager = ChunkManager::new(0, 2);
+        let colormaps = BiomeColormaps::default();
+        let position = Vec3::new(1.5, 64.5, 1.5);
+        let tint = |manager: &ChunkManager| {
+            particle_tint(manager, &colormaps, BiomeTint::Foliage, position)
+        };
+        assert_eq!(tint(&manager), Vec3::ONE);
+        let chunk = Chunk::new(ChunkPos { x: 0, z: 0 });
+        let foliage = colormaps.color(BiomeTint::Foliage, chunk.biome(1, 64, 1));
+        manager.insert_chunk(chunk);
+        assert_eq!(tint(&manager), Vec3::from_array(foliage));
+    }
+}
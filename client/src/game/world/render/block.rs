@@ -1,7 +1,17 @@
+use bevy::prelude::*;
+use librecraft_shared::block::Block;
+use serde::Deserialize;
+
+use super::texture::BlockTextureArray;
 
 /// Specifies which position in the block this face occupies
-pub enum FaceDirection(u8) {
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FaceDirection {
+    #[serde(rename = "down", alias = "bottom")]
     Bottom = 0,
+    #[serde(rename = "up", alias = "top")]
     Top = 1,
     North = 2,
     South = 3,
@@ -9,20 +19,244 @@ pub enum FaceDirection(u8) {
     East = 5,
 }
 
+impl FaceDirection {
+    pub const ALL: [FaceDirection; 6] = [
+        FaceDirection::Bottom,
+        FaceDirection::Top,
+        FaceDirection::North,
+        FaceDirection::South,
+        FaceDirection::West,
+        FaceDirection::East,
+    ];
+
+    /// Offset to the neighbouring block this face looks at. North is -Z, west is -X.
+    pub fn offset(self) -> [i32; 3] {
+        match self {
+            FaceDirection::Bottom => [0, -1, 0],
+            FaceDirection::Top => [0, 1, 0],
+            FaceDirection::North => [0, 0, -1],
+            FaceDirection::South => [0, 0, 1],
+            FaceDirection::West => [-1, 0, 0],
+            FaceDirection::East => [1, 0, 0],
+        }
+    }
+
+    /// Direction that looks along `offset`, if it's one of [`FaceDirection::offset`].
+    pub fn from_offset(offset: [i32; 3]) -> Option<FaceDirection> {
+        FaceDirection::ALL
+            .into_iter()
+            .find(|d| d.offset() == offset)
+    }
+
+    pub fn normal(self) -> [f32; 3] {
+        self.offset().map(|v| v as f32)
+    }
+
+    pub fn opposite(self) -> FaceDirection {
+        match self {
+            FaceDirection::Bottom => FaceDirection::Top,
+            FaceDirection::Top => FaceDirection::Bottom,
+            FaceDirection::North => FaceDirection::South,
+            FaceDirection::South => FaceDirection::North,
+            FaceDirection::West => FaceDirection::East,
+            FaceDirection::East => FaceDirection::West,
+        }
+    }
+}
+
+/// How faces of a block are drawn, each layer is a separate mesh with its own material.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RenderLayer {
+    /// Fully opaque faces, alpha is ignored.
+    Opaque = 0,
+    /// Faces with holes, pixels are either drawn or discarded by alpha test.
+    Cutout = 1,
+    /// Semi-transparent faces, blended and drawn back to front.
+    Translucent = 2,
+}
+
+impl RenderLayer {
+    pub const ALL: [RenderLayer; 3] = [
+        RenderLayer::Opaque,
+        RenderLayer::Cutout,
+        RenderLayer::Translucent,
+    ];
+
+    /// Layer that faces of `block` are drawn in.
+    pub fn of(block: Block) -> Self {
+        match block {
+            Block::Leaves => RenderLayer::Cutout,
+            Block::Water | Block::Glass => RenderLayer::Translucent,
+            _ => RenderLayer::Opaque,
+        }
+    }
+}
+
+/// UVs of a face showing the whole texture, in vertex order.
+pub const FULL_UVS: [[f32; 2]; 4] = [[0., 1.], [1., 1.], [1., 0.], [0., 0.]];
+
+/// Quad of a block model, in block-local coordinates (`0..1` on each axis).
+///
+/// Vertices go counter-clockwise when looking at the face from outside: bottom-left,
+/// bottom-right, top-right, top-left.
+#[derive(Clone, Debug)]
 pub struct Face {
     pub direction: FaceDirection,
     pub vertices: Vec<[f32; 3]>,
     pub indices: Vec<u32>,
     pub normals: Vec<[f32; 3]>,
+    /// Tint of vertices, white by default. Light is applied on top of it while meshing.
     pub colors: Vec<[f32; 4]>,
     pub uvs: Vec<[f32; 2]>,
     pub texture: String,
+    /// Side whose neighbour hides this face when it's opaque. Never hidden if `None`.
+    pub cullface: Option<FaceDirection>,
+    /// Index of tint applied to this face, such as grass color.
+    pub tint_index: Option<u32>,
+    /// Layer of `texture` in [`BlockTextureArray`], set by [`BlockShapes::resolve_layers`].
+    pub layer: u32,
+}
+
+impl Face {
+    /// Full square face of a unit cube on `direction` side.
+    pub fn square(direction: FaceDirection, texture: &str) -> Self {
+        Self::quad(direction, [0.; 3], [1.; 3], texture)
+    }
+
+    /// Face on `direction` side of the box between `from` and `to`, culled by that side.
+    pub fn quad(direction: FaceDirection, from: [f32; 3], to: [f32; 3], texture: &str) -> Self {
+        let ([x0, y0, z0], [x1, y1, z1]) = (from, to);
+        let vertices = match direction {
+            FaceDirection::Bottom => [[x0, y0, z0], [x1, y0, z0], [x1, y0, z1], [x0, y0, z1]],
+            FaceDirection::Top => [[x0, y1, z1], [x1, y1, z1], [x1, y1, z0], [x0, y1, z0]],
+            FaceDirection::North => [[x1, y0, z0], [x0, y0, z0], [x0, y1, z0], [x1, y1, z0]],
+            FaceDirection::South => [[x0, y0, z1], [x1, y0, z1], [x1, y1, z1], [x0, y1, z1]],
+            FaceDirection::West => [[x0, y0, z0], [x0, y0, z1], [x0, y1, z1], [x0, y1, z0]],
+            FaceDirection::East => [[x1, y0, z1], [x1, y0, z0], [x1, y1, z0], [x1, y1, z1]],
+        };
+
+        Self {
+            direction,
+            vertices: vertices.to_vec(),
+            indices: vec![0, 1, 2, 0, 2, 3],
+            normals: vec![direction.normal(); 4],
+            colors: vec![[1., 1., 1., 1.]; 4],
+            uvs: FULL_UVS.to_vec(),
+            texture: texture.to_owned(),
+            cullface: Some(direction),
+            tint_index: None,
+            layer: 0,
+        }
+    }
+
+    /// Whether face covers a whole side of the block, so it can be merged with its neighbours.
+    pub fn is_full_square(&self) -> bool {
+        let [nx, ny, nz] = self.direction.offset();
+        let side = if nx + ny + nz > 0 { 1. } else { 0. };
+        let axis = [nx, ny, nz].iter().position(|v| *v != 0).unwrap_or(0);
+
+        self.vertices.len() == 4
+            && self
+                .vertices
+                .iter()
+                .all(|v| v[axis] == side && v.iter().all(|c| *c == 0. || *c == 1.))
+            && self.indices.len() == 6
+    }
+
+    /// Whether face can be merged with same faces of its neighbours: it covers the whole side,
+    /// is culled by that side, and shows the whole texture once.
+    pub fn is_mergeable(&self) -> bool {
+        self.cullface == Some(self.direction) && self.is_full_square() && self.uvs == FULL_UVS
+    }
 }
 
+/// Geometry of a block, made of faces.
+#[derive(Clone, Debug, Default)]
 pub struct BlockShape {
     pub faces: Vec<Face>,
 }
 
-pub fn full_cube(block: &BlockData) -> Self {
+impl BlockShape {
+    /// Unit cube textured by block's registry textures. Empty for blocks without textures.
+    pub fn full_cube(block: Block) -> Self {
+        let Some(textures) = block.textures() else {
+            return Self::default();
+        };
+
+        let faces = FaceDirection::ALL
+            .iter()
+            .map(|direction| {
+                let texture = match direction {
+                    FaceDirection::Top => textures.top,
+                    FaceDirection::Bottom => textures.bottom,
+                    _ => textures.side,
+                };
+                Face::square(*direction, texture)
+            })
+            .collect();
+
+        Self { faces }
+    }
+
+    /// Smallest box that contains every face, as `(min, max)` corners. `None` if there are no
+    /// faces.
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let mut vertices = self
+            .faces
+            .iter()
+            .flat_map(|face| face.vertices.iter().map(|v| Vec3::from(*v)));
+        let first = vertices.next()?;
+        Some(vertices.fold((first, first), |(min, max), v| (min.min(v), max.max(v))))
+    }
+}
+
+/// Shapes of every registered block, indexed by block id.
+#[derive(Resource, Clone)]
+pub struct BlockShapes {
+    shapes: Vec<BlockShape>,
+}
+
+impl BlockShapes {
+    /// Full cubes for every block of the registry.
+    pub fn full_cubes() -> Self {
+        Self {
+            shapes: Block::ALL
+                .iter()
+                .map(|b| BlockShape::full_cube(*b))
+                .collect(),
+        }
+    }
+
+    /// Replaces shape of `block`, for example with one from resource pack.
+    pub fn set(&mut self, block: Block, shape: BlockShape) {
+        self.shapes[block.id() as usize] = shape;
+    }
+
+    /// Names of every texture used by faces, without duplicates.
+    pub fn texture_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .shapes
+            .iter()
+            .flat_map(|shape| shape.faces.iter().map(|face| face.texture.as_str()))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Points faces to layers of their textures in `textures`.
+    pub fn resolve_layers(&mut self, textures: &BlockTextureArray) {
+        for face in self
+            .shapes
+            .iter_mut()
+            .flat_map(|shape| shape.faces.iter_mut())
+        {
+            face.layer = textures.layer(&face.texture);
+        }
+    }
 
+    pub fn get(&self, block: Block) -> &BlockShape {
+        &self.shapes[block.id() as usize]
+    }
 }
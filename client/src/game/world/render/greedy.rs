@@ -0,0 +1,148 @@
+use librecraft_shared::chunk::SECTION_SIZE;
+
+use super::block::{Face, FaceDirection};
+use super::mesher::{FaceShade, SectionMesh};
+
+/// Face put into the mask, with shade of its vertices.
+#[derive(Clone, Copy)]
+struct MaskCell<'a> {
+    face: &'a Face,
+    shade: FaceShade,
+}
+
+/// Faces of one direction laid out by slice along the normal and by position inside the slice.
+///
+/// Only full square faces of opaque blocks are put into the mask.
+pub struct GreedyMask<'a> {
+    direction: FaceDirection,
+    cells: Vec<Option<MaskCell<'a>>>,
+}
+
+/// Index of the axis the face looks along, and two axes that span the face.
+fn axes(direction: FaceDirection) -> (usize, usize, usize) {
+    match direction {
+        FaceDirection::Bottom | FaceDirection::Top => (1, 0, 2),
+        FaceDirection::North | FaceDirection::South => (2, 0, 1),
+        FaceDirection::West | FaceDirection::East => (0, 2, 1),
+    }
+}
+
+/// Faces can be merged if they look the same: same texture, same lighting and same tint.
+///
+/// Faces with shade differing between vertices, like ambient occlusion, are kept alone, otherwise
+/// the gradient would be stretched over the whole merged quad.
+fn can_merge(a: &MaskCell, b: &MaskCell) -> bool {
+    a.face.texture == b.face.texture
+        && a.face.colors == b.face.colors
+        && a.shade == b.shade
+        && a.shade.is_flat()
+}
+
+impl<'a> GreedyMask<'a> {
+    pub fn new(direction: FaceDirection) -> Self {
+        Self {
+            direction,
+            cells: vec![None; SECTION_SIZE * SECTION_SIZE * SECTION_SIZE],
+        }
+    }
+
+    /// Puts face of block at section-local `pos` into the mask.
+    pub fn insert(&mut self, pos: [usize; 3], face: &'a Face, shade: FaceShade) {
+        let (n, u, v) = axes(self.direction);
+        self.cells[Self::index(pos[n], pos[u], pos[v])] = Some(MaskCell { face, shade });
+    }
+
+    fn index(slice: usize, u: usize, v: usize) -> usize {
+        (slice * SECTION_SIZE + v) * SECTION_SIZE + u
+    }
+
+    /// Merges faces of every slice into the largest rectangles it can and appends them to `mesh`.
+    pub fn merge_into(mut self, mesh: &mut SectionMesh) {
+        let size = SECTION_SIZE;
+        for slice in 0..size {
+            for v in 0..size {
+                let mut u = 0;
+                while u < size {
+                    let Some(cell) = self.cells[Self::index(slice, u, v)] else {
+                        u += 1;
+                        continue;
+                    };
+
+                    let mergeable =
+                        |other: Option<MaskCell>| other.is_some_and(|o| can_merge(&cell, &o));
+
+                    let mut width = 1;
+                    while u + width < size
+                        && mergeable(self.cells[Self::index(slice, u + width, v)])
+                    {
+                        width += 1;
+                    }
+
+                    let mut height = 1;
+                    'rows: while v + height < size {
+                        for du in 0..width {
+                            if !mergeable(self.cells[Self::index(slice, u + du, v + height)]) {
+                                break 'rows;
+                            }
+                        }
+                        height += 1;
+                    }
+
+                    for dv in 0..height {
+                        for du in 0..width {
+                            self.cells[Self::index(slice, u + du, v + dv)] = None;
+                        }
+                    }
+
+                    self.push_quad(mesh, cell, [slice, u, v], [width, height]);
+                    u += width;
+                }
+            }
+        }
+    }
+
+    /// Stretches unit face of `cell` over `size` blocks starting at `start` (in slice, u, v order).
+    ///
+    /// UVs are scaled in blocks, so texture repeats once per block.
+    fn push_quad(
+        &self,
+        mesh: &mut SectionMesh,
+        cell: MaskCell,
+        start: [usize; 3],
+        size: [usize; 2],
+    ) {
+        let face = cell.face;
+        let (n, u, v) = axes(self.direction);
+        let mut origin = [0.; 3];
+        origin[n] = start[0] as f32;
+        origin[u] = start[1] as f32;
+        origin[v] = start[2] as f32;
+        let mut scale = [1.; 3];
+        scale[u] = size[0] as f32;
+        scale[v] = size[1] as f32;
+
+        let positions: Vec<[f32; 3]> = face
+            .vertices
+            .iter()
+            .map(|p| {
+                [
+                    origin[0] + p[0] * scale[0],
+                    origin[1] + p[1] * scale[1],
+                    origin[2] + p[2] * scale[2],
+                ]
+            })
+            .collect();
+
+        // Texture axes: along the bottom edge (first to second vertex) and along the side edge.
+        let axis_of = |a: [f32; 3], b: [f32; 3]| (0..3).find(|i| a[*i] != b[*i]).unwrap_or(0);
+        let s_scale = scale[axis_of(face.vertices[0], face.vertices[1])];
+        let t_scale = scale[axis_of(face.vertices[1], face.vertices[2])];
+        let uvs: Vec<[f32; 2]> = face
+            .uvs
+            .iter()
+            .map(|uv| [uv[0] * s_scale, uv[1] * t_scale])
+            .collect();
+
+        mesh.push_quad(face, &positions, &uvs, &cell.shade);
+    }
+}
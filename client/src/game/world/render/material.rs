@@ -0,0 +1,45 @@
+use bevy::asset::{load_internal_asset, weak_handle};
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_resource::{AsBindGroup, ShaderRef};
+
+/// The `Handle` for the shader of [`BlockMaterial`].
+///
+/// Generated with `Uuid::new_v4()`
+pub const BLOCK_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("f366849d-38d0-4e59-9ec7-f25673b4b14e");
+
+/// Material of chunk sections: [`StandardMaterial`] colored by block texture array.
+pub type BlockMaterial = ExtendedMaterial<StandardMaterial, BlockTextureExtension>;
+
+/// Binds block texture array to [`StandardMaterial`].
+///
+/// Meshes choose texture layer with the first component of [`Mesh::ATTRIBUTE_UV_1`], the second
+/// one makes texture scroll over time.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct BlockTextureExtension {
+    #[texture(100, dimension = "2d_array")]
+    #[sampler(101)]
+    pub textures: Handle<Image>,
+}
+
+impl MaterialExtension for BlockTextureExtension {
+    fn fragment_shader() -> ShaderRef {
+        BLOCK_SHADER_HANDLE.into()
+    }
+}
+
+/// Registers [`BlockMaterial`] and its shader.
+pub struct BlockMaterialPlugin;
+
+impl Plugin for BlockMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            BLOCK_SHADER_HANDLE,
+            "shaders/block.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(MaterialPlugin::<BlockMaterial>::default());
+    }
+}
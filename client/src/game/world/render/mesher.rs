@@ -0,0 +1,899 @@
+use std::ops::Range;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use librecraft_shared::biome::Biome;
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::{CHUNK_HEIGHT, Chunk, SECTION_SIZE};
+use librecraft_shared::light::{LightKind, MAX_LIGHT};
+
+use super::block::{BlockShapes, Face, FaceDirection, RenderLayer};
+use super::greedy::GreedyMask;
+use super::tint::{BiomeColormaps, BiomeTint};
+
+/// How sections are meshed.
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MeshingMode {
+    /// One quad per visible face.
+    Naive,
+    /// Coplanar opaque faces that look the same are merged into larger quads.
+    #[default]
+    Greedy,
+}
+
+/// Side of a section as signed integer, to match on coordinates.
+const SIZE: i32 = SECTION_SIZE as i32;
+/// Brightness of light level 0, so that dark places are not pitch black.
+const MIN_BRIGHTNESS: f32 = 0.05;
+/// Brightness of a vertex by its ambient occlusion, from fully occluded to open.
+const AO_BRIGHTNESS: [f32; 4] = [0.5, 0.7, 0.85, 1.];
+/// Height of liquid surface in blocks, when there is no more liquid above it.
+pub const LIQUID_SURFACE_HEIGHT: f32 = 14. / 16.;
+/// Biome colors are averaged over a square of columns with this radius, like in Minecraft.
+const BIOME_BLEND_RADIUS: i32 = 2;
+
+/// Brightness of a light level. Like in Minecraft, it falls off faster for darker levels.
+pub fn light_brightness(level: u8) -> f32 {
+    let darkness = 1. - level.min(MAX_LIGHT) as f32 / MAX_LIGHT as f32;
+    let brightness = (1. - darkness) / (darkness * 3. + 1.);
+    MIN_BRIGHTNESS + (1. - MIN_BRIGHTNESS) * brightness
+}
+
+/// Chunk being meshed together with its loaded horizontal neighbours.
+pub struct ChunkNeighbourhood<'a> {
+    pub center: &'a Chunk,
+    /// Neighbours in order: north (-Z), south (+Z), west (-X), east (+X).
+    pub neighbours: [Option<&'a Chunk>; 4],
+}
+
+impl<'a> ChunkNeighbourhood<'a> {
+    /// Block at coordinates relative to the center chunk.
+    ///
+    /// Returns `None` when block lies in a neighbour that is not loaded.
+    pub fn block(&self, x: i32, y: i32, z: i32) -> Option<Block> {
+        let chunk = self.chunk_at(x, z)?;
+        Some(chunk.get(x.rem_euclid(SIZE) as usize, y, z.rem_euclid(SIZE) as usize))
+    }
+
+    /// Brightest of sky and block light at coordinates relative to the center chunk.
+    ///
+    /// Returns 0 when block lies in a neighbour that is not loaded.
+    pub fn light(&self, x: i32, y: i32, z: i32) -> u8 {
+        let Some(chunk) = self.chunk_at(x, z) else {
+            return 0;
+        };
+        let (x, z) = (x.rem_euclid(SIZE) as usize, z.rem_euclid(SIZE) as usize);
+        chunk
+            .light(LightKind::Sky, x, y, z)
+            .max(chunk.light(LightKind::Block, x, y, z))
+    }
+
+    /// Whether block at coordinates relative to the center chunk casts ambient occlusion.
+    ///
+    /// Blocks in chunks that are not loaded don't.
+    fn is_occluding(&self, x: i32, y: i32, z: i32) -> bool {
+        self.block(x, y, z).is_some_and(Block::is_opaque)
+    }
+
+    /// Biome at coordinates relative to the center chunk.
+    ///
+    /// Returns `None` when block lies in a neighbour that is not loaded.
+    pub fn biome(&self, x: i32, y: i32, z: i32) -> Option<Biome> {
+        let chunk = self.chunk_at(x, z)?;
+        Some(chunk.biome(x.rem_euclid(SIZE) as usize, y, z.rem_euclid(SIZE) as usize))
+    }
+
+    fn chunk_at(&self, x: i32, z: i32) -> Option<&'a Chunk> {
+        match (x, z) {
+            (0..SIZE, 0..SIZE) => Some(self.center),
+            (0..SIZE, ..0) => self.neighbours[0],
+            (0..SIZE, SIZE..) => self.neighbours[1],
+            (..0, 0..SIZE) => self.neighbours[2],
+            (SIZE.., 0..SIZE) => self.neighbours[3],
+            // Diagonal neighbours are never looked at.
+            _ => None,
+        }
+    }
+}
+
+/// How vertices of a face are colored on top of colors of the face itself.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FaceShade {
+    /// Brightness of every vertex, from light and ambient occlusion.
+    pub brightness: [f32; 4],
+    /// Linear biome color, white for faces that are not tinted.
+    pub tint: [f32; 3],
+    /// Texture scrolls over time, like on liquids.
+    pub animated: bool,
+}
+
+impl Default for FaceShade {
+    fn default() -> Self {
+        Self {
+            brightness: [1.; 4],
+            tint: [1.; 3],
+            animated: false,
+        }
+    }
+}
+
+impl FaceShade {
+    /// Whether every vertex has the same brightness.
+    pub fn is_flat(&self) -> bool {
+        self.brightness.iter().all(|b| *b == self.brightness[0])
+    }
+}
+
+/// Vertex data of a chunk section, ready to be turned into [`Mesh`].
+#[derive(Default, Debug)]
+pub struct SectionMesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub colors: Vec<[f32; 4]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// Texture array layer of every vertex in the first component, 1 in the second component
+    /// if texture of the vertex scrolls over time.
+    pub layers: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+    /// Center of every face and its range in `indices`, to sort translucent faces.
+    pub faces: Vec<(Vec3, Range<usize>)>,
+    pub face_count: usize,
+}
+
+impl SectionMesh {
+    pub fn is_empty(&self) -> bool {
+        self.face_count == 0
+    }
+
+    /// Appends `face` moved by `offset`, with its colors multiplied by `shade` of each vertex.
+    pub fn push_face(&mut self, face: &Face, offset: [f32; 3], shade: &FaceShade) {
+        let positions: Vec<[f32; 3]> = face
+            .vertices
+            .iter()
+            .map(|v| [v[0] + offset[0], v[1] + offset[1], v[2] + offset[2]])
+            .collect();
+        self.push_quad(face, &positions, &face.uvs, shade);
+    }
+
+    /// Appends `face` with its vertices replaced by `positions` and `uvs`.
+    ///
+    /// Quads are split along the diagonal that keeps shade gradients symmetric, otherwise
+    /// ambient occlusion looks different depending on how the quad is oriented.
+    pub fn push_quad(
+        &mut self,
+        face: &Face,
+        positions: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        shade: &FaceShade,
+    ) {
+        let start = self.positions.len() as u32;
+        self.positions.extend_from_slice(positions);
+        self.normals.extend_from_slice(&face.normals);
+        let [r, g, b] = shade.tint;
+        self.colors.extend(
+            face.colors
+                .iter()
+                .zip(shade.brightness)
+                .map(|(c, s)| [c[0] * r * s, c[1] * g * s, c[2] * b * s, c[3]]),
+        );
+        self.uvs.extend_from_slice(uvs);
+        let scroll = if shade.animated { 1. } else { 0. };
+        self.layers
+            .extend(positions.iter().map(|_| [face.layer as f32, scroll]));
+        let index_start = self.indices.len();
+        let [b0, b1, b2, b3] = shade.brightness;
+        let flip = face.indices.len() == 6 && b0 + b2 > b1 + b3;
+        self.indices.extend(face.indices.iter().map(|i| {
+            let i = if flip { (i + 1) % 4 } else { *i };
+            start + i
+        }));
+        let center = positions
+            .iter()
+            .fold(Vec3::ZERO, |sum, p| sum + Vec3::from_array(*p))
+            / positions.len() as f32;
+        self.faces.push((center, index_start..self.indices.len()));
+        self.face_count += 1;
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Faces of the mesh that can be sorted by distance.
+    pub fn translucent_faces(&self) -> TranslucentFaces {
+        TranslucentFaces {
+            faces: self.faces.clone(),
+            indices: self.indices.clone(),
+            sorted_from: None,
+        }
+    }
+
+    pub fn into_mesh(self) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, self.positions)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_UV_1, self.layers)
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, self.colors)
+        .with_inserted_indices(Indices::U32(self.indices))
+    }
+}
+
+/// Ambient occlusion of every vertex of `face`, looking at block `neighbour` in front of it.
+///
+/// Classic voxel AO: a vertex is darkened by the two blocks along the edges next to it and by the
+/// block in the corner between them. Two sides occlude fully, no matter the corner.
+fn face_occlusion(chunks: &ChunkNeighbourhood, face: &Face, neighbour: [i32; 3]) -> [f32; 4] {
+    let normal = face.direction.offset();
+    let mut tangents = (0..3).filter(|axis| normal[*axis] == 0);
+    let (u, v) = (tangents.next().unwrap(), tangents.next().unwrap());
+
+    let mut occlusion = [1.; 4];
+    for (ao, vertex) in occlusion.iter_mut().zip(&face.vertices) {
+        let step = |axis: usize| if vertex[axis] >= 0.5 { 1 } else { -1 };
+        let mut side1 = neighbour;
+        side1[u] += step(u);
+        let mut side2 = neighbour;
+        side2[v] += step(v);
+        let mut corner = side1;
+        corner[v] += step(v);
+
+        let [side1, side2, corner] =
+            [side1, side2, corner].map(|[x, y, z]| chunks.is_occluding(x, y, z));
+        let level = if side1 && side2 {
+            0
+        } else {
+            3 - side1 as usize - side2 as usize - corner as usize
+        };
+        *ao = AO_BRIGHTNESS[level];
+    }
+    occlusion
+}
+
+/// Meshes of a section, one per [`RenderLayer`].
+#[derive(Default, Debug)]
+pub struct SectionMeshes([SectionMesh; 3]);
+
+impl SectionMeshes {
+    pub fn get(&self, layer: RenderLayer) -> &SectionMesh {
+        &self.0[layer as usize]
+    }
+
+    pub fn get_mut(&mut self, layer: RenderLayer) -> &mut SectionMesh {
+        &mut self.0[layer as usize]
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.0.iter().map(|mesh| mesh.face_count).sum()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.0.iter().map(SectionMesh::vertex_count).sum()
+    }
+
+    /// Meshes that have any faces, with their layers.
+    pub fn into_layers(self) -> impl Iterator<Item = (RenderLayer, SectionMesh)> {
+        RenderLayer::ALL
+            .into_iter()
+            .zip(self.0)
+            .filter(|(_, mesh)| !mesh.is_empty())
+    }
+}
+
+/// Faces of a translucent section mesh, kept to draw them back to front as the camera moves.
+///
+/// Blending is only correct when farther faces are drawn first, and translucent faces of one
+/// section are a single draw call.
+#[derive(Component, Clone, Debug, Default)]
+pub struct TranslucentFaces {
+    faces: Vec<(Vec3, Range<usize>)>,
+    indices: Vec<u32>,
+    /// Block the camera was in when faces were last sorted.
+    pub sorted_from: Option<IVec3>,
+}
+
+impl TranslucentFaces {
+    /// Indices of faces ordered from the farthest to the closest to `eye`, relative to the
+    /// section origin.
+    pub fn sorted_indices(&self, eye: Vec3) -> Vec<u32> {
+        let mut order: Vec<(f32, &Range<usize>)> = self
+            .faces
+            .iter()
+            .map(|(center, range)| (center.distance_squared(eye), range))
+            .collect();
+        order.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+        order
+            .into_iter()
+            .flat_map(|(_, range)| self.indices[range.clone()].iter().copied())
+            .collect()
+    }
+}
+
+/// Color of `tint` at coordinates relative to the center chunk, averaged over biomes of nearby
+/// columns so that colors blend across biome borders.
+fn blended_tint(
+    chunks: &ChunkNeighbourhood,
+    colormaps: &BiomeColormaps,
+    tint: BiomeTint,
+    [x, y, z]: [i32; 3],
+) -> [f32; 3] {
+    let mut sum = [0.; 3];
+    let mut count = 0;
+    for dz in -BIOME_BLEND_RADIUS..=BIOME_BLEND_RADIUS {
+        for dx in -BIOME_BLEND_RADIUS..=BIOME_BLEND_RADIUS {
+            if let Some(biome) = chunks.biome(x + dx, y, z + dz) {
+                let color = colormaps.color(tint, biome);
+                for (s, c) in sum.iter_mut().zip(color) {
+                    *s += c;
+                }
+                count += 1;
+            }
+        }
+    }
+    sum.map(|s| s / count.max(1) as f32)
+}
+
+/// Whether face of `block` that looks at `neighbour` can be seen.
+///
+/// Unloaded neighbours hide faces, the chunk is meshed again when they load.
+fn is_face_visible(block: Block, neighbour: Option<Block>) -> bool {
+    match neighbour {
+        Some(neighbour) => !neighbour.is_opaque() && neighbour != block,
+        None => false,
+    }
+}
+
+/// Builds meshes of section `section_index` of the center chunk, emitting only visible faces
+/// into meshes of their render layers.
+///
+/// Positions are relative to the section origin. Faces are shaded by light and, when
+/// `ambient_occlusion` is set, by blocks around their vertices. Faces with tint index are
+/// colored by biomes from `colormaps`. Liquid surfaces are lowered and their textures scroll.
+pub fn mesh_section(
+    chunks: &ChunkNeighbourhood,
+    shapes: &BlockShapes,
+    colormaps: &BiomeColormaps,
+    section_index: usize,
+    mode: MeshingMode,
+    ambient_occlusion: bool,
+) -> SectionMeshes {
+    let mut meshes = SectionMeshes::default();
+    if chunks.center.section(section_index).is_empty() {
+        return meshes;
+    }
+
+    // Masks of every render layer and direction, allocated once a face is put into them.
+    let mut masks: Vec<Option<GreedyMask>> = Vec::new();
+    masks.resize_with(RenderLayer::ALL.len() * FaceDirection::ALL.len(), || None);
+
+    let base_y = section_index as i32 * SIZE;
+    for y in 0..SIZE {
+        for z in 0..SIZE {
+            for x in 0..SIZE {
+                let block = chunks.center.get(x as usize, base_y + y, z as usize);
+                if block.is_air() {
+                    continue;
+                }
+
+                let layer = RenderLayer::of(block);
+                let offset = [x as f32, y as f32, z as f32];
+                // Surface of liquid is lower, unless the same liquid continues above.
+                let surface = block.is_liquid()
+                    && chunks.center.get(x as usize, base_y + y + 1, z as usize) != block;
+                let tint = BiomeTint::of(block)
+                    .map(|tint| blended_tint(chunks, colormaps, tint, [x, base_y + y, z]));
+
+                let shape = shapes.get(block);
+                for face in &shape.faces {
+                    // Faces without cullface, like insides of plants, are always drawn.
+                    let [dx, dy, dz] = match face.cullface {
+                        Some(cullface) => {
+                            let [dx, dy, dz] = cullface.offset();
+                            let (nx, ny, nz) = (x + dx, base_y + y + dy, z + dz);
+                            let neighbour = if ny < 0 {
+                                // Nothing is visible below the world.
+                                None
+                            } else if ny >= CHUNK_HEIGHT {
+                                Some(Block::Air)
+                            } else {
+                                chunks.block(nx, ny, nz)
+                            };
+
+                            if !is_face_visible(block, neighbour) {
+                                continue;
+                            }
+                            [dx, dy, dz]
+                        },
+                        None => [0; 3],
+                    };
+
+                    // Face is lit by the block it looks at.
+                    let brightness =
+                        light_brightness(chunks.light(x + dx, base_y + y + dy, z + dz));
+                    let mut shade = FaceShade {
+                        brightness: [brightness; 4],
+                        animated: block.is_liquid(),
+                        ..default()
+                    };
+                    if ambient_occlusion && face.cullface.is_some() {
+                        let neighbour = [x + dx, base_y + y + dy, z + dz];
+                        let occlusion = face_occlusion(chunks, face, neighbour);
+                        for (s, ao) in shade.brightness.iter_mut().zip(occlusion) {
+                            *s *= ao;
+                        }
+                    }
+                    if let (Some(tint), Some(_)) = (tint, face.tint_index) {
+                        shade.tint = tint;
+                    }
+
+                    // Overlays, like grass on block sides, have to stay on top of the face
+                    // under them, so sides with more than one face are never merged.
+                    let mergeable = mode == MeshingMode::Greedy
+                        && block.is_opaque()
+                        && face.is_mergeable()
+                        && shape
+                            .faces
+                            .iter()
+                            .filter(|f| f.direction == face.direction)
+                            .count()
+                            == 1;
+                    if mergeable {
+                        let index =
+                            layer as usize * FaceDirection::ALL.len() + face.direction as usize;
+                        masks[index]
+                            .get_or_insert_with(|| GreedyMask::new(face.direction))
+                            .insert([x as usize, y as usize, z as usize], face, shade);
+                    } else if surface {
+                        let positions: Vec<[f32; 3]> = face
+                            .vertices
+                            .iter()
+                            .map(|v| {
+                                let height = if v[1] == 1. {
+                                    LIQUID_SURFACE_HEIGHT
+                                } else {
+                                    v[1]
+                                };
+                                [v[0] + offset[0], height + offset[1], v[2] + offset[2]]
+                            })
+                            .collect();
+                        meshes
+                            .get_mut(layer)
+                            .push_quad(face, &positions, &face.uvs, &shade);
+                    } else {
+                        meshes.get_mut(layer).push_face(face, offset, &shade);
+                    }
+                }
+            }
+        }
+    }
+
+    for (index, mask) in masks.into_iter().enumerate() {
+        if let Some(mask) = mask {
+            let layer = RenderLayer::ALL[index / FaceDirection::ALL.len()];
+            mask.merge_into(meshes.get_mut(layer));
+        }
+    }
+
+    meshes
+}
+
+/// Neighbour chunk offsets in order of [`ChunkNeighbourhood::neighbours`].
+pub const NEIGHBOUR_OFFSETS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+
+#[cfg(test)]
+mod tests {
+    use librecraft_shared::chunk::ChunkPos;
+
+    use super::*;
+
+    fn mesh_of(center: &Chunk, neighbours: [Option<&Chunk>; 4]) -> SectionMeshes {
+        mesh_with_mode(center, neighbours, MeshingMode::Naive)
+    }
+
+    fn mesh_with_ao(center: &Chunk, neighbours: [Option<&Chunk>; 4]) -> SectionMeshes {
+        let shapes = BlockShapes::full_cubes();
+        let chunks = ChunkNeighbourhood { center, neighbours };
+        let colormaps = BiomeColormaps::default();
+        mesh_section(&chunks, &shapes, &colormaps, 0, MeshingMode::Naive, true)
+    }
+
+    fn mesh_with_mode(
+        center: &Chunk,
+        neighbours: [Option<&Chunk>; 4],
+        mode: MeshingMode,
+    ) -> SectionMeshes {
+        let shapes = BlockShapes::full_cubes();
+        mesh_section(
+            &ChunkNeighbourhood { center, neighbours },
+            &shapes,
+            &BiomeColormaps::default(),
+            0,
+            mode,
+            false,
+        )
+    }
+
+    fn opaque(meshes: SectionMeshes) -> SectionMesh {
+        let [opaque, _, _] = meshes.0;
+        opaque
+    }
+
+    fn empty_neighbours() -> [Chunk; 4] {
+        NEIGHBOUR_OFFSETS.map(|(x, z)| Chunk::new(ChunkPos::new(x, z)))
+    }
+
+    #[test]
+    fn single_block_has_six_faces() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(5, 5, 5, Block::Stone);
+
+        let mesh = opaque(mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]));
+        assert_eq!(mesh.face_count, 6);
+        assert_eq!(mesh.positions.len(), 24);
+        assert_eq!(mesh.indices.len(), 36);
+    }
+
+    #[test]
+    fn shared_faces_are_culled() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        // 2x2x2 cube has 24 visible faces.
+        for y in 1..3 {
+            for z in 4..6 {
+                for x in 4..6 {
+                    chunk.set(x, y, z, Block::Dirt);
+                }
+            }
+        }
+        let mesh = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        assert_eq!(mesh.face_count(), 24);
+    }
+
+    #[test]
+    fn faces_are_culled_against_neighbour_chunks() {
+        let [n, s, w, mut e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(15, 5, 5, Block::Stone);
+        chunk.set(0, 5, 5, Block::Stone);
+
+        // Both blocks border empty chunks.
+        let mesh = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        assert_eq!(mesh.face_count(), 12);
+
+        // East neighbour hides one face.
+        e.set(0, 5, 5, Block::Stone);
+        let mesh = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        assert_eq!(mesh.face_count(), 11);
+
+        // Unloaded west neighbour hides one face too.
+        let mesh = mesh_of(&chunk, [Some(&n), Some(&s), None, Some(&e)]);
+        assert_eq!(mesh.face_count(), 10);
+    }
+
+    #[test]
+    fn bottom_of_world_and_liquids() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(3, 0, 3, Block::Bedrock);
+        chunk.set(3, 1, 3, Block::Water);
+        chunk.set(4, 1, 3, Block::Water);
+
+        // Bedrock has no bottom face, its top is seen through water.
+        // Water blocks hide faces between each other, first one stands on bedrock.
+        let mesh = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        assert_eq!(mesh.face_count(), 5 + 4 + 5);
+    }
+
+    #[test]
+    fn faces_are_shaded_by_light() {
+        use librecraft_shared::light::light_chunk;
+
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(5, 1, 5, Block::Stone);
+        // Roof over the second block, it's lit only from the sides.
+        chunk.set(9, 1, 9, Block::Stone);
+        for z in 6..13 {
+            for x in 6..13 {
+                chunk.set(x, 3, z, Block::Stone);
+            }
+        }
+        light_chunk(&mut chunk);
+
+        let mesh = opaque(mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]));
+        let top_colors: Vec<f32> = mesh
+            .normals
+            .iter()
+            .zip(&mesh.colors)
+            .zip(&mesh.positions)
+            .filter(|((normal, _), pos)| **normal == [0., 1., 0.] && pos[1] == 2.)
+            .map(|((_, color), _)| color[0])
+            .collect();
+
+        assert_eq!(top_colors.len(), 8);
+        assert!(
+            top_colors[..4]
+                .iter()
+                .all(|c| *c == light_brightness(MAX_LIGHT))
+        );
+        assert!(
+            top_colors[4..]
+                .iter()
+                .all(|c| *c == light_brightness(MAX_LIGHT - 4))
+        );
+        assert_eq!(light_brightness(MAX_LIGHT), 1.);
+        assert_eq!(light_brightness(0), MIN_BRIGHTNESS);
+    }
+
+    #[test]
+    fn vertices_next_to_walls_are_occluded() {
+        use librecraft_shared::light::light_chunk;
+
+        let [n, s, w, e] = empty_neighbours();
+        let neighbours = [Some(&n), Some(&s), Some(&w), Some(&e)];
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(5, 1, 5, Block::Stone);
+        // Wall to the west of the block, one block higher.
+        chunk.set(4, 2, 5, Block::Stone);
+        light_chunk(&mut chunk);
+
+        // Top face goes from (5, 6) over (6, 6) and (6, 5) to (5, 5) in x and z.
+        let top_shade = |mesh: &SectionMesh| {
+            let start = (0..mesh.positions.len())
+                .find(|i| mesh.normals[*i] == [0., 1., 0.] && mesh.positions[*i][1] == 2.)
+                .unwrap();
+            let shade: Vec<f32> = mesh.colors[start..start + 4].iter().map(|c| c[0]).collect();
+            (start, shade)
+        };
+        let (_, shade) = top_shade(&opaque(mesh_with_ao(&chunk, neighbours)));
+        assert_eq!(shade, [AO_BRIGHTNESS[2], 1., 1., AO_BRIGHTNESS[2]]);
+        let (_, shade) = top_shade(&opaque(mesh_of(&chunk, neighbours)));
+        assert_eq!(shade, [1.; 4]);
+
+        // Block in the corner darkens only one vertex, the quad is split through it.
+        chunk.set(4, 2, 5, Block::Air);
+        chunk.set(4, 2, 4, Block::Stone);
+        light_chunk(&mut chunk);
+        let mesh = opaque(mesh_with_ao(&chunk, neighbours));
+        let (start, shade) = top_shade(&mesh);
+        assert_eq!(shade, [1., 1., 1., AO_BRIGHTNESS[2]]);
+        let quad: Vec<usize> = mesh.indices[start / 4 * 6..start / 4 * 6 + 6]
+            .iter()
+            .map(|i| *i as usize - start)
+            .collect();
+        assert_eq!(quad, [1, 2, 3, 1, 3, 0]);
+    }
+
+    #[test]
+    fn greedy_merges_same_faces() {
+        let [n, s, w, e] = empty_neighbours();
+        let neighbours = [Some(&n), Some(&s), Some(&w), Some(&e)];
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        for z in 0..16 {
+            for x in 0..16 {
+                chunk.set(x, 1, z, Block::Stone);
+            }
+        }
+
+        // Every side of the layer becomes a single quad.
+        let mesh = opaque(mesh_with_mode(&chunk, neighbours, MeshingMode::Greedy));
+        assert_eq!(mesh.face_count, 6);
+        let max_uv = mesh.uvs.iter().fold(0_f32, |m, uv| m.max(uv[0]).max(uv[1]));
+        assert_eq!(max_uv, 16.);
+
+        // Different texture splits the top quad.
+        chunk.set(0, 1, 0, Block::Glowstone);
+        let mesh = opaque(mesh_with_mode(&chunk, neighbours, MeshingMode::Greedy));
+        let top_count = mesh.normals.iter().filter(|n| **n == [0., 1., 0.]).count() / 4;
+        assert_eq!(top_count, 3);
+    }
+
+    #[test]
+    fn greedy_keeps_liquids_per_face() {
+        let [n, s, w, e] = empty_neighbours();
+        let neighbours = [Some(&n), Some(&s), Some(&w), Some(&e)];
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(3, 1, 3, Block::Water);
+        chunk.set(4, 1, 3, Block::Water);
+
+        let naive = mesh_with_mode(&chunk, neighbours, MeshingMode::Naive);
+        let greedy = mesh_with_mode(&chunk, neighbours, MeshingMode::Greedy);
+        assert_eq!(naive.face_count(), greedy.face_count());
+    }
+
+    #[test]
+    fn blocks_are_split_by_render_layer() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(3, 1, 3, Block::Stone);
+        chunk.set(5, 1, 3, Block::Leaves);
+        chunk.set(7, 1, 3, Block::Glass);
+        // Stone next to glass is visible through it.
+        chunk.set(8, 1, 3, Block::Stone);
+
+        let meshes = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        assert_eq!(meshes.get(RenderLayer::Opaque).face_count, 12);
+        assert_eq!(meshes.get(RenderLayer::Cutout).face_count, 6);
+        assert_eq!(meshes.get(RenderLayer::Translucent).face_count, 5);
+    }
+
+    #[test]
+    fn liquid_surface_is_lowered_and_animated() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(3, 1, 3, Block::Water);
+        chunk.set(3, 2, 3, Block::Water);
+
+        let meshes = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        let water = meshes.get(RenderLayer::Translucent);
+        let max_y = water.positions.iter().fold(0_f32, |m, p| m.max(p[1]));
+        assert_eq!(max_y, 2. + LIQUID_SURFACE_HEIGHT);
+        // Lower block keeps its full height.
+        assert!(water.positions.iter().any(|p| p[1] == 2.));
+        assert!(water.layers.iter().all(|l| l[1] == 1.));
+    }
+
+    #[test]
+    fn translucent_faces_are_sorted_back_to_front() {
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(1, 1, 1, Block::Glass);
+        chunk.set(10, 1, 1, Block::Glass);
+
+        let meshes = mesh_of(&chunk, [Some(&n), Some(&s), Some(&w), Some(&e)]);
+        let glass = meshes.get(RenderLayer::Translucent);
+        let faces = glass.translucent_faces();
+        let x_of = |indices: &[u32]| glass.positions[indices[0] as usize][0];
+
+        // Looking from the west, the east block is drawn first.
+        let indices = faces.sorted_indices(Vec3::new(-5., 1.5, 1.5));
+        assert_eq!(indices.len(), glass.indices.len());
+        assert!(x_of(&indices[..6]) >= 10.);
+        assert!(x_of(&indices[indices.len() - 6..]) <= 2.);
+
+        let indices = faces.sorted_indices(Vec3::new(20., 1.5, 1.5));
+        assert!(x_of(&indices[..6]) <= 2.);
+    }
+
+    #[test]
+    fn tinted_faces_blend_biome_colors() {
+        use librecraft_shared::biome::Biome;
+        use librecraft_shared::light::light_chunk;
+
+        use super::super::block::BlockShape;
+        use super::super::tint::Colormap;
+
+        let [n, s, w, e] = empty_neighbours();
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        for x in 0..16 {
+            chunk.set(x, 1, 8, Block::Grass);
+        }
+        // Columns up to x = 8 are snowy plains, the rest is desert.
+        for cz in 0..4 {
+            for cx in 0..4 {
+                let biome = if cx < 2 {
+                    Biome::SnowyPlains
+                } else {
+                    Biome::Desert
+                };
+                chunk.section_mut(0).biomes.set_cell(cx, 0, cz, biome);
+            }
+        }
+        light_chunk(&mut chunk);
+
+        // Only the top face is tinted.
+        let mut shapes = BlockShapes::full_cubes();
+        let mut grass = BlockShape::full_cube(Block::Grass);
+        for face in &mut grass.faces {
+            if face.direction == FaceDirection::Top {
+                face.tint_index = Some(0);
+            }
+        }
+        shapes.set(Block::Grass, grass);
+        // Hot climates are red, cold ones are green.
+        let image = Image::new(
+            bevy::render::render_resource::Extent3d {
+                width: 2,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            bevy::render::render_resource::TextureDimension::D2,
+            [[255, 0, 0, 255], [0, 255, 0, 255]].concat(),
+            bevy::render::render_resource::TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        let colormaps = BiomeColormaps {
+            grass: Colormap::from_image(&image).unwrap(),
+            ..default()
+        };
+
+        let chunks = ChunkNeighbourhood {
+            center: &chunk,
+            neighbours: [Some(&n), Some(&s), Some(&w), Some(&e)],
+        };
+        let meshes = mesh_section(&chunks, &shapes, &colormaps, 0, MeshingMode::Naive, false);
+        let mesh = meshes.get(RenderLayer::Opaque);
+        // First vertex of a top face lies on the west edge of its block.
+        let top_color = |x: f32| {
+            let i = (0..mesh.positions.len())
+                .step_by(4)
+                .find(|i| mesh.normals[*i] == [0., 1., 0.] && mesh.positions[*i][0] == x)
+                .unwrap();
+            mesh.colors[i]
+        };
+
+        // Blocks between snowy plains and desert get a mix of both colors.
+        let snowy = top_color(4.);
+        let border = top_color(7.);
+        let desert = top_color(12.);
+        assert_eq!(snowy[..3], [0., 1., 0.]);
+        assert_eq!(desert[..3], [1., 0., 0.]);
+        assert!(border[0] > 0. && border[1] > 0.);
+        // Sides are not tinted.
+        assert!(
+            mesh.normals
+                .iter()
+                .zip(&mesh.colors)
+                .filter(|(n, _)| **n != [0., 1., 0.])
+                .all(|(_, c)| c[0] == c[1] && c[1] == c[2])
+        );
+    }
+
+    /// Reports vertex counts and meshing time per section on generated terrain.
+    ///
+    /// Run with `cargo test --release -- --ignored meshing_benchmark --nocapture`.
+    #[test]
+    #[ignore]
+    fn meshing_benchmark() {
+        use std::time::Instant;
+
+        use librecraft_shared::chunk::SECTION_COUNT;
+        use librecraft_shared::worldgen::WorldGenerator;
+
+        let generator = WorldGenerator::new(0);
+        let center = generator.generate_chunk(ChunkPos::new(0, 0));
+        let neighbours =
+            NEIGHBOUR_OFFSETS.map(|(x, z)| generator.generate_chunk(ChunkPos::new(x, z)));
+        let chunks = ChunkNeighbourhood {
+            center: &center,
+            neighbours: [
+                Some(&neighbours[0]),
+                Some(&neighbours[1]),
+                Some(&neighbours[2]),
+                Some(&neighbours[3]),
+            ],
+        };
+        let shapes = BlockShapes::full_cubes();
+        let colormaps = BiomeColormaps::default();
+        let rounds = 20;
+
+        for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+            let mut vertex_count = 0;
+            let mut section_count = 0;
+            let start = Instant::now();
+            for _ in 0..rounds {
+                vertex_count = 0;
+                section_count = 0;
+                for index in 0..SECTION_COUNT {
+                    if center.section(index).is_empty() {
+                        continue;
+                    }
+                    vertex_count += mesh_section(&chunks, &shapes, &colormaps, index, mode, true)
+                        .vertex_count();
+                    section_count += 1;
+                }
+            }
+            let per_section = start.elapsed() / (rounds * section_count.max(1)) as u32;
+            println!(
+                "{mode:?}: {vertex_count} vertices in {section_count} sections, {} per section, \
+                 {per_section:?} per section",
+                vertex_count / section_count.max(1)
+            );
+        }
+    }
+}
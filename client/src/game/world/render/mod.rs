@@ -0,0 +1,30 @@
+/// Block shapes built from faces.
+pub mod block;
+/// Greedy merging of coplanar faces.
+pub mod greedy;
+/// Material of chunk sections.
+pub mod material;
+/// Chunk section meshing.
+pub mod mesher;
+/// Resource pack block models and blockstates.
+pub mod model;
+/// Instanced rendering of particles.
+pub mod particle;
+/// Chunk section entities and meshing systems.
+pub mod section;
+/// Block textures packed into texture array.
+pub mod texture;
+/// Biome colors of grass, foliage and water.
+pub mod tint;
+/// Frustum and cave culling of chunk sections.
+pub mod visibility;
+
+pub use block::*;
+pub use material::*;
+pub use mesher::*;
+pub use model::*;
+pub use particle::*;
+pub use section::*;
+pub use texture::*;
+pub use tint::*;
+pub use visibility::*;
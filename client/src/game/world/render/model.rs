@@ -0,0 +1,750 @@
+use std::fmt;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadDirectError};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use librecraft_shared::block::Block;
+use serde::Deserialize;
+
+use super::block::{BlockShape, BlockShapes, Face, FaceDirection};
+use super::texture::MISSING_TEXTURE;
+
+/// Folder of blockstate files in resource pack.
+pub const BLOCKSTATES_FOLDER: &str = "blockstates";
+/// Folder of model files in resource pack.
+pub const MODELS_FOLDER: &str = "models";
+
+/// How many texture variables (like `#all`) are followed before texture is considered missing.
+const MAX_TEXTURE_VARIABLE_DEPTH: usize = 8;
+/// Size of a block in model coordinates.
+const MODEL_UNIT: f32 = 16.;
+
+/// Path of blockstate file of `block`, such as `blockstates/stone.json`.
+pub fn blockstate_path(block: Block) -> String {
+    format!(
+        "{BLOCKSTATES_FOLDER}/{}.json",
+        strip_namespace(block.name())
+    )
+}
+
+/// Path of model file referenced as `block/cube_all` or `minecraft:block/cube_all`.
+pub fn model_path(reference: &str) -> String {
+    format!("{MODELS_FOLDER}/{}.json", strip_namespace(reference))
+}
+
+/// Name of texture in block texture array, referenced as `block/dirt` or `minecraft:block/dirt`.
+pub fn texture_name(reference: &str) -> String {
+    let path = strip_namespace(reference);
+    path.strip_prefix("block/").unwrap_or(path).to_owned()
+}
+
+fn strip_namespace(reference: &str) -> &str {
+    reference
+        .split_once(':')
+        .map_or(reference, |(_, path)| path)
+}
+
+/// Error that could happen while loading a model or blockstate.
+#[derive(Debug)]
+pub enum ModelError {
+    /// Happens if file can't be read.
+    Io(std::io::Error),
+    /// Happens if file is not valid JSON or doesn't match the format.
+    Json(serde_json::Error),
+    /// Happens if a parent model or a model of blockstate can't be loaded.
+    Dependency(String),
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelError::Io(e) => write!(f, "can't read file: {e}"),
+            ModelError::Json(e) => write!(f, "invalid JSON: {e}"),
+            ModelError::Dependency(e) => write!(f, "can't load dependency: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+impl From<std::io::Error> for ModelError {
+    fn from(e: std::io::Error) -> Self {
+        ModelError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ModelError {
+    fn from(e: serde_json::Error) -> Self {
+        ModelError::Json(e)
+    }
+}
+
+impl From<LoadDirectError> for ModelError {
+    fn from(e: LoadDirectError) -> Self {
+        ModelError::Dependency(e.to_string())
+    }
+}
+
+/// Axis of element rotation.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// Rotation of element around `origin`, in model coordinates (`0..16`).
+#[derive(Deserialize, Clone, Debug)]
+pub struct ElementRotation {
+    pub origin: [f32; 3],
+    pub axis: Axis,
+    /// Degrees, from -45 to 45 in steps of 22.5.
+    pub angle: f32,
+    /// Whether faces are scaled across the whole block after rotation.
+    #[serde(default)]
+    pub rescale: bool,
+}
+
+/// Face of model element.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModelFace {
+    /// Area of texture as `[u1, v1, u2, v2]` in `0..16`. Derived from element position if absent.
+    pub uv: Option<[f32; 4]>,
+    /// Texture reference or variable like `#all`.
+    pub texture: String,
+    pub cullface: Option<FaceDirection>,
+    /// Clockwise rotation of texture in degrees, multiple of 90.
+    #[serde(default)]
+    pub rotation: u32,
+    pub tintindex: Option<u32>,
+}
+
+/// Box of a model, in model coordinates (`0..16`).
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModelElement {
+    pub from: [f32; 3],
+    pub to: [f32; 3],
+    pub rotation: Option<ElementRotation>,
+    #[serde(default)]
+    pub faces: HashMap<FaceDirection, ModelFace>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModelJson {
+    parent: Option<String>,
+    #[serde(default)]
+    textures: HashMap<String, String>,
+    elements: Option<Vec<ModelElement>>,
+}
+
+/// Block model with everything it inherits from its parents.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct BlockModel {
+    /// Texture variables of the model and its parents, the closest definition wins.
+    pub textures: HashMap<String, String>,
+    /// Elements of the closest model in hierarchy that has any.
+    pub elements: Vec<ModelElement>,
+}
+
+impl BlockModel {
+    /// Model from `json`, taking texture variables and elements it lacks from `parent`.
+    fn inherit(json: ModelJson, parent: Option<BlockModel>) -> Self {
+        let parent = parent.unwrap_or_default();
+        let mut textures = parent.textures;
+        textures.extend(json.textures);
+
+        Self {
+            textures,
+            elements: json.elements.unwrap_or(parent.elements),
+        }
+    }
+
+    /// Follows texture variables until a texture reference, then turns it into texture name.
+    pub fn resolve_texture(&self, reference: &str) -> String {
+        let mut reference = reference;
+        for _ in 0..MAX_TEXTURE_VARIABLE_DEPTH {
+            let Some(variable) = reference.strip_prefix('#') else {
+                return texture_name(reference);
+            };
+            match self.textures.get(variable) {
+                Some(next) => reference = next,
+                None => break,
+            }
+        }
+        MISSING_TEXTURE.to_owned()
+    }
+
+    /// Faces of every element, rotated by `x` then `y` degrees around the block center.
+    pub fn faces(&self, x: i32, y: i32) -> Vec<Face> {
+        let mut faces = Vec::new();
+        for element in &self.elements {
+            for direction in FaceDirection::ALL {
+                let Some(model_face) = element.faces.get(&direction) else {
+                    continue;
+                };
+                let mut face = self.element_face(element, direction, model_face);
+                rotate_face(&mut face, x, y);
+                faces.push(face);
+            }
+        }
+        faces
+    }
+
+    fn element_face(
+        &self,
+        element: &ModelElement,
+        direction: FaceDirection,
+        model_face: &ModelFace,
+    ) -> Face {
+        let from = element.from.map(|c| c / MODEL_UNIT);
+        let to = element.to.map(|c| c / MODEL_UNIT);
+        let mut face = Face::quad(
+            direction,
+            from,
+            to,
+            &self.resolve_texture(&model_face.texture),
+        );
+        face.cullface = model_face.cullface;
+        face.tint_index = model_face.tintindex;
+
+        let [u1, v1, u2, v2] = model_face
+            .uv
+            .unwrap_or_else(|| default_uv(direction, element.from, element.to))
+            .map(|c| c / MODEL_UNIT);
+        let mut uvs = vec![[u1, v2], [u2, v2], [u2, v1], [u1, v1]];
+        uvs.rotate_left((model_face.rotation / 90 % 4) as usize);
+        face.uvs = uvs;
+
+        if let Some(rotation) = &element.rotation {
+            rotate_element_face(&mut face, rotation);
+        }
+        face
+    }
+}
+
+/// Area of texture that matches position of element face, as Minecraft does.
+fn default_uv(direction: FaceDirection, from: [f32; 3], to: [f32; 3]) -> [f32; 4] {
+    let ([x0, y0, z0], [x1, y1, z1]) = (from, to);
+    let u = MODEL_UNIT;
+    match direction {
+        FaceDirection::Bottom => [x0, u - z1, x1, u - z0],
+        FaceDirection::Top => [x0, z0, x1, z1],
+        FaceDirection::North => [u - x1, u - y1, u - x0, u - y0],
+        FaceDirection::South => [x0, u - y1, x1, u - y0],
+        FaceDirection::West => [z0, u - y1, z1, u - y0],
+        FaceDirection::East => [u - z1, u - y1, u - z0, u - y0],
+    }
+}
+
+/// Rotates `v` around `axis` by `angle` degrees.
+fn rotate_vector(v: [f32; 3], axis: Axis, angle: f32) -> [f32; 3] {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let [x, y, z] = v;
+    match axis {
+        Axis::X => [x, y * cos - z * sin, y * sin + z * cos],
+        Axis::Y => [x * cos + z * sin, y, -x * sin + z * cos],
+        Axis::Z => [x * cos - y * sin, x * sin + y * cos, z],
+    }
+}
+
+fn rotate_element_face(face: &mut Face, rotation: &ElementRotation) {
+    let origin = rotation.origin.map(|c| c / MODEL_UNIT);
+    let scale = if rotation.rescale {
+        1. / rotation.angle.to_radians().cos()
+    } else {
+        1.
+    };
+
+    for vertex in &mut face.vertices {
+        let relative = [0, 1, 2].map(|i| vertex[i] - origin[i]);
+        let rotated = rotate_vector(relative, rotation.axis, rotation.angle);
+        *vertex = [0, 1, 2].map(|i| {
+            let along_axis = i == rotation.axis as usize;
+            origin[i]
+                + if along_axis {
+                    rotated[i]
+                } else {
+                    rotated[i] * scale
+                }
+        });
+    }
+    for normal in &mut face.normals {
+        *normal = rotate_vector(*normal, rotation.axis, rotation.angle);
+    }
+}
+
+/// Rotates `v` by `x` then `y` degrees in steps of 90. Positive `x` turns north to down,
+/// positive `y` turns north to east, as blockstates do.
+fn rotate_quarters(v: [f32; 3], x: i32, y: i32) -> [f32; 3] {
+    let mut v = v;
+    for _ in 0..x.rem_euclid(360) / 90 {
+        v = [v[0], v[2], -v[1]];
+    }
+    for _ in 0..y.rem_euclid(360) / 90 {
+        v = [-v[2], v[1], v[0]];
+    }
+    v
+}
+
+/// Rotates face of a model placed by blockstate around the block center.
+fn rotate_face(face: &mut Face, x: i32, y: i32) {
+    let rotate_direction = |d: FaceDirection| {
+        FaceDirection::from_offset(rotate_quarters(d.normal(), x, y).map(|c| c as i32)).unwrap_or(d)
+    };
+
+    for vertex in &mut face.vertices {
+        *vertex = rotate_quarters(vertex.map(|c| c - 0.5), x, y).map(|c| c + 0.5);
+    }
+    for normal in &mut face.normals {
+        *normal = rotate_quarters(*normal, x, y);
+    }
+    face.direction = rotate_direction(face.direction);
+    face.cullface = face.cullface.map(rotate_direction);
+}
+
+/// Model placed by blockstate variant.
+#[derive(Clone, Debug)]
+pub struct PlacedModel {
+    pub model: BlockModel,
+    /// Rotation around X axis in degrees, multiple of 90.
+    pub x: i32,
+    /// Rotation around Y axis in degrees, multiple of 90.
+    pub y: i32,
+}
+
+/// Part of multipart blockstate.
+#[derive(Clone, Debug)]
+pub struct MultipartCase {
+    /// Condition on block properties, always applied if absent.
+    pub when: Option<serde_json::Value>,
+    pub apply: PlacedModel,
+}
+
+/// Blockstate with models it refers to.
+#[derive(Asset, TypePath, Clone, Debug, Default)]
+pub struct BlockState {
+    /// Models by property string, like `facing=north,half=top`.
+    pub variants: HashMap<String, PlacedModel>,
+    pub multipart: Vec<MultipartCase>,
+}
+
+impl BlockState {
+    /// Shape of default state of block.
+    ///
+    /// Blocks have no properties yet, so the variant without properties is used together with
+    /// multipart cases that have no condition.
+    pub fn shape(&self) -> BlockShape {
+        let variant = self
+            .variants
+            .get("")
+            .or_else(|| self.variants.get("normal"))
+            .or_else(|| {
+                // Any variant is better than nothing, take the same one every time.
+                let mut keys: Vec<&String> = self.variants.keys().collect();
+                keys.sort_unstable();
+                keys.first().and_then(|key| self.variants.get(*key))
+            });
+
+        let faces = variant
+            .into_iter()
+            .chain(
+                self.multipart
+                    .iter()
+                    .filter(|case| case.when.is_none())
+                    .map(|case| &case.apply),
+            )
+            .flat_map(|placed| placed.model.faces(placed.x, placed.y))
+            .collect();
+
+        BlockShape { faces }
+    }
+}
+
+/// Blockstates of every block that are being loaded, before shapes are built from them.
+#[derive(Resource, Debug)]
+pub struct BlockStateHandles {
+    handles: Vec<(Block, Handle<BlockState>)>,
+}
+
+impl BlockStateHandles {
+    /// Starts loading blockstate of every block but air.
+    pub fn new(assets: &AssetServer) -> Self {
+        Self {
+            handles: Block::ALL
+                .iter()
+                .filter(|block| !block.is_air())
+                .map(|block| (*block, assets.load(blockstate_path(*block))))
+                .collect(),
+        }
+    }
+
+    /// Whether every blockstate has either loaded or failed to load.
+    pub fn is_finished(&self, assets: &AssetServer) -> bool {
+        self.handles.iter().all(|(_, handle)| {
+            let state = assets.load_state(handle.id());
+            state.is_loaded() || state.is_failed()
+        })
+    }
+
+    /// Shapes of blocks from their blockstates. Blocks whose blockstate can't be loaded stay
+    /// cubes with registry textures.
+    pub fn shapes(&self, blockstates: &Assets<BlockState>) -> BlockShapes {
+        let mut shapes = BlockShapes::full_cubes();
+        for (block, handle) in &self.handles {
+            match blockstates.get(handle) {
+                Some(state) => shapes.set(*block, state.shape()),
+                None => warn!(
+                    "Blockstate of {} can't be loaded, it's drawn as a cube.",
+                    block.name()
+                ),
+            }
+        }
+        shapes
+    }
+}
+
+/// Variant lists are weighted random choices, first one is used.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    One(T),
+    Many(Vec<T>),
+}
+
+impl<T> OneOrMany<T> {
+    fn first(&self) -> Option<&T> {
+        match self {
+            OneOrMany::One(value) => Some(value),
+            OneOrMany::Many(values) => values.first(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct VariantJson {
+    model: String,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+}
+
+#[derive(Deserialize, Debug)]
+struct MultipartJson {
+    when: Option<serde_json::Value>,
+    apply: OneOrMany<VariantJson>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BlockStateJson {
+    #[serde(default)]
+    variants: HashMap<String, OneOrMany<VariantJson>>,
+    #[serde(default)]
+    multipart: Vec<MultipartJson>,
+}
+
+/// Loads `models/**/*.json`, resolving parents right away.
+#[derive(Default)]
+pub struct BlockModelLoader;
+
+impl AssetLoader for BlockModelLoader {
+    type Asset = BlockModel;
+    type Settings = ();
+    type Error = ModelError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<BlockModel, ModelError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let json: ModelJson = serde_json::from_slice(&bytes)?;
+
+        let parent = match &json.parent {
+            Some(parent) => Some(
+                load_context
+                    .loader()
+                    .immediate()
+                    .load::<BlockModel>(model_path(parent))
+                    .await?
+                    .take(),
+            ),
+            None => None,
+        };
+        Ok(BlockModel::inherit(json, parent))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+/// Loads `blockstates/*.json` together with models they refer to.
+#[derive(Default)]
+pub struct BlockStateLoader;
+
+impl AssetLoader for BlockStateLoader {
+    type Asset = BlockState;
+    type Settings = ();
+    type Error = ModelError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<BlockState, ModelError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let json: BlockStateJson = serde_json::from_slice(&bytes)?;
+
+        let mut state = BlockState::default();
+        for (properties, variants) in &json.variants {
+            if let Some(variant) = variants.first() {
+                let placed = place_model(load_context, variant).await?;
+                state.variants.insert(properties.clone(), placed);
+            }
+        }
+        for case in json.multipart {
+            if let Some(variant) = case.apply.first() {
+                let apply = place_model(load_context, variant).await?;
+                state.multipart.push(MultipartCase {
+                    when: case.when,
+                    apply,
+                });
+            }
+        }
+        Ok(state)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["json"]
+    }
+}
+
+async fn place_model(
+    load_context: &mut LoadContext<'_>,
+    variant: &VariantJson,
+) -> Result<PlacedModel, ModelError> {
+    let model = load_context
+        .loader()
+        .immediate()
+        .load::<BlockModel>(model_path(&variant.model))
+        .await?
+        .take();
+    Ok(PlacedModel {
+        model,
+        x: variant.x,
+        y: variant.y,
+    })
+}
+
+/// Registers block models and blockstates of resource pack.
+///
+/// Both are JSON files, so they are told apart by the requested asset type.
+pub struct BlockModelPlugin;
+
+impl Plugin for BlockModelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BlockModel>()
+            .init_asset::<BlockState>()
+            .init_asset_loader::<BlockModelLoader>()
+            .init_asset_loader::<BlockStateLoader>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::world::render::texture::block_texture_path;
+
+    fn model(json: &str, parent: Option<BlockModel>) -> BlockModel {
+        BlockModel::inherit(serde_json::from_str(json).unwrap(), parent)
+    }
+
+    fn cube_all(texture: &str) -> BlockModel {
+        let cube = model(
+            r##"{ "elements": [{ "from": [0, 0, 0], "to": [16, 16, 16], "faces": {
+                "down": { "texture": "#down", "cullface": "down" },
+                "up": { "texture": "#up", "cullface": "up", "tintindex": 0 },
+                "north": { "texture": "#north", "cullface": "north" },
+                "south": { "texture": "#south", "cullface": "south" },
+                "west": { "texture": "#west", "cullface": "west" },
+                "east": { "texture": "#east", "cullface": "east" } } }] }"##,
+            None,
+        );
+        let cube_all = model(
+            r##"{ "parent": "block/cube", "textures": { "down": "#all", "up": "#all",
+                "north": "#all", "south": "#all", "west": "#all", "east": "#all" } }"##,
+            Some(cube),
+        );
+        model(
+            &format!(r#"{{ "parent": "block/cube_all", "textures": {{ "all": "{texture}" }} }}"#),
+            Some(cube_all),
+        )
+    }
+
+    #[test]
+    fn paths_and_names() {
+        assert_eq!(
+            blockstate_path(Block::Grass),
+            "blockstates/grass_block.json"
+        );
+        assert_eq!(
+            model_path("minecraft:block/cube_all"),
+            "models/block/cube_all.json"
+        );
+        assert_eq!(texture_name("minecraft:block/dirt"), "dirt");
+        assert_eq!(texture_name("block/grass_top"), "grass_top");
+    }
+
+    #[test]
+    fn parents_are_inherited() {
+        let dirt = cube_all("block/dirt");
+        let faces = dirt.faces(0, 0);
+
+        assert_eq!(faces.len(), 6);
+        for (face, square) in faces
+            .iter()
+            .zip(FaceDirection::ALL.map(|d| Face::square(d, "dirt")))
+        {
+            assert_eq!(face.texture, "dirt");
+            assert_eq!(face.vertices, square.vertices);
+            assert_eq!(face.uvs, square.uvs);
+            assert!(face.is_mergeable());
+        }
+        assert_eq!(faces[1].tint_index, Some(0));
+        assert_eq!(faces[0].tint_index, None);
+    }
+
+    #[test]
+    fn unresolved_texture_is_missing() {
+        let looped = model(r##"{ "textures": { "a": "#b", "b": "#a" } }"##, None);
+        assert_eq!(looped.resolve_texture("#a"), MISSING_TEXTURE);
+        assert_eq!(looped.resolve_texture("#c"), MISSING_TEXTURE);
+    }
+
+    #[test]
+    fn slab_is_culled_only_below() {
+        let slab = model(
+            r##"{ "textures": { "all": "block/stone" }, "elements": [{
+                "from": [0, 0, 0], "to": [16, 8, 16], "faces": {
+                "down": { "texture": "#all", "cullface": "down" },
+                "up": { "texture": "#all" },
+                "north": { "uv": [0, 8, 16, 16], "texture": "#all", "cullface": "north" } } }] }"##,
+            None,
+        );
+        let faces = slab.faces(0, 0);
+
+        assert_eq!(faces[1].cullface, None);
+        assert!(faces[1].vertices.iter().all(|v| v[1] == 0.5));
+        assert_eq!(faces[2].uvs, vec![[0., 1.], [1., 1.], [1., 0.5], [0., 0.5]]);
+        assert!(!faces[2].is_mergeable());
+    }
+
+    #[test]
+    fn variant_rotation_turns_faces() {
+        let faces = cube_all("block/dirt").faces(0, 90);
+        let north = &faces[2];
+        assert_eq!(north.direction, FaceDirection::East);
+        assert_eq!(north.cullface, Some(FaceDirection::East));
+        assert!(north.vertices.iter().all(|v| v[0] == 1.));
+        assert!(north.is_mergeable());
+
+        let faces = cube_all("block/dirt").faces(90, 0);
+        assert_eq!(faces[1].direction, FaceDirection::North);
+        assert_eq!(faces[2].direction, FaceDirection::Bottom);
+    }
+
+    #[test]
+    fn element_rotation_makes_cross() {
+        let plant = model(
+            r##"{ "textures": { "cross": "block/grass" }, "elements": [{
+                "from": [0.8, 0, 8], "to": [15.2, 16, 8],
+                "rotation": { "origin": [8, 8, 8], "axis": "y", "angle": 45, "rescale": true },
+                "faces": { "north": { "texture": "#cross" } } }] }"##,
+            None,
+        );
+        let faces = plant.faces(0, 0);
+
+        assert_eq!(faces[0].cullface, None);
+        for v in &faces[0].vertices {
+            // Rotated to a diagonal of the block.
+            assert!((v[0] - v[2]).abs() < 1e-4 || (v[0] + v[2] - 1.).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn blockstate_uses_default_variant_and_unconditional_parts() {
+        let placed = |x, y| PlacedModel {
+            model: cube_all("block/stone"),
+            x,
+            y,
+        };
+        let mut state = BlockState::default();
+        state.variants.insert(String::new(), placed(0, 0));
+        state.multipart.push(MultipartCase {
+            when: None,
+            apply: placed(0, 90),
+        });
+        state.multipart.push(MultipartCase {
+            when: Some(serde_json::json!({ "north": "true" })),
+            apply: placed(0, 180),
+        });
+
+        assert_eq!(state.shape().faces.len(), 12);
+    }
+
+    /// Resource pack shipped with the game.
+    fn shipped(path: &str) -> std::path::PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(crate::consts::ASSET_FOLDER)
+            .join(path)
+    }
+
+    /// Loads shipped model `reference` with its parents.
+    fn load_shipped(reference: &str) -> BlockModel {
+        let json = std::fs::read_to_string(shipped(&model_path(reference))).unwrap();
+        let json: ModelJson = serde_json::from_str(&json).unwrap();
+        let parent = json.parent.as_deref().map(load_shipped);
+        BlockModel::inherit(json, parent)
+    }
+
+    #[test]
+    fn shipped_models_have_shipped_textures() {
+        let models = std::fs::read_dir(shipped(MODELS_FOLDER).join("block")).unwrap();
+        for entry in models {
+            let path = entry.unwrap().path();
+            let json: ModelJson =
+                serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+            for reference in json.textures.values().filter(|r| !r.starts_with('#')) {
+                let texture = block_texture_path(&texture_name(reference));
+                assert!(
+                    shipped(&texture).is_file(),
+                    "{} uses {texture}, which is not shipped",
+                    path.display()
+                );
+            }
+        }
+
+        for block in Block::ALL.into_iter().filter(|block| !block.is_air()) {
+            let json = std::fs::read_to_string(shipped(&blockstate_path(block))).unwrap();
+            let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            for (_, variant) in json["variants"].as_object().unwrap() {
+                let model = load_shipped(variant["model"].as_str().unwrap());
+                for face in model.faces(0, 0) {
+                    assert_ne!(
+                        face.texture, MISSING_TEXTURE,
+                        "{block:?} has missing texture"
+                    );
+                    let texture = block_texture_path(&face.texture);
+                    assert!(shipped(&texture).is_file(), "{texture} is not shipped");
+                }
+            }
+        }
+    }
+}
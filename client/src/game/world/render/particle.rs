@@ -0,0 +1,349 @@
+use bevy::asset::{load_internal_asset, weak_handle};
+use bevy::core_pipeline::core_3d::{CORE_3D_DEPTH_FORMAT, Transparent3d};
+use bevy::core_pipeline::oit::OrderIndependentTransparencySettings;
+use bevy::core_pipeline::prepass::{
+    DeferredPrepass, DepthPrepass, MotionVectorPrepass, NormalPrepass,
+};
+use bevy::ecs::query::QueryItem;
+use bevy::ecs::system::SystemParamItem;
+use bevy::ecs::system::lifetimeless::{Read, SRes};
+use bevy::image::BevyDefault;
+use bevy::pbr::{MeshPipeline, MeshPipelineKey, SetMeshViewBindGroup};
+use bevy::prelude::*;
+use bevy::render::extract_component::{ExtractComponent, ExtractComponentPlugin};
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{
+    AddRenderCommand, DrawFunctions, PhaseItem, PhaseItemExtraIndex, RenderCommand,
+    RenderCommandResult, SetItemPipeline, TrackedRenderPass, ViewSortedRenderPhases,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d_array};
+use bevy::render::render_resource::encase::StorageBuffer;
+use bevy::render::render_resource::*;
+use bevy::render::renderer::RenderDevice;
+use bevy::render::sync_world::MainEntity;
+use bevy::render::texture::GpuImage;
+use bevy::render::view::{ExtractedView, ViewTarget};
+use bevy::render::{Render, RenderApp, RenderSet};
+
+/// The `Handle` for the shader of instanced particles.
+///
+/// Generated with `Uuid::new_v4()`
+pub const PARTICLE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("b1ce789a-32fd-43c0-a552-481ab8925e48");
+
+/// Corners of the quad every particle is an instance of, in triangle strip order.
+const QUAD_CORNERS: [Vec2; 4] = [
+    Vec2::new(-1., -1.),
+    Vec2::new(1., -1.),
+    Vec2::new(-1., 1.),
+    Vec2::new(1., 1.),
+];
+
+/// One particle as an instance of the quad. The vertex shader turns the quad to the camera.
+#[derive(ShaderType, Clone, Copy, PartialEq, Debug, Default)]
+pub struct ParticleInstance {
+    /// Center of the billboard, and its side in `w`.
+    pub position_size: Vec4,
+    /// Part of the texture shown, as `[min_u, min_v, max_u, max_v]`.
+    pub uvs: Vec4,
+    /// Linear color multiplied with the texture, and layer of the texture array in `w`.
+    pub color_layer: Vec4,
+}
+
+/// Particles drawn with one texture array in one instanced draw call.
+///
+/// They are extracted only when they change, and not at all when there are none.
+#[derive(Component, Clone, Default, Debug)]
+pub struct ParticleInstances {
+    pub texture: Handle<Image>,
+    pub instances: Vec<ParticleInstance>,
+}
+
+impl ExtractComponent for ParticleInstances {
+    type QueryData = &'static Self;
+    type QueryFilter = Changed<Self>;
+    type Out = Self;
+
+    fn extract_component(item: QueryItem<'_, Self::QueryData>) -> Option<Self> {
+        (!item.instances.is_empty()).then(|| item.clone())
+    }
+}
+
+/// Registers the pipeline that draws [`ParticleInstances`] and its shader.
+pub struct ParticleRenderPlugin;
+
+impl Plugin for ParticleRenderPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            PARTICLE_SHADER_HANDLE,
+            "shaders/particle.wgsl",
+            Shader::from_wgsl
+        );
+        app.add_plugins(ExtractComponentPlugin::<ParticleInstances>::default());
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_command::<Transparent3d, DrawParticles>()
+            .init_resource::<SpecializedRenderPipelines<ParticlePipeline>>()
+            .add_systems(
+                Render,
+                (
+                    queue_particles.in_set(RenderSet::Queue),
+                    prepare_particles.in_set(RenderSet::PrepareBindGroups),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.init_resource::<ParticlePipeline>();
+        }
+    }
+}
+
+/// Pipeline of particles, with the quad they are instances of.
+#[derive(Resource)]
+pub struct ParticlePipeline {
+    mesh_pipeline: MeshPipeline,
+    texture_layout: BindGroupLayout,
+    quad: Buffer,
+}
+
+impl FromWorld for ParticlePipeline {
+    fn from_world(world: &mut World) -> Self {
+        let device = world.resource::<RenderDevice>();
+        let texture_layout = device.create_bind_group_layout(
+            "particle_texture_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d_array(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                ),
+            ),
+        );
+        let quad = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("particle_quad_buffer"),
+            contents: &encode(&QUAD_CORNERS),
+            usage: BufferUsages::VERTEX,
+        });
+
+        Self {
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+            texture_layout,
+            quad,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for ParticlePipeline {
+    type Key = MeshPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let format = if key.contains(MeshPipelineKey::HDR) {
+            ViewTarget::TEXTURE_FORMAT_HDR
+        } else {
+            TextureFormat::bevy_default()
+        };
+        let instance_attributes = [1, 2, 3].map(|location| VertexAttribute {
+            format: VertexFormat::Float32x4,
+            offset: (location - 1) * VertexFormat::Float32x4.size(),
+            shader_location: location as u32,
+        });
+
+        RenderPipelineDescriptor {
+            label: Some("particle_pipeline".into()),
+            layout: vec![
+                self.mesh_pipeline.get_view_layout(key.into()).clone(),
+                self.texture_layout.clone(),
+            ],
+            push_constant_ranges: Vec::new(),
+            vertex: VertexState {
+                shader: PARTICLE_SHADER_HANDLE,
+                shader_defs: Vec::new(),
+                entry_point: "vertex".into(),
+                buffers: vec![
+                    VertexBufferLayout::from_vertex_formats(VertexStepMode::Vertex, [
+                        VertexFormat::Float32x2,
+                    ]),
+                    VertexBufferLayout {
+                        array_stride: ParticleInstance::min_size().get(),
+                        step_mode: VertexStepMode::Instance,
+                        attributes: instance_attributes.to_vec(),
+                    },
+                ],
+            },
+            fragment: Some(FragmentState {
+                shader: PARTICLE_SHADER_HANDLE,
+                shader_defs: Vec::new(),
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleStrip,
+                cull_mode: None,
+                ..default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: CORE_3D_DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::GreaterEqual,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState {
+                count: key.msaa_samples(),
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            zero_initialize_workgroup_memory: false,
+        }
+    }
+}
+
+/// Bytes of `value` laid out like in a GPU buffer.
+fn encode<T: ShaderType + bevy::render::render_resource::encase::internal::WriteInto>(
+    value: &T,
+) -> Vec<u8> {
+    let mut buffer = StorageBuffer::new(Vec::new());
+    buffer
+        .write(value)
+        .expect("Particle data always fits into a Vec");
+    buffer.into_inner()
+}
+
+/// Instance buffer and texture bind group of [`ParticleInstances`] in the render world.
+#[derive(Component)]
+pub struct PreparedParticles {
+    instances: Buffer,
+    count: u32,
+    textures: BindGroup,
+}
+
+/// Uploads particle instances that were extracted in this frame.
+fn prepare_particles(
+    mut commands: Commands,
+    device: Res<RenderDevice>,
+    pipeline: Res<ParticlePipeline>,
+    images: Res<RenderAssets<GpuImage>>,
+    particles_q: Query<(Entity, Ref<ParticleInstances>, Has<PreparedParticles>)>,
+) {
+    for (entity, particles, prepared) in particles_q.iter() {
+        if prepared && !particles.is_changed() {
+            continue;
+        }
+        let Some(image) = images.get(&particles.texture) else {
+            continue;
+        };
+
+        let instances = device.create_buffer_with_data(&BufferInitDescriptor {
+            label: Some("particle_instance_buffer"),
+            contents: &encode(&particles.instances),
+            usage: BufferUsages::VERTEX,
+        });
+        let textures = device.create_bind_group(
+            "particle_texture_bind_group",
+            &pipeline.texture_layout,
+            &BindGroupEntries::sequential((&image.texture_view, &image.sampler)),
+        );
+        commands.entity(entity).insert(PreparedParticles {
+            instances,
+            count: particles.instances.len() as u32,
+            textures,
+        });
+    }
+}
+
+/// Adds particles to the transparent phase of every 3D view.
+fn queue_particles(
+    draw_functions: Res<DrawFunctions<Transparent3d>>,
+    pipeline: Res<ParticlePipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<ParticlePipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    mut phases: ResMut<ViewSortedRenderPhases<Transparent3d>>,
+    particles_q: Query<(Entity, &MainEntity), With<ParticleInstances>>,
+    view_q: Query<(
+        &ExtractedView,
+        &Msaa,
+        (
+            Has<NormalPrepass>,
+            Has<DepthPrepass>,
+            Has<MotionVectorPrepass>,
+            Has<DeferredPrepass>,
+            Has<OrderIndependentTransparencySettings>,
+        ),
+    )>,
+) {
+    let draw_function = draw_functions.read().id::<DrawParticles>();
+    for (view, msaa, (normal, depth, motion_vector, deferred, oit)) in view_q.iter() {
+        let Some(phase) = phases.get_mut(&view.retained_view_entity) else {
+            continue;
+        };
+
+        // The view bind group is shared with meshes, so its layout depends on the same flags.
+        let mut key = MeshPipelineKey::from_msaa_samples(msaa.samples())
+            | MeshPipelineKey::from_hdr(view.hdr);
+        for (enabled, flag) in [
+            (normal, MeshPipelineKey::NORMAL_PREPASS),
+            (depth, MeshPipelineKey::DEPTH_PREPASS),
+            (motion_vector, MeshPipelineKey::MOTION_VECTOR_PREPASS),
+            (deferred, MeshPipelineKey::DEFERRED_PREPASS),
+            (oit, MeshPipelineKey::OIT_ENABLED),
+        ] {
+            key.set(flag, enabled);
+        }
+        let pipeline = pipelines.specialize(&pipeline_cache, &pipeline, key);
+
+        for (entity, main_entity) in particles_q.iter() {
+            phase.add(Transparent3d {
+                entity: (entity, *main_entity),
+                pipeline,
+                draw_function,
+                distance: 0.,
+                batch_range: 0..1,
+                extra_index: PhaseItemExtraIndex::None,
+                indexed: false,
+            });
+        }
+    }
+}
+
+type DrawParticles = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    DrawParticleInstances,
+);
+
+/// Draws the quad once for every particle.
+pub struct DrawParticleInstances;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawParticleInstances {
+    type Param = SRes<ParticlePipeline>;
+    type ViewQuery = ();
+    type ItemQuery = Read<PreparedParticles>;
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        prepared: Option<&'w PreparedParticles>,
+        pipeline: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(prepared) = prepared else {
+            return RenderCommandResult::Skip;
+        };
+
+        pass.set_bind_group(1, &prepared.textures, &[]);
+        pass.set_vertex_buffer(0, pipeline.into_inner().quad.slice(..));
+        pass.set_vertex_buffer(1, prepared.instances.slice(..));
+        pass.draw(0..QUAD_CORNERS.len() as u32, 0..prepared.count);
+        RenderCommandResult::Success
+    }
+}
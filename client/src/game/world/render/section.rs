@@ -0,0 +1,374 @@
+use std::sync::Arc;
+
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::mesh::Indices;
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use librecraft_shared::chunk::{ChunkPos, SECTION_COUNT, SECTION_SIZE};
+
+use super::block::{BlockShapes, RenderLayer};
+use super::material::{BlockMaterial, BlockTextureExtension};
+use super::mesher::{
+    ChunkNeighbourhood, MeshingMode, NEIGHBOUR_OFFSETS, SectionMeshes, TranslucentFaces,
+    mesh_section,
+};
+use super::model::{BlockState, BlockStateHandles};
+use super::texture::{BlockTextureArray, BlockTextureLoader, pack_textures};
+use super::tint::{BiomeColormaps, ColormapLoader};
+use super::visibility::{FaceConnections, SectionCulling, SectionGraph};
+use crate::game::world::chunk::{ChunkLoaded, ChunkManager};
+use crate::settings::{Settings, SettingsUpdated};
+
+/// How many chunks can be meshed at the same time.
+const MAX_MESH_TASKS: usize = 8;
+
+/// Mesh entity of one render layer of a chunk section, child of chunk entity.
+#[derive(Component, Debug)]
+pub struct ChunkSection {
+    pub pos: ChunkPos,
+    pub index: usize,
+    pub layer: RenderLayer,
+}
+
+/// Materials shared by all chunk section meshes, one per [`RenderLayer`].
+///
+/// Inserted once block textures are packed, chunks aren't meshed before that.
+#[derive(Resource)]
+pub struct ChunkMaterials([Handle<BlockMaterial>; 3]);
+
+impl ChunkMaterials {
+    pub fn get(&self, layer: RenderLayer) -> &Handle<BlockMaterial> {
+        &self.0[layer as usize]
+    }
+}
+
+/// Chunks that need to be meshed again.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct RemeshQueue(pub HashSet<ChunkPos>);
+
+/// Meshes of every section of a chunk, with face connections of the sections.
+type ChunkMeshes = Vec<(SectionMeshes, FaceConnections)>;
+
+/// Chunks being meshed on [`AsyncComputeTaskPool`], at most one task per chunk.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct MeshTasks(pub HashMap<ChunkPos, Task<ChunkMeshes>>);
+
+/// Block shapes and colormaps shared by meshing tasks, copied again only when they change.
+pub struct MeshingContext {
+    shapes: BlockShapes,
+    colormaps: BiomeColormaps,
+}
+
+/// Starts loading blockstates and colormaps, and setups meshing resources.
+pub fn setup_chunk_rendering(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(BlockStateHandles::new(&assets));
+    commands.insert_resource(ColormapLoader::new(&assets));
+    commands.init_resource::<RemeshQueue>();
+    commands.init_resource::<MeshTasks>();
+    commands.init_resource::<MeshingMode>();
+    commands.init_resource::<SectionGraph>();
+    commands.init_resource::<SectionCulling>();
+}
+
+/// Builds block shapes once blockstates are loaded, then starts loading their textures.
+pub fn build_block_shapes(
+    mut commands: Commands,
+    handles: Res<BlockStateHandles>,
+    assets: Res<AssetServer>,
+    blockstates: Res<Assets<BlockState>>,
+) {
+    if !handles.is_finished(&assets) {
+        return;
+    }
+
+    let shapes = handles.shapes(&blockstates);
+    commands.insert_resource(BlockTextureLoader::new(&assets, &shapes));
+    commands.insert_resource(shapes);
+    commands.remove_resource::<BlockStateHandles>();
+}
+
+/// Packs block textures once they are loaded, then setups chunk material.
+pub fn build_block_textures(
+    mut commands: Commands,
+    loader: Res<BlockTextureLoader>,
+    mut shapes: ResMut<BlockShapes>,
+    assets: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<BlockMaterial>>,
+) {
+    if !loader.is_finished(&assets) {
+        return;
+    }
+
+    let packed = pack_textures(&loader.textures(&images));
+    info!(
+        "Block textures were packed into {} layers of {}x{}.",
+        packed.layers.len(),
+        packed.resolution,
+        packed.resolution
+    );
+    let textures =
+        BlockTextureArray::new(images.add(packed.image), packed.resolution, packed.layers);
+    shapes.resolve_layers(&textures);
+
+    commands.insert_resource(ChunkMaterials(RenderLayer::ALL.map(|layer| {
+        materials.add(BlockMaterial {
+            base: StandardMaterial {
+                perceptual_roughness: 1.,
+                reflectance: 0.,
+                alpha_mode: match layer {
+                    RenderLayer::Opaque => AlphaMode::Opaque,
+                    RenderLayer::Cutout => AlphaMode::Mask(0.5),
+                    RenderLayer::Translucent => AlphaMode::Blend,
+                },
+                ..default()
+            },
+            extension: BlockTextureExtension {
+                textures: textures.image.clone(),
+            },
+        })
+    })));
+    commands.insert_resource(textures);
+    commands.remove_resource::<BlockTextureLoader>();
+}
+
+/// Reads biome colormaps once they are loaded.
+pub fn build_colormaps(
+    mut commands: Commands,
+    loader: Res<ColormapLoader>,
+    assets: Res<AssetServer>,
+    images: Res<Assets<Image>>,
+) {
+    if !loader.is_finished(&assets) {
+        return;
+    }
+
+    commands.insert_resource(loader.colormaps(&images));
+    commands.remove_resource::<ColormapLoader>();
+}
+
+/// Switches meshing mode by pressing F6 and meshes all loaded chunks again, for comparison.
+pub fn toggle_meshing_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<MeshingMode>,
+    mut queue: ResMut<RemeshQueue>,
+    manager: Res<ChunkManager>,
+) {
+    if keys.just_pressed(KeyCode::F6) {
+        *mode = match *mode {
+            MeshingMode::Naive => MeshingMode::Greedy,
+            MeshingMode::Greedy => MeshingMode::Naive,
+        };
+        queue.extend(manager.loaded_positions());
+        info!("Meshing mode: {:?}.", *mode);
+    }
+}
+
+/// Switches ambient occlusion by pressing F7 and meshes all loaded chunks again.
+pub fn toggle_ambient_occlusion(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut settings_writer: EventWriter<SettingsUpdated>,
+    mut queue: ResMut<RemeshQueue>,
+    manager: Res<ChunkManager>,
+) {
+    if keys.just_pressed(KeyCode::F7) {
+        settings.ambient_occlusion = !settings.ambient_occlusion;
+        queue.extend(manager.loaded_positions());
+        debug!("Ambient occlusion: {}", settings.ambient_occlusion);
+
+        settings_writer.write(SettingsUpdated {
+            settings: settings.clone(),
+        });
+    }
+}
+
+/// Queues loaded chunks and their neighbours, as their border faces could be uncovered.
+pub fn queue_loaded_chunks(
+    mut loaded_reader: EventReader<ChunkLoaded>,
+    mut queue: ResMut<RemeshQueue>,
+    manager: Res<ChunkManager>,
+) {
+    for ev in loaded_reader.read() {
+        queue.insert(ev.pos);
+        for (dx, dz) in NEIGHBOUR_OFFSETS {
+            let pos = ChunkPos::new(ev.pos.x + dx, ev.pos.z + dz);
+            if manager.chunk(pos).is_some() {
+                queue.insert(pos);
+            }
+        }
+    }
+}
+
+/// Starts meshing queued chunks closest to the camera on [`AsyncComputeTaskPool`], with copies
+/// of them and their neighbours.
+///
+/// Chunks that are being meshed stay queued until their task finishes, so that meshes are
+/// replaced in order.
+pub fn remesh_chunks(
+    mut queue: ResMut<RemeshQueue>,
+    mut tasks: ResMut<MeshTasks>,
+    mut context: Local<Option<Arc<MeshingContext>>>,
+    manager: Res<ChunkManager>,
+    shapes: Res<BlockShapes>,
+    colormaps: Res<BiomeColormaps>,
+    mode: Res<MeshingMode>,
+    settings: Res<Settings>,
+) {
+    if queue.is_empty() || tasks.len() >= MAX_MESH_TASKS {
+        return;
+    }
+
+    // Chunks that were unloaded meanwhile are dropped.
+    queue.retain(|pos| manager.chunk(*pos).is_some());
+
+    let center = manager.center().unwrap_or_default();
+    let mut positions: Vec<ChunkPos> = queue
+        .iter()
+        .filter(|pos| !tasks.contains_key(*pos))
+        .copied()
+        .collect();
+    positions.sort_unstable_by_key(|pos| (pos.x - center.x).pow(2) + (pos.z - center.z).pow(2));
+
+    if context.is_none() || shapes.is_changed() || colormaps.is_changed() {
+        *context = Some(Arc::new(MeshingContext {
+            shapes: shapes.clone(),
+            colormaps: colormaps.clone(),
+        }));
+    }
+    let Some(context) = context.clone() else {
+        return;
+    };
+
+    let pool = AsyncComputeTaskPool::get();
+    let free_count = MAX_MESH_TASKS - tasks.len();
+    for pos in positions.into_iter().take(free_count) {
+        queue.remove(&pos);
+        let Some(chunk) = manager.chunk(pos).cloned() else {
+            continue;
+        };
+        let neighbours = NEIGHBOUR_OFFSETS.map(|(dx, dz)| {
+            manager
+                .chunk(ChunkPos::new(pos.x + dx, pos.z + dz))
+                .cloned()
+        });
+
+        let context = context.clone();
+        let (mode, ambient_occlusion) = (*mode, settings.ambient_occlusion);
+        let task = pool.spawn(async move {
+            let chunks = ChunkNeighbourhood {
+                center: &chunk,
+                neighbours: neighbours.each_ref().map(Option::as_ref),
+            };
+            (0..SECTION_COUNT)
+                .map(|index| {
+                    let meshes = mesh_section(
+                        &chunks,
+                        &context.shapes,
+                        &context.colormaps,
+                        index,
+                        mode,
+                        ambient_occlusion,
+                    );
+                    (meshes, FaceConnections::of(chunk.section(index)))
+                })
+                .collect()
+        });
+        tasks.insert(pos, task);
+    }
+}
+
+/// Replaces section entities of chunks whose meshing tasks finished.
+///
+/// Translucent meshes are sorted for the current camera position right away, and face
+/// connections of sections are updated for culling.
+pub fn receive_mesh_tasks(
+    mut commands: Commands,
+    mut tasks: ResMut<MeshTasks>,
+    mut graph: ResMut<SectionGraph>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    manager: Res<ChunkManager>,
+    materials: Res<ChunkMaterials>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let mut finished: Vec<(ChunkPos, ChunkMeshes)> = Vec::new();
+    tasks.retain(|pos, task| match block_on(poll_once(task)) {
+        Some(chunk_meshes) => {
+            finished.push((*pos, chunk_meshes));
+            false
+        },
+        None => true,
+    });
+    if finished.is_empty() {
+        return;
+    }
+
+    let eye = camera_q
+        .single()
+        .map(|transform| transform.translation())
+        .unwrap_or_default();
+
+    for (pos, chunk_meshes) in finished {
+        // Chunk was unloaded meanwhile.
+        let Some(entity) = manager.entity(pos) else {
+            continue;
+        };
+
+        commands.entity(entity).despawn_related::<Children>();
+        for (index, (section_meshes, connections)) in chunk_meshes.into_iter().enumerate() {
+            graph.insert((pos, index), connections);
+            let origin = Vec3::new(
+                pos.min_block_x() as f32,
+                (index * SECTION_SIZE) as f32,
+                pos.min_block_z() as f32,
+            );
+            for (layer, mut mesh) in section_meshes.into_layers() {
+                let translucent = (layer == RenderLayer::Translucent).then(|| {
+                    let mut faces = mesh.translucent_faces();
+                    mesh.indices = faces.sorted_indices(eye - origin);
+                    faces.sorted_from = Some(eye.floor().as_ivec3());
+                    faces
+                });
+
+                let mut section = commands.spawn((
+                    ChunkSection { pos, index, layer },
+                    Mesh3d(meshes.add(mesh.into_mesh())),
+                    MeshMaterial3d(materials.get(layer).clone()),
+                    Transform::from_xyz(0., (index * SECTION_SIZE) as f32, 0.),
+                ));
+                if let Some(faces) = translucent {
+                    section.insert(faces);
+                }
+                let section = section.id();
+                commands.entity(entity).add_child(section);
+            }
+        }
+    }
+}
+
+/// Sorts translucent faces back to front again once the camera moves to another block.
+pub fn sort_translucent_faces(
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+    mut section_q: Query<(&GlobalTransform, &Mesh3d, &mut TranslucentFaces)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+    let eye = camera.translation();
+    let eye_block = eye.floor().as_ivec3();
+
+    for (transform, mesh, mut faces) in &mut section_q {
+        if faces.sorted_from == Some(eye_block) {
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {
+            continue;
+        };
+
+        mesh.insert_indices(Indices::U32(
+            faces.sorted_indices(eye - transform.translation()),
+        ));
+        faces.sorted_from = Some(eye_block);
+    }
+}
@@ -0,0 +1,42 @@
+// Fragment shader of chunk sections. Base color is taken from the layer of block texture array
+// stored in the first component of the second UV set, the rest is the standard PBR pipeline.
+// The second component scrolls texture over time, for liquids.
+
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    forward_io::{VertexOutput, FragmentOutput},
+    mesh_view_bindings::globals,
+}
+
+// Seconds it takes a scrolling texture to move by its whole height.
+const SCROLL_PERIOD: f32 = 8.0;
+
+@group(2) @binding(100)
+var block_textures: texture_2d_array<f32>;
+@group(2) @binding(101)
+var block_sampler: sampler;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+#ifdef VERTEX_UVS_B
+    let layer = i32(in.uv_b.x + 0.5);
+    let scroll = in.uv_b.y;
+#else
+    let layer = 0;
+    let scroll = 0.0;
+#endif
+    let uv = in.uv - vec2(0.0, fract(globals.time / SCROLL_PERIOD) * scroll);
+    pbr_input.material.base_color *= textureSample(block_textures, block_sampler, uv, layer);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
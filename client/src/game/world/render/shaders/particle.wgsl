@@ -0,0 +1,55 @@
+// Instanced particles. Every particle is an instance of the same quad, which is turned to face
+// the camera, moved to the particle and textured with its part of a texture array layer.
+
+#import bevy_render::view::View
+
+@group(0) @binding(0)
+var<uniform> view: View;
+
+@group(1) @binding(0)
+var particle_textures: texture_2d_array<f32>;
+@group(1) @binding(1)
+var particle_sampler: sampler;
+
+struct Vertex {
+    // Corner of the quad, from -1 to 1 on both axes.
+    @location(0) corner: vec2<f32>,
+    // Center of the particle, and its side in `w`.
+    @location(1) position_size: vec4<f32>,
+    // Part of the texture shown, as min u, min v, max u and max v.
+    @location(2) uvs: vec4<f32>,
+    // Linear color multiplied with the texture, and texture layer in `w`.
+    @location(3) color_layer: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) color: vec3<f32>,
+    @location(2) @interpolate(flat) layer: i32,
+}
+
+@vertex
+fn vertex(in: Vertex) -> VertexOutput {
+    let right = view.world_from_view[0].xyz;
+    let up = view.world_from_view[1].xyz;
+    let offset = (right * in.corner.x + up * in.corner.y) * in.position_size.w * 0.5;
+
+    // Texture coordinates go down, while the corner goes up.
+    let t = in.corner * 0.5 + 0.5;
+    var out: VertexOutput;
+    out.clip_position = view.clip_from_world * vec4(in.position_size.xyz + offset, 1.0);
+    out.uv = vec2(mix(in.uvs.x, in.uvs.z, t.x), mix(in.uvs.w, in.uvs.y, t.y));
+    out.color = in.color_layer.rgb;
+    out.layer = i32(in.color_layer.w + 0.5);
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(particle_textures, particle_sampler, in.uv, in.layer);
+    if color.a < 0.5 {
+        discard;
+    }
+    return vec4(color.rgb * in.color, 1.0);
+}
@@ -0,0 +1,369 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension,
+};
+
+use super::block::BlockShapes;
+
+/// Lowest resolution of the resource pack, smaller textures are scaled up.
+pub const MIN_TEXTURE_RESOLUTION: u32 = 32;
+/// Highest resolution of the resource pack, bigger textures are scaled down.
+pub const MAX_TEXTURE_RESOLUTION: u32 = 128;
+/// Name of the generated texture used in place of textures that can't be loaded.
+pub const MISSING_TEXTURE: &str = "missing";
+
+/// Bytes per pixel of `Rgba8UnormSrgb`.
+const PIXEL_SIZE: usize = 4;
+/// Side of one square of the missing texture checkerboard, in pixels.
+const MISSING_CHECKER_SIZE: u32 = 8;
+
+/// Path of block texture `name` in [`AssetServer`].
+pub fn block_texture_path(name: &str) -> String {
+    format!("{name}.png")
+}
+
+/// Block textures that are being loaded, before they are packed into [`BlockTextureArray`].
+#[derive(Resource, Debug)]
+pub struct BlockTextureLoader {
+    handles: Vec<(String, Handle<Image>)>,
+}
+
+impl BlockTextureLoader {
+    /// Starts loading every texture used by block `shapes`.
+    pub fn new(assets: &AssetServer, shapes: &BlockShapes) -> Self {
+        Self {
+            handles: shapes
+                .texture_names()
+                .into_iter()
+                .filter(|name| *name != MISSING_TEXTURE)
+                .map(|name| (name.to_owned(), assets.load(block_texture_path(name))))
+                .collect(),
+        }
+    }
+
+    /// Whether every texture has either loaded or failed to load.
+    pub fn is_finished(&self, assets: &AssetServer) -> bool {
+        self.handles.iter().all(|(_, handle)| {
+            let state = assets.load_state(handle.id());
+            state.is_loaded() || state.is_failed()
+        })
+    }
+
+    /// Names of textures together with their images, `None` for those that failed to load.
+    pub fn textures<'a>(&'a self, images: &'a Assets<Image>) -> Vec<(&'a str, Option<&'a Image>)> {
+        self.handles
+            .iter()
+            .map(|(name, handle)| (name.as_str(), images.get(handle)))
+            .collect()
+    }
+}
+
+/// Block textures packed into layers of one `texture_2d_array`.
+///
+/// Every texture has a layer of its own, so mipmaps never bleed between textures and a texture
+/// can repeat over merged faces. Layer 0 is the missing texture.
+#[derive(Resource, Debug, Clone)]
+pub struct BlockTextureArray {
+    pub image: Handle<Image>,
+    resolution: u32,
+    layers: HashMap<String, u32>,
+}
+
+impl BlockTextureArray {
+    pub fn new(image: Handle<Image>, resolution: u32, layers: HashMap<String, u32>) -> Self {
+        Self {
+            image,
+            resolution,
+            layers,
+        }
+    }
+
+    /// Side of every layer in pixels.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    pub fn layer_count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Layer of texture `name`, or layer of the missing texture if it wasn't packed.
+    pub fn layer(&self, name: &str) -> u32 {
+        self.layers.get(name).copied().unwrap_or(0)
+    }
+}
+
+/// Result of [`pack_textures`].
+#[derive(Debug)]
+pub struct PackedTextures {
+    pub image: Image,
+    pub resolution: u32,
+    pub layers: HashMap<String, u32>,
+}
+
+/// Packs `textures` into layers of an array image with full mip chain.
+///
+/// All layers share the resolution of the largest texture, rounded up to a power of two and
+/// clamped to [`MIN_TEXTURE_RESOLUTION`]..=[`MAX_TEXTURE_RESOLUTION`]. Textures taller than wide
+/// are animation strips, only their first frame is taken. Textures that are `None` or can't be
+/// read are replaced by the missing texture.
+pub fn pack_textures(textures: &[(&str, Option<&Image>)]) -> PackedTextures {
+    let pixels: Vec<(&str, Option<RgbaImage>)> = textures
+        .iter()
+        .map(|(name, image)| {
+            let rgba = image.and_then(RgbaImage::from_image);
+            if rgba.is_none() {
+                warn!("Block texture {name} can't be read, it's replaced by missing texture.");
+            }
+            (*name, rgba)
+        })
+        .collect();
+
+    let resolution = pixels
+        .iter()
+        .filter_map(|(_, image)| image.as_ref().map(|i| i.width))
+        .max()
+        .unwrap_or(MIN_TEXTURE_RESOLUTION)
+        .next_power_of_two()
+        .clamp(MIN_TEXTURE_RESOLUTION, MAX_TEXTURE_RESOLUTION);
+    let mip_level_count = resolution.ilog2() + 1;
+
+    let missing = RgbaImage::missing(resolution);
+    let mut layers = HashMap::new();
+    layers.insert(MISSING_TEXTURE.to_owned(), 0);
+    let mut data = missing.with_mipmaps(mip_level_count);
+
+    for (name, image) in pixels {
+        let Some(image) = image else {
+            continue;
+        };
+        layers.insert(name.to_owned(), layers.len() as u32);
+        data.extend(image.resized(resolution).with_mipmaps(mip_level_count));
+    }
+
+    let mut image = Image::new_uninit(
+        Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: layers.len() as u32,
+        },
+        TextureDimension::D2,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    // Layers go one after another, each with all of its mip levels.
+    image.data = Some(data);
+    image.texture_descriptor.mip_level_count = mip_level_count;
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("block_textures".to_owned()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Nearest,
+        min_filter: ImageFilterMode::Nearest,
+        mipmap_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+
+    PackedTextures {
+        image,
+        resolution,
+        layers,
+    }
+}
+
+/// Square RGBA8 image.
+struct RgbaImage {
+    width: u32,
+    data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Top square of `image`, or `None` if it's empty or has unsupported format.
+    fn from_image(image: &Image) -> Option<Self> {
+        let converted;
+        let image = if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb {
+            image
+        } else {
+            converted = image.convert(TextureFormat::Rgba8UnormSrgb)?;
+            &converted
+        };
+
+        let width = image.width();
+        let size = width as usize * width as usize * PIXEL_SIZE;
+        let data = image.data.as_ref()?;
+        if width == 0 || image.height() < width || data.len() < size {
+            return None;
+        }
+
+        Some(Self {
+            width,
+            data: data[..size].to_vec(),
+        })
+    }
+
+    /// Magenta and black checkerboard.
+    fn missing(width: u32) -> Self {
+        let mut data = Vec::with_capacity(width as usize * width as usize * PIXEL_SIZE);
+        for y in 0..width {
+            for x in 0..width {
+                let odd = (x / MISSING_CHECKER_SIZE + y / MISSING_CHECKER_SIZE) & 1 == 1;
+                data.extend_from_slice(if odd {
+                    &[0, 0, 0, 255]
+                } else {
+                    &[248, 0, 248, 255]
+                });
+            }
+        }
+        Self { width, data }
+    }
+
+    /// Nearest neighbour scaling, to keep pixel art sharp.
+    fn resized(self, width: u32) -> Self {
+        if self.width == width {
+            return self;
+        }
+
+        let mut data = Vec::with_capacity(width as usize * width as usize * PIXEL_SIZE);
+        for y in 0..width {
+            for x in 0..width {
+                let sx = (x * self.width / width) as usize;
+                let sy = (y * self.width / width) as usize;
+                let start = (sy * self.width as usize + sx) * PIXEL_SIZE;
+                data.extend_from_slice(&self.data[start..start + PIXEL_SIZE]);
+            }
+        }
+        Self { width, data }
+    }
+
+    /// Half sized image, every pixel averages 2x2 pixels.
+    ///
+    /// Colors are weighted by alpha, so fully transparent pixels don't darken edges of cutouts.
+    fn downsampled(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let mut data = Vec::with_capacity(width as usize * width as usize * PIXEL_SIZE);
+        for y in 0..width {
+            for x in 0..width {
+                let mut color = [0u32; 3];
+                let mut alpha = 0u32;
+                let mut plain = [0u32; 3];
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let sx = (x * 2 + dx).min(self.width - 1) as usize;
+                    let sy = (y * 2 + dy).min(self.width - 1) as usize;
+                    let start = (sy * self.width as usize + sx) * PIXEL_SIZE;
+                    let pixel = &self.data[start..start + PIXEL_SIZE];
+                    for c in 0..3 {
+                        color[c] += pixel[c] as u32 * pixel[3] as u32;
+                        plain[c] += pixel[c] as u32;
+                    }
+                    alpha += pixel[3] as u32;
+                }
+
+                for c in 0..3 {
+                    data.push(match alpha {
+                        0 => (plain[c] / 4) as u8,
+                        _ => (color[c] / alpha) as u8,
+                    });
+                }
+                data.push((alpha / 4) as u8);
+            }
+        }
+        Self { width, data }
+    }
+
+    /// Bytes of this image followed by `mip_level_count - 1` mip levels.
+    fn with_mipmaps(self, mip_level_count: u32) -> Vec<u8> {
+        let mut level = self;
+        let mut data = level.data.clone();
+        for _ in 1..mip_level_count {
+            level = level.downsampled();
+            data.extend_from_slice(&level.data);
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(width: u32, height: u32, pixel: [u8; 4]) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &pixel,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    /// Size in bytes of one layer with all its mip levels.
+    fn layer_size(resolution: u32) -> usize {
+        (0..=resolution.ilog2())
+            .map(|level| (resolution >> level).pow(2) as usize * PIXEL_SIZE)
+            .sum()
+    }
+
+    #[test]
+    fn layers_share_resolution() {
+        let small = filled(16, 16, [255, 0, 0, 255]);
+        let big = filled(64, 64, [0, 255, 0, 255]);
+        let strip = filled(32, 96, [0, 0, 255, 255]);
+        let packed = pack_textures(&[
+            ("small", Some(&small)),
+            ("big", Some(&big)),
+            ("strip", Some(&strip)),
+            ("absent", None),
+        ]);
+
+        assert_eq!(packed.resolution, 64);
+        assert_eq!(packed.image.texture_descriptor.mip_level_count, 7);
+        assert_eq!(packed.image.texture_descriptor.array_layer_count(), 4);
+        assert_eq!(packed.layers[MISSING_TEXTURE], 0);
+        assert!(!packed.layers.contains_key("absent"));
+        assert_eq!(packed.image.data.unwrap().len(), layer_size(64) * 4);
+    }
+
+    #[test]
+    fn resolution_is_clamped() {
+        let tiny = filled(8, 8, [255; 4]);
+        assert_eq!(pack_textures(&[("tiny", Some(&tiny))]).resolution, 32);
+
+        let huge = filled(512, 512, [255; 4]);
+        assert_eq!(pack_textures(&[("huge", Some(&huge))]).resolution, 128);
+    }
+
+    #[test]
+    fn mipmaps_do_not_bleed() {
+        let red = filled(32, 32, [255, 0, 0, 255]);
+        let blue = filled(32, 32, [0, 0, 255, 255]);
+        let packed = pack_textures(&[("red", Some(&red)), ("blue", Some(&blue))]);
+        let data = packed.image.data.unwrap();
+
+        let size = layer_size(32);
+        for (layer, color) in [(1, [255, 0, 0, 255]), (2, [0, 0, 255, 255])] {
+            let layer_data = &data[layer * size..(layer + 1) * size];
+            assert!(layer_data.chunks(PIXEL_SIZE).all(|pixel| pixel == color));
+        }
+    }
+
+    #[test]
+    fn transparent_pixels_do_not_darken_mipmaps() {
+        let mut image = RgbaImage {
+            width: 2,
+            data: vec![0; 2 * 2 * PIXEL_SIZE],
+        };
+        image.data[..PIXEL_SIZE].copy_from_slice(&[200, 100, 50, 255]);
+        assert_eq!(image.downsampled().data, [200, 100, 50, 63]);
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use librecraft_shared::biome::Biome;
+use librecraft_shared::block::Block;
+
+/// Path of grass colormap in [`AssetServer`].
+pub const GRASS_COLORMAP_PATH: &str = "colormap/grass.png";
+/// Path of foliage colormap in [`AssetServer`].
+pub const FOLIAGE_COLORMAP_PATH: &str = "colormap/foliage.png";
+
+/// Grass color of plains, used when grass colormap can't be loaded.
+const DEFAULT_GRASS_COLOR: Srgba = Srgba::rgb(0.569, 0.741, 0.349);
+/// Foliage color of plains, used when foliage colormap can't be loaded.
+const DEFAULT_FOLIAGE_COLOR: Srgba = Srgba::rgb(0.467, 0.671, 0.184);
+/// Water has the same color in every biome so far.
+const WATER_COLOR: Srgba = Srgba::rgb(0.247, 0.463, 0.894);
+
+/// Which biome color tints faces of a block that have `tintindex`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BiomeTint {
+    Grass,
+    Foliage,
+    Water,
+}
+
+impl BiomeTint {
+    /// Tint of `block`, `None` if its faces are never tinted.
+    pub fn of(block: Block) -> Option<Self> {
+        match block {
+            Block::Grass => Some(BiomeTint::Grass),
+            Block::Leaves => Some(BiomeTint::Foliage),
+            Block::Water => Some(BiomeTint::Water),
+            _ => None,
+        }
+    }
+}
+
+/// Triangle of colors indexed by biome temperature and downfall, like `colormap/grass.png`.
+///
+/// Colors are linear, so they can be multiplied with vertex colors and averaged.
+#[derive(Clone, Debug)]
+pub struct Colormap {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl Colormap {
+    /// Colormap of one color, for every temperature and downfall.
+    pub fn uniform(color: Srgba) -> Self {
+        let color = LinearRgba::from(color);
+        Self {
+            width: 1,
+            height: 1,
+            pixels: vec![[color.red, color.green, color.blue]],
+        }
+    }
+
+    /// Reads colormap from `image`, or `None` if it's empty or has unsupported format.
+    pub fn from_image(image: &Image) -> Option<Self> {
+        let converted;
+        let image = if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb {
+            image
+        } else {
+            converted = image.convert(TextureFormat::Rgba8UnormSrgb)?;
+            &converted
+        };
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let data = image.data.as_ref()?;
+        if width == 0 || height == 0 || data.len() < width * height * 4 {
+            return None;
+        }
+
+        let pixels = data
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|p| {
+                let color = LinearRgba::from(Srgba::rgb_u8(p[0], p[1], p[2]));
+                [color.red, color.green, color.blue]
+            })
+            .collect();
+        Some(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Color of climate with `temperature` and `downfall`, the same way as in Minecraft:
+    /// hot climates are on the left, and downfall is scaled by temperature, so that cold
+    /// climates are always dry.
+    pub fn sample(&self, temperature: f32, downfall: f32) -> [f32; 3] {
+        let temperature = temperature.clamp(0., 1.);
+        let downfall = downfall.clamp(0., 1.) * temperature;
+        let x = ((1. - temperature) * (self.width - 1) as f32) as usize;
+        let y = ((1. - downfall) * (self.height - 1) as f32) as usize;
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Colormaps of grass and foliage from resource pack.
+#[derive(Resource, Clone, Debug)]
+pub struct BiomeColormaps {
+    pub grass: Colormap,
+    pub foliage: Colormap,
+}
+
+impl Default for BiomeColormaps {
+    fn default() -> Self {
+        Self {
+            grass: Colormap::uniform(DEFAULT_GRASS_COLOR),
+            foliage: Colormap::uniform(DEFAULT_FOLIAGE_COLOR),
+        }
+    }
+}
+
+impl BiomeColormaps {
+    /// Linear color of `tint` in `biome`.
+    pub fn color(&self, tint: BiomeTint, biome: Biome) -> [f32; 3] {
+        let (temperature, downfall) = (biome.temperature(), biome.downfall());
+        match tint {
+            BiomeTint::Grass => self.grass.sample(temperature, downfall),
+            BiomeTint::Foliage => self.foliage.sample(temperature, downfall),
+            BiomeTint::Water => {
+                let color = LinearRgba::from(WATER_COLOR);
+                [color.red, color.green, color.blue]
+            },
+        }
+    }
+}
+
+/// Colormaps that are being loaded, before they become [`BiomeColormaps`].
+#[derive(Resource, Debug)]
+pub struct ColormapLoader {
+    grass: Handle<Image>,
+    foliage: Handle<Image>,
+}
+
+impl ColormapLoader {
+    pub fn new(assets: &AssetServer) -> Self {
+        Self {
+            grass: assets.load(GRASS_COLORMAP_PATH),
+            foliage: assets.load(FOLIAGE_COLORMAP_PATH),
+        }
+    }
+
+    /// Whether both colormaps have either loaded or failed to load.
+    pub fn is_finished(&self, assets: &AssetServer) -> bool {
+        [&self.grass, &self.foliage].iter().all(|handle| {
+            let state = assets.load_state(handle.id());
+            state.is_loaded() || state.is_failed()
+        })
+    }
+
+    /// Colormaps read from loaded images. Those that failed to load are replaced by colors of
+    /// plains.
+    pub fn colormaps(&self, images: &Assets<Image>) -> BiomeColormaps {
+        let read = |handle: &Handle<Image>, path: &str, default: Colormap| {
+            let colormap = images.get(handle).and_then(Colormap::from_image);
+            colormap.unwrap_or_else(|| {
+                warn!("Colormap {path} can't be loaded, plains color is used instead.");
+                default
+            })
+        };
+
+        let defaults = BiomeColormaps::default();
+        BiomeColormaps {
+            grass: read(&self.grass, GRASS_COLORMAP_PATH, defaults.grass),
+            foliage: read(&self.foliage, FOLIAGE_COLORMAP_PATH, defaults.foliage),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::RenderAssetUsages;
+    use bevy::render::render_resource::{Extent3d, TextureDimension};
+
+    use super::*;
+
+    #[test]
+    fn colormap_is_indexed_by_climate() {
+        // 2x2 colormap: hot and wet is red, hot and dry is green, cold is blue.
+        let (red, green, blue) = ([255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]);
+        let data = [red, blue, green, blue];
+        let image = Image::new(
+            Extent3d {
+                width: 2,
+                height: 2,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            data.concat(),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        let colormap = Colormap::from_image(&image).unwrap();
+
+        assert_eq!(colormap.sample(1., 1.), [1., 0., 0.]);
+        assert_eq!(colormap.sample(1., 0.), [0., 1., 0.]);
+        assert_eq!(colormap.sample(0., 1.), [0., 0., 1.]);
+        // Out of range climates are clamped.
+        assert_eq!(colormap.sample(2., 0.), [0., 1., 0.]);
+    }
+}
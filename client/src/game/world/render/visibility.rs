@@ -0,0 +1,288 @@
+use std::collections::VecDeque;
+
+use bevy::math::{Affine3A, Vec3A};
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::primitives::{Aabb, Frustum};
+use librecraft_shared::chunk::{
+    CHUNK_HEIGHT, ChunkPos, SECTION_COUNT, SECTION_SIZE, SECTION_VOLUME, Section, section_index,
+};
+
+use super::block::FaceDirection;
+use super::section::ChunkSection;
+use crate::game::world::chunk::ChunkManager;
+
+/// Which faces of a section can be seen from each other through its non-opaque blocks.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FaceConnections(u64);
+
+impl FaceConnections {
+    /// Every face sees every other, like in a section of air.
+    pub const ALL: FaceConnections = FaceConnections((1 << 36) - 1);
+    /// No face sees another, like in a section of stone.
+    pub const NONE: FaceConnections = FaceConnections(0);
+
+    fn bit(a: FaceDirection, b: FaceDirection) -> u64 {
+        1 << (a as u64 * 6 + b as u64)
+    }
+
+    pub fn connect(&mut self, a: FaceDirection, b: FaceDirection) {
+        self.0 |= Self::bit(a, b) | Self::bit(b, a);
+    }
+
+    pub fn connected(&self, a: FaceDirection, b: FaceDirection) -> bool {
+        self.0 & Self::bit(a, b) != 0
+    }
+
+    /// Flood-fills non-opaque blocks of `section` and connects faces touched by the same
+    /// connected area.
+    pub fn of(section: &Section) -> Self {
+        if section.is_empty() {
+            return Self::ALL;
+        }
+
+        let mut visited = vec![false; SECTION_VOLUME];
+        let mut connections = Self::NONE;
+        let mut queue = VecDeque::new();
+        let last = SECTION_SIZE - 1;
+
+        for start in 0..SECTION_VOLUME {
+            let (x, y, z) = (
+                start % SECTION_SIZE,
+                start / (SECTION_SIZE * SECTION_SIZE),
+                start / SECTION_SIZE % SECTION_SIZE,
+            );
+            if visited[start] || section.get(x, y, z).is_opaque() {
+                continue;
+            }
+
+            let mut touched: Vec<FaceDirection> = Vec::new();
+            visited[start] = true;
+            queue.push_back((x, y, z));
+            while let Some((x, y, z)) = queue.pop_front() {
+                for direction in FaceDirection::ALL {
+                    let [dx, dy, dz] = direction.offset();
+                    let on_border = match direction {
+                        FaceDirection::West => x == 0,
+                        FaceDirection::East => x == last,
+                        FaceDirection::Bottom => y == 0,
+                        FaceDirection::Top => y == last,
+                        FaceDirection::North => z == 0,
+                        FaceDirection::South => z == last,
+                    };
+                    if on_border {
+                        if !touched.contains(&direction) {
+                            touched.push(direction);
+                        }
+                        continue;
+                    }
+
+                    let (nx, ny, nz) = (
+                        (x as i32 + dx) as usize,
+                        (y as i32 + dy) as usize,
+                        (z as i32 + dz) as usize,
+                    );
+                    let index = section_index(nx, ny, nz);
+                    if !visited[index] && !section.get(nx, ny, nz).is_opaque() {
+                        visited[index] = true;
+                        queue.push_back((nx, ny, nz));
+                    }
+                }
+            }
+
+            for a in &touched {
+                for b in &touched {
+                    connections.connect(*a, *b);
+                }
+            }
+            if connections == Self::ALL {
+                break;
+            }
+        }
+        connections
+    }
+}
+
+/// Face connections of meshed sections, by chunk and section index.
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct SectionGraph(pub HashMap<(ChunkPos, usize), FaceConnections>);
+
+/// Numbers of chunk sections that are drawn and hidden by culling, shown in debug HUD.
+#[derive(Resource, Default, Debug)]
+pub struct SectionCulling {
+    pub drawn: usize,
+    pub culled: usize,
+}
+
+/// Section position as `(chunk x, section index, chunk z)`.
+fn section_at(pos: IVec3) -> (ChunkPos, usize) {
+    (ChunkPos::new(pos.x, pos.z), pos.y as usize)
+}
+
+fn direction_offset(direction: FaceDirection) -> IVec3 {
+    IVec3::from_array(direction.offset())
+}
+
+/// Finds sections that can be seen from section `start`, the way advanced cave culling does.
+///
+/// Sections are walked breadth-first. A section is entered only through a face that its
+/// predecessor connects with the face it was entered through, and the walk never turns back
+/// along any axis it has already moved on. Sections for which `is_visible` returns `false`, such
+/// as those out of frustum or not loaded, stop the walk.
+pub fn visible_sections(
+    start: IVec3,
+    connections: impl Fn(IVec3) -> FaceConnections,
+    is_visible: impl Fn(IVec3) -> bool,
+) -> HashSet<IVec3> {
+    let mut visited = HashSet::default();
+    let mut queue = VecDeque::new();
+    visited.insert(start);
+    queue.push_back((start, None::<FaceDirection>, 0_u8));
+
+    while let Some((pos, entered, travelled)) = queue.pop_front() {
+        let graph = connections(pos);
+        for direction in FaceDirection::ALL {
+            if travelled & (1 << direction.opposite() as u8) != 0 {
+                continue;
+            }
+            if entered.is_some_and(|entered| !graph.connected(entered, direction)) {
+                continue;
+            }
+
+            let next = pos + direction_offset(direction);
+            if !(0..SECTION_COUNT as i32).contains(&next.y)
+                || visited.contains(&next)
+                || !is_visible(next)
+            {
+                continue;
+            }
+            visited.insert(next);
+            queue.push_back((
+                next,
+                Some(direction.opposite()),
+                travelled | (1 << direction as u8),
+            ));
+        }
+    }
+    visited
+}
+
+/// Box of the section in world coordinates.
+fn section_aabb(pos: IVec3) -> Aabb {
+    let size = SECTION_SIZE as f32;
+    let half = Vec3A::splat(size / 2.);
+    let min = Vec3A::new(pos.x as f32, pos.y as f32, pos.z as f32) * size;
+    Aabb {
+        center: min + half,
+        half_extents: half,
+    }
+}
+
+/// Hides chunk sections that are out of camera frustum or behind solid terrain.
+///
+/// Visibility is found again only when the camera or meshed sections change.
+pub fn cull_sections(
+    manager: Res<ChunkManager>,
+    mut graph: ResMut<SectionGraph>,
+    mut culling: ResMut<SectionCulling>,
+    camera_q: Query<(Ref<GlobalTransform>, &Frustum), With<Camera3d>>,
+    added_q: Query<(), Added<ChunkSection>>,
+    mut section_q: Query<(&ChunkSection, &mut Visibility)>,
+) {
+    let Ok((camera, frustum)) = camera_q.single() else {
+        return;
+    };
+    if !camera.is_changed() && !graph.is_changed() && added_q.is_empty() {
+        return;
+    }
+
+    // Sections of unloaded chunks are forgotten.
+    if graph.len() > manager.loaded_count() * SECTION_COUNT {
+        graph.retain(|(pos, _), _| manager.chunk(*pos).is_some());
+    }
+
+    let eye = camera.translation();
+    let chunk = ChunkPos::from_block(eye.x.floor() as i32, eye.z.floor() as i32);
+    let index = (eye.y.floor() as i32).clamp(0, CHUNK_HEIGHT - 1) / SECTION_SIZE as i32;
+    let start = IVec3::new(chunk.x, index, chunk.z);
+
+    let visible = visible_sections(
+        start,
+        |pos| {
+            graph
+                .get(&section_at(pos))
+                .copied()
+                .unwrap_or(FaceConnections::ALL)
+        },
+        |pos| {
+            manager.chunk(section_at(pos).0).is_some()
+                && frustum.intersects_obb(&section_aabb(pos), &Affine3A::IDENTITY, true, false)
+        },
+    );
+
+    let mut drawn = HashSet::<(ChunkPos, usize)>::default();
+    let mut culled = HashSet::<(ChunkPos, usize)>::default();
+    for (section, mut visibility) in &mut section_q {
+        let pos = IVec3::new(section.pos.x, section.index as i32, section.pos.z);
+        let (wanted, counted) = if visible.contains(&pos) {
+            (Visibility::Inherited, &mut drawn)
+        } else {
+            (Visibility::Hidden, &mut culled)
+        };
+        counted.insert((section.pos, section.index));
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+
+    culling.drawn = drawn.len();
+    culling.culled = culled.len();
+}
+
+#[cfg(test)]
+mod tests {
+    use librecraft_shared::block::Block;
+
+    use super::*;
+
+    #[test]
+    fn wall_splits_section_faces() {
+        let mut section = Section::default();
+        for y in 0..SECTION_SIZE {
+            for z in 0..SECTION_SIZE {
+                section.set(8, y, z, Block::Stone);
+            }
+        }
+
+        let connections = FaceConnections::of(&section);
+        assert!(!connections.connected(FaceDirection::West, FaceDirection::East));
+        assert!(connections.connected(FaceDirection::West, FaceDirection::Top));
+        assert!(connections.connected(FaceDirection::Top, FaceDirection::Bottom));
+        // Glass doesn't block the view.
+        section.set(8, 5, 5, Block::Glass);
+        let connections = FaceConnections::of(&section);
+        assert!(connections.connected(FaceDirection::West, FaceDirection::East));
+    }
+
+    #[test]
+    fn sections_behind_solid_ones_are_hidden() {
+        // Column of air at x = 0 with solid sections everywhere else.
+        let connections = |pos: IVec3| {
+            if pos.x == 0 {
+                FaceConnections::ALL
+            } else {
+                FaceConnections::NONE
+            }
+        };
+        let visible = visible_sections(IVec3::new(0, 4, 0), connections, |pos| {
+            pos.x.abs() <= 3 && pos.z.abs() <= 3
+        });
+
+        // Solid sections next to the air are seen, those behind them are not.
+        assert!(visible.contains(&IVec3::new(0, 0, 3)));
+        assert!(visible.contains(&IVec3::new(1, 4, 0)));
+        assert!(!visible.contains(&IVec3::new(2, 4, 0)));
+        // Frustum and loaded area stop the walk.
+        assert!(!visible.contains(&IVec3::new(0, 4, 4)));
+    }
+}
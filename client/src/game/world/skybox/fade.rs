@@ -0,0 +1,114 @@
+#![cfg(feature = "fast-skybox")]
+
+use bevy::asset::weak_handle;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+};
+
+use super::SKY_RADIUS;
+use crate::game::world::time::{SKYBOX_BRIGHTNESS, WorldTime};
+
+/// The `Handle` for the shader for the [`SkyboxFadeMaterial`]
+///
+/// Generated with `Uuid::new_v4()`
+pub const SKYBOX_FADE_SHADER_HANDLE: Handle<Shader> =
+    weak_handle!("8110aab1-7ca4-4832-8e9f-ad592563621d");
+
+/// How long old skybox fades into the new one, in seconds.
+pub const SKYBOX_FADE_SECONDS: f32 = 1.5;
+
+/// Blend of two skybox cubemaps, drawn over the new [`Skybox`] while it replaces the old one.
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct SkyboxFadeMaterial {
+    #[texture(0, dimension = "cube")]
+    #[sampler(1)]
+    pub from: Handle<Image>,
+    #[texture(2, dimension = "cube")]
+    #[sampler(3)]
+    pub to: Handle<Image>,
+    /// From 0 showing only `from` to 1 showing only `to`.
+    #[uniform(4)]
+    pub progress: f32,
+    /// Same as [`Skybox::brightness`].
+    #[uniform(5)]
+    pub brightness: f32,
+}
+
+impl Material for SkyboxFadeMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SKYBOX_FADE_SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Sky is seen from inside of the sphere.
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+/// Marker of the sphere that crossfades skyboxes, a child of [`super::SkyboxCamera`].
+#[derive(Component)]
+pub struct SkyboxFade;
+
+/// Spawns sphere fading from skybox `from` to `to` as a child of `cam`, replacing the sphere
+/// of the previous fade if there is one.
+pub fn spawn_skybox_fade(
+    commands: &mut Commands,
+    cam: Entity,
+    children: Option<&Children>,
+    fade_q: &Query<(), With<SkyboxFade>>,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<SkyboxFadeMaterial>,
+    from: Handle<Image>,
+    to: Handle<Image>,
+    brightness: f32,
+) {
+    for child in children.into_iter().flatten() {
+        if fade_q.contains(*child) {
+            commands.entity(*child).despawn();
+        }
+    }
+
+    commands.entity(cam).with_child((
+        SkyboxFade,
+        Mesh3d(meshes.add(Sphere::new(SKY_RADIUS).mesh().uv(32, 18))),
+        MeshMaterial3d(materials.add(SkyboxFadeMaterial {
+            from,
+            to,
+            progress: 0.,
+            brightness,
+        })),
+        NotShadowCaster,
+        NotShadowReceiver,
+    ));
+}
+
+/// System that advances skybox crossfades and removes their spheres once they are done.
+pub fn fade_skybox(
+    mut commands: Commands,
+    time: Res<Time>,
+    world_time: Option<Res<WorldTime>>,
+    mut materials: ResMut<Assets<SkyboxFadeMaterial>>,
+    fade_q: Query<(Entity, &MeshMaterial3d<SkyboxFadeMaterial>), With<SkyboxFade>>,
+) {
+    let brightness = world_time.map_or(SKYBOX_BRIGHTNESS, |time| time.skybox_brightness());
+    for (sky, material) in fade_q.iter() {
+        let Some(material) = materials.get_mut(&material.0) else {
+            continue;
+        };
+
+        material.progress += time.delta_secs() / SKYBOX_FADE_SECONDS;
+        material.brightness = brightness;
+        if material.progress >= 1. {
+            commands.entity(sky).despawn();
+        }
+    }
+}
@@ -4,11 +4,18 @@ use bevy::asset::{load_internal_asset, weak_handle};
 use bevy::core_pipeline::Skybox;
 use bevy::image::CompressedImageFormats;
 use bevy::prelude::*;
-use bevy::render::render_resource::{TextureViewDescriptor, TextureViewDimension};
+use bevy::render::render_resource::{TextureFormat, TextureViewDescriptor, TextureViewDimension};
 use bevy::render::renderer::RenderDevice;
 
-pub mod image;
-pub use image::*;
+pub mod fade;
+pub mod procedural;
+pub use fade::*;
+pub use librecraft::skybox_image::{self as image, *};
+pub use procedural::*;
+
+use super::fog::SkyHorizon;
+use super::time::{SKYBOX_BRIGHTNESS, WorldTime};
+use crate::settings::{Settings, SettingsUpdated, SkyMode};
 
 /// The `Handle` for the shader for the [`SkyboxMaterial`]
 ///
@@ -24,31 +31,59 @@ pub const SKYBOX_SHADER_HANDLE: Handle<Shader> =
 /// Scans [`Camera3d`] that have [`SkyboxCamera`] component on it.
 #[derive(Debug, Clone, Resource)]
 pub struct SkyboxPlugin {
-    /// String with path to image in [`AssetServer`].
+    /// String with path to image in [`AssetServer`], or pattern of face paths for
+    /// [`SkyboxLayout::Faces`].
     /// Is none if asset can't be loaded.
-    /// Automatically creates a handle if it wasn't passed.
+    /// Automatically creates handles if they weren't passed.
     image: Option<String>,
-    /// Automatically set whenever image is changed.
-    /// If handle is absent, check logs for error.
-    handle: Option<Handle<Image>>,
+    /// How faces are laid out in image, detected by aspect ratio if none.
+    layout: Option<SkyboxLayout>,
+    /// Automatically set whenever image is changed, one per image file.
+    /// If handles are absent, check logs for error.
+    handles: Vec<Handle<Image>>,
+    /// Cube texture made from image, set once it's ready.
+    cubemap: Option<Handle<Image>>,
+    /// Average color of the horizon of [`SkyboxPlugin::cubemap`], if its format can be read.
+    horizon: Option<Srgba>,
 }
 
 impl Plugin for SkyboxPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(self.clone())
+            .add_plugins((
+                MaterialPlugin::<SkyMaterial>::default(),
+                MaterialPlugin::<SkyboxFadeMaterial>::default(),
+            ))
+            .init_resource::<SkyHorizon>()
+            .add_event::<SetSkybox>()
             .add_systems(Startup, (check_device_features,))
             .add_systems(
                 Update,
-                (detect_new_cameras, load_skybox_image, create_skybox),
+                (
+                    set_skybox,
+                    load_skybox_image,
+                    create_skybox,
+                    update_skybox_brightness,
+                    update_sky_mode,
+                    update_sky_horizon,
+                    update_procedural_sky,
+                    fade_skybox,
+                )
+                    .chain(),
             );
 
-        // todo: test usage of skybox shader.
         load_internal_asset!(
             app,
             SKYBOX_SHADER_HANDLE,
             "shaders/skybox.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            SKYBOX_FADE_SHADER_HANDLE,
+            "shaders/skybox_fade.wgsl",
+            Shader::from_wgsl
+        );
     }
 }
 
@@ -60,15 +95,44 @@ impl SkyboxPlugin {
     pub fn from_image_file(image: &str) -> SkyboxPlugin {
         Self {
             image: Some(image.to_owned()),
-            handle: None,
+            layout: None,
+            handles: Vec::new(),
+            cubemap: None,
+            horizon: None,
+        }
+    }
+
+    /// Same as [`SkyboxPlugin::from_image_file`], but takes six face files.
+    ///
+    /// `pattern` is a path with `{}` that is replaced by [`FACE_NAMES`], like `sky/{}.png`.
+    pub fn from_face_files(pattern: &str) -> SkyboxPlugin {
+        Self::from_image_file(pattern).with_layout(SkyboxLayout::Faces)
+    }
+
+    /// Plugin for `image` path, which is a pattern of face files if it has `{}`. Empty if
+    /// there is no image.
+    pub fn from_path(image: Option<&str>) -> SkyboxPlugin {
+        match image {
+            Some(pattern) if pattern.contains("{}") => Self::from_face_files(pattern),
+            Some(image) => Self::from_image_file(image),
+            None => Self::empty(),
         }
     }
 
+    /// Overrides layout that is otherwise detected by aspect ratio of image.
+    pub fn with_layout(mut self, layout: SkyboxLayout) -> SkyboxPlugin {
+        self.layout = Some(layout);
+        self
+    }
+
     /// Removes [`Skybox`] entity from all cameras that have [`SkyboxCamera`] component.
     pub fn empty() -> SkyboxPlugin {
         Self {
             image: None,
-            handle: None,
+            layout: None,
+            handles: Vec::new(),
+            cubemap: None,
+            horizon: None,
         }
     }
 }
@@ -77,33 +141,65 @@ impl SkyboxPlugin {
 ///
 /// When added, a [`Skybox`] will be created as a child if [`SkyboxPlugin`] has image or handle.
 /// When removed, that [`Skybox`] will also be removed.
+///
+/// If the image can't be used or [`SkyMode::Procedural`] is selected, [`ProceduralSky`] is drawn
+/// instead.
 #[derive(Component, Default, Debug, Clone)]
 pub struct SkyboxCamera;
 
+/// Fired to replace skybox image at runtime, the old one fades into the new one once it's
+/// loaded. `None` leaves only procedural sky.
+///
+/// Image is a path in [`AssetServer`], or a pattern of face files, see
+/// [`SkyboxPlugin::from_face_files`].
+#[derive(Event, Debug, Clone)]
+pub struct SetSkybox {
+    pub image: Option<String>,
+}
+
 fn check_device_features(render_device: Res<RenderDevice>) {
     let features = render_device.features();
     let formats = CompressedImageFormats::from_features(features);
     if !formats.contains(CompressedImageFormats::NONE) {
         error!("Uncompressed format of images needs support for skybox to work.");
-        return;
     }
 }
 
+/// System that starts loading image of the last [`SetSkybox`].
+///
+/// Cameras keep the old skybox until the new one is ready.
+fn set_skybox(mut set_reader: EventReader<SetSkybox>, mut plugin: ResMut<SkyboxPlugin>) {
+    let Some(ev) = set_reader.read().last() else {
+        return;
+    };
+
+    info!("Setting skybox: {:?}", ev.image);
+    *plugin = SkyboxPlugin::from_path(ev.image.as_deref());
+}
+
 fn load_skybox_image(
     assets: Res<AssetServer>,
     mut commands: Commands,
     mut plugin: ResMut<SkyboxPlugin>,
     camera_q: Query<Entity, With<SkyboxCamera>>,
 ) {
-    if let Some(image) = &plugin.image {
-        if let Some(handle) = &plugin.handle {
-            let state = assets.load_state(handle.id());
-            if state.is_failed() {
-                error!("Skybox image can't be loaded.");
-                *plugin = SkyboxPlugin::empty();
-            }
-        } else {
-            plugin.handle = Some(assets.load(image));
+    if let Some(image) = plugin.image.clone() {
+        if plugin.handles.is_empty() {
+            plugin.handles = if plugin.layout == Some(SkyboxLayout::Faces) {
+                FACE_NAMES
+                    .iter()
+                    .map(|face| assets.load(face_path(&image, face)))
+                    .collect()
+            } else {
+                vec![assets.load(image)]
+            };
+        } else if plugin
+            .handles
+            .iter()
+            .any(|handle| assets.load_state(handle.id()).is_failed())
+        {
+            error!("Skybox image can't be loaded.");
+            *plugin = SkyboxPlugin::empty();
         }
     } else {
         for cam in camera_q.iter() {
@@ -112,30 +208,49 @@ fn load_skybox_image(
     }
 }
 
+/// Makes cube texture from loaded image, it's given to cameras by [`update_sky_mode`].
 fn create_skybox(
     assets: Res<AssetServer>,
-    mut commands: Commands,
     mut plugin: ResMut<SkyboxPlugin>,
     mut images: ResMut<Assets<Image>>,
-    camera_q: Query<Entity, (Added<Camera3d>, With<SkyboxCamera>)>,
 ) {
-    if let Some(handle) = &plugin.handle {
-        let state = assets.load_state(handle.id());
-        if !state.is_loaded() {
+    if plugin.cubemap.is_none() && !plugin.handles.is_empty() {
+        let loaded = plugin
+            .handles
+            .iter()
+            .all(|handle| assets.load_state(handle.id()).is_loaded());
+        if !loaded {
             return;
         }
 
-        match image::get_skybox(&images, handle) {
+        // KTX2 cubemaps made by `skybox-convert` are already in the right shape.
+        let cubemap = match plugin.handles.as_slice() {
+            [handle] => images
+                .get(handle.id())
+                .filter(|image| image.texture_descriptor.array_layer_count() == 6)
+                .map(|_| handle.clone()),
+            _ => None,
+        };
+        if let Some(cubemap) = cubemap {
+            plugin.horizon = images.get(cubemap.id()).and_then(horizon_color);
+            plugin.cubemap = Some(cubemap);
+            info!("Skybox was successfully initialized from a cubemap!");
+            return;
+        }
+
+        match image::get_skybox(&images, &plugin.handles, plugin.layout) {
             Ok(mut image) => {
                 if image.texture_descriptor.array_layer_count() != 1 {
                     error!("Array layer count is incorrect.");
                     *plugin = SkyboxPlugin::empty();
+                    return;
                 }
 
                 image.reinterpret_stacked_2d_as_array(6);
                 if image.texture_descriptor.array_layer_count() != 6 {
                     error!("Array layer count is incorrect after reinterpreting.");
                     *plugin = SkyboxPlugin::empty();
+                    return;
                 }
 
                 image.texture_view_descriptor = Some(TextureViewDescriptor {
@@ -143,12 +258,8 @@ fn create_skybox(
                     ..default()
                 });
 
-                let skybox_handle = images.add(image);
-                plugin.handle = Some(skybox_handle.clone());
-
-                for cam in camera_q.iter() {
-                    insert_skybox_camera(&mut commands, cam, &skybox_handle);
-                }
+                plugin.horizon = horizon_color(&image);
+                plugin.cubemap = Some(images.add(image));
                 info!("Skybox was successfully initialized!");
             },
             Err(e) => {
@@ -159,24 +270,222 @@ fn create_skybox(
     }
 }
 
-/// System that detects new cameras with [`SkyboxCamera`] component.
-fn detect_new_cameras(
+/// Average color of the middle rows of side faces of cube `image`, where the sky meets the
+/// ground. Only uncompressed sRGB images can be read.
+pub fn horizon_color(image: &Image) -> Option<Srgba> {
+    if image.texture_descriptor.format != TextureFormat::Rgba8UnormSrgb {
+        return None;
+    }
+    let data = image.data.as_ref()?;
+    let side = image.width() as usize;
+    let face_len = side * side * 4;
+    if side == 0 || data.len() < face_len * 6 {
+        return None;
+    }
+
+    // Faces are in `FACE_NAMES` order, py and ny are the top and bottom.
+    let row = side / 2 * side * 4;
+    let mut sum = [0u64; 3];
+    for face in [0, 1, 4, 5] {
+        let start = face * face_len + row;
+        for pixel in data[start..start + side * 4].chunks_exact(4) {
+            for (sum, value) in sum.iter_mut().zip(pixel) {
+                *sum += *value as u64;
+            }
+        }
+    }
+    let [red, green, blue] = sum.map(|sum| (sum / (side as u64 * 4)) as u8);
+    Some(Srgba::rgb_u8(red, green, blue))
+}
+
+/// System that switches between skybox image and procedural sky by pressing F8.
+///
+/// It isn't added by [`SkyboxPlugin`], so that the game runs it only during gameplay.
+pub fn switch_sky_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Option<ResMut<Settings>>,
+    mut settings_writer: EventWriter<SettingsUpdated>,
+) {
+    let (true, Some(mut settings)) = (keys.just_pressed(KeyCode::F8), settings) else {
+        return;
+    };
+
+    settings.sky_mode = match settings.sky_mode {
+        SkyMode::Image => SkyMode::Procedural,
+        SkyMode::Procedural => SkyMode::Image,
+    };
+    info!("Sky mode: {:?}", settings.sky_mode);
+
+    settings_writer.write(SettingsUpdated {
+        settings: settings.clone(),
+    });
+}
+
+/// System that draws [`ProceduralSky`] instead of skybox image, if it's selected in settings
+/// or the image can't be used, and back.
+///
+/// Gives cameras the newest skybox, fading into it from the one they had.
+fn update_sky_mode(
     mut commands: Commands,
     plugin: Res<SkyboxPlugin>,
-    camera_q: Query<Entity, (Added<Camera3d>, With<SkyboxCamera>)>,
+    settings: Option<Res<Settings>>,
+    world_time: Option<Res<WorldTime>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+    mut fade_materials: ResMut<Assets<SkyboxFadeMaterial>>,
+    camera_q: Query<(Entity, Option<&Skybox>, Option<&Children>), With<SkyboxCamera>>,
+    sky_q: Query<(), With<ProceduralSky>>,
+    fade_q: Query<(), With<SkyboxFade>>,
 ) {
-    if let Some(handle) = &plugin.handle {
-        for cam in camera_q.iter() {
-            insert_skybox_camera(&mut commands, cam, handle);
+    let procedural = is_procedural(&plugin, settings.as_deref());
+
+    for (cam, skybox, children) in camera_q.iter() {
+        let sky = children
+            .into_iter()
+            .flatten()
+            .find(|child| sky_q.contains(**child));
+
+        if procedural {
+            if skybox.is_some() {
+                commands.entity(cam).remove::<Skybox>();
+            }
+            if sky.is_none() {
+                let material = materials.add(SkyMaterial {
+                    sky: SkyUniform::at(world_time.as_deref()),
+                });
+                spawn_procedural_sky(&mut commands, cam, &mut meshes, material);
+                info!("Procedural sky is drawn.");
+            }
+            continue;
+        }
+
+        if let Some(sky) = sky {
+            commands.entity(*sky).despawn();
+        }
+        let Some(cubemap) = plugin
+            .cubemap
+            .as_ref()
+            .filter(|cubemap| skybox.is_none_or(|skybox| skybox.image != **cubemap))
+        else {
+            continue;
+        };
+
+        let brightness = skybox_brightness(world_time.as_deref());
+        insert_skybox_camera(&mut commands, cam, cubemap, brightness);
+        if let Some(skybox) = skybox {
+            spawn_skybox_fade(
+                &mut commands,
+                cam,
+                children,
+                &fade_q,
+                &mut meshes,
+                &mut fade_materials,
+                skybox.image.clone(),
+                cubemap.clone(),
+                brightness,
+            );
+        }
+    }
+}
+
+/// Whether [`ProceduralSky`] is drawn instead of skybox image.
+fn is_procedural(plugin: &SkyboxPlugin, settings: Option<&Settings>) -> bool {
+    plugin.image.is_none()
+        || settings.is_some_and(|settings| settings.sky_mode == SkyMode::Procedural)
+}
+
+/// System that gives fog the horizon color of skybox image while it's drawn.
+fn update_sky_horizon(
+    plugin: Res<SkyboxPlugin>,
+    settings: Option<Res<Settings>>,
+    mut horizon: ResMut<SkyHorizon>,
+) {
+    let color = if is_procedural(&plugin, settings.as_deref()) {
+        None
+    } else {
+        plugin.horizon
+    };
+    if horizon.color != color {
+        horizon.color = color;
+    }
+}
+
+/// Brightness of skybox at the time of day, full day brightness if there is no world time.
+fn skybox_brightness(world_time: Option<&WorldTime>) -> f32 {
+    world_time.map_or(SKYBOX_BRIGHTNESS, WorldTime::skybox_brightness)
+}
+
+/// System that dims skybox of [`SkyboxCamera`] at night.
+fn update_skybox_brightness(
+    world_time: Option<Res<WorldTime>>,
+    mut skybox_q: Query<&mut Skybox, With<SkyboxCamera>>,
+) {
+    let Some(world_time) = world_time.filter(|time| time.is_changed()) else {
+        return;
+    };
+
+    let brightness = world_time.skybox_brightness();
+    for mut skybox in skybox_q.iter_mut() {
+        if skybox.brightness != brightness {
+            skybox.brightness = brightness;
         }
     }
 }
 
 /// Inserts `Skybox` to `cam` `Entity`.
-fn insert_skybox_camera(commands: &mut Commands, cam: Entity, handle: &Handle<Image>) {
+fn insert_skybox_camera(
+    commands: &mut Commands,
+    cam: Entity,
+    handle: &Handle<Image>,
+    brightness: f32,
+) {
     commands.entity(cam).insert(Skybox {
         image: handle.clone(),
-        brightness: 1000.,
+        brightness,
         ..default()
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skybox_path_selects_layout() {
+        let plugin = SkyboxPlugin::from_path(Some("sky/{}.png"));
+        assert_eq!(plugin.layout, Some(SkyboxLayout::Faces));
+        let plugin = SkyboxPlugin::from_path(Some("skybox_test.png"));
+        assert_eq!(plugin.image.as_deref(), Some("skybox_test.png"));
+        assert_eq!(plugin.layout, None);
+        assert!(SkyboxPlugin::from_path(None).image.is_none());
+    }
+
+    #[test]
+    fn horizon_is_middle_of_side_faces() {
+        // 2x2 faces, top rows are white and bottom rows, where the horizon is, have face colors.
+        let colors = [[200, 0, 0], [0, 0, 0], [9, 9, 9], [9, 9, 9], [0, 100, 0], [
+            0, 0, 40,
+        ]];
+        let data = colors
+            .iter()
+            .flat_map(|[r, g, b]| {
+                [[255, 255, 255, 255]; 2]
+                    .into_iter()
+                    .chain([[*r, *g, *b, 255]; 2])
+                    .flatten()
+            })
+            .collect();
+        let image = Image::new(
+            bevy::render::render_resource::Extent3d {
+                width: 2,
+                height: 12,
+                depth_or_array_layers: 1,
+            },
+            bevy::render::render_resource::TextureDimension::D2,
+            data,
+            TextureFormat::Rgba8UnormSrgb,
+            bevy::asset::RenderAssetUsages::all(),
+        );
+        assert_eq!(horizon_color(&image), Some(Srgba::rgb_u8(50, 25, 10)));
+    }
+}
@@ -0,0 +1,166 @@
+#![cfg(feature = "fast-skybox")]
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey, NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::MeshVertexBufferLayoutRef;
+use bevy::render::render_resource::{
+    AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError,
+};
+
+use super::SKYBOX_SHADER_HANDLE;
+use crate::game::world::fog::{DAY_HORIZON, NIGHT_HORIZON};
+use crate::game::world::time::{WorldTime, sun_direction};
+
+/// Radius of the sky sphere around the camera, it has to be within camera's far plane.
+pub const SKY_RADIUS: f32 = 900.;
+/// Cosine of the angular radius of the sun disc.
+const SUN_SIZE: f32 = 0.9995;
+
+const DAY_ZENITH: Srgba = Srgba::rgb(0.47, 0.65, 1.);
+const NIGHT_ZENITH: Srgba = Srgba::rgb(0., 0., 0.01);
+/// Color of the horizon around the sun at dawn and dusk.
+const SUNRISE: Srgba = Srgba::rgb(1., 0.5, 0.2);
+const SUN: Srgba = Srgba::rgb(1., 0.95, 0.8);
+
+/// Uniform of [`SkyMaterial`], must match `Sky` struct in `skybox.wgsl`.
+#[derive(ShaderType, Clone, Copy, Debug)]
+pub struct SkyUniform {
+    pub zenith_color: Vec4,
+    pub horizon_color: Vec4,
+    /// Direction to the sun in `xyz`, cosine of sun disc radius in `w`.
+    pub sun: Vec4,
+    pub sun_color: Vec4,
+    /// Brightness of stars, 0 during the day.
+    pub stars: f32,
+    /// Rotation of stars, they turn with the sun.
+    pub star_angle: f32,
+}
+
+/// Procedural gradient sky with sun disc and stars, drawn by [`SKYBOX_SHADER_HANDLE`].
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct SkyMaterial {
+    #[uniform(0)]
+    pub sky: SkyUniform,
+}
+
+impl Material for SkyMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SKYBOX_SHADER_HANDLE.into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // Sky is seen from inside of the sphere.
+        descriptor.primitive.cull_mode = None;
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push("DITHER".into());
+        }
+        Ok(())
+    }
+}
+
+/// Marker of the sky sphere, a child of [`super::SkyboxCamera`].
+#[derive(Component)]
+pub struct ProceduralSky;
+
+/// How strongly horizon glows around the sun at `celestial_angle`, the same way as sunrise
+/// color in Minecraft. It's 0 when the sun is far from the horizon.
+pub fn sunrise_glow(celestial_angle: f32) -> f32 {
+    let f = (celestial_angle * TAU).cos();
+    if !(-0.4..=0.4).contains(&f) {
+        return 0.;
+    }
+    let g = f / 0.4 * 0.5 + 0.5;
+    (1. - (1. - (g * PI).sin()) * 0.99).powi(2)
+}
+
+impl SkyUniform {
+    /// Colors of the sky at the time of day, noon if there is no world time.
+    pub fn at(world_time: Option<&WorldTime>) -> Self {
+        let (angle, brightness, stars) = world_time.map_or((0., 1., 0.), |time| {
+            // Stars are hidden by clouds when it rains.
+            let stars = (1. - time.daylight()) * (1. - time.sky_darkness);
+            (time.celestial_angle(), time.sky_brightness(), stars)
+        });
+        let sun = sun_direction(angle);
+
+        let zenith = NIGHT_ZENITH.mix(&DAY_ZENITH, brightness);
+        let horizon = NIGHT_HORIZON
+            .mix(&DAY_HORIZON, brightness)
+            .mix(&SUNRISE, sunrise_glow(angle) * 0.6);
+        Self {
+            zenith_color: LinearRgba::from(zenith).to_vec4(),
+            horizon_color: LinearRgba::from(horizon).to_vec4(),
+            sun: sun.extend(SUN_SIZE),
+            sun_color: LinearRgba::from(SUN).to_vec4() * 4.,
+            stars,
+            star_angle: angle * TAU,
+        }
+    }
+}
+
+/// Spawns sky sphere as a child of `cam`.
+pub fn spawn_procedural_sky(
+    commands: &mut Commands,
+    cam: Entity,
+    meshes: &mut Assets<Mesh>,
+    material: Handle<SkyMaterial>,
+) {
+    commands.entity(cam).with_child((
+        ProceduralSky,
+        Mesh3d(meshes.add(Sphere::new(SKY_RADIUS).mesh().uv(32, 18))),
+        MeshMaterial3d(material),
+        NotShadowCaster,
+        NotShadowReceiver,
+    ));
+}
+
+/// System that moves the sun and changes sky colors by time of day.
+pub fn update_procedural_sky(
+    world_time: Option<Res<WorldTime>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+    sky_q: Query<&MeshMaterial3d<SkyMaterial>, With<ProceduralSky>>,
+) {
+    if sky_q.is_empty() || world_time.as_ref().is_some_and(|time| !time.is_changed()) {
+        return;
+    }
+
+    let sky = SkyUniform::at(world_time.as_deref());
+    for material in sky_q.iter() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.sky = sky;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use librecraft_shared::time::{MIDNIGHT, NOON};
+
+    use super::*;
+
+    #[test]
+    fn horizon_glows_only_at_sunrise_and_sunset() {
+        assert_eq!(sunrise_glow(0.), 0.);
+        assert_eq!(sunrise_glow(0.5), 0.);
+        assert!(sunrise_glow(0.25) > 0.9);
+        assert!(sunrise_glow(0.75) > 0.9);
+    }
+
+    #[test]
+    fn stars_come_out_at_night() {
+        let time = |day_time| WorldTime {
+            day_time,
+            ..default()
+        };
+        assert_eq!(SkyUniform::at(Some(&time(NOON))).stars, 0.);
+        assert_eq!(SkyUniform::at(Some(&time(MIDNIGHT))).stars, 1.);
+        assert!(SkyUniform::at(None).sun.y > 0.99);
+    }
+}
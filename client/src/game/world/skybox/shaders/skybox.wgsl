@@ -11,17 +11,55 @@ fn dither(frag_coord: vec2<f32>) -> vec3<f32> {
 #endif
 
 #import bevy_pbr::forward_io::VertexOutput
+#import bevy_pbr::mesh_view_bindings::view
+
+struct Sky {
+    zenith_color: vec4<f32>,
+    horizon_color: vec4<f32>,
+    // Direction to the sun in xyz, cosine of sun disc radius in w.
+    sun: vec4<f32>,
+    sun_color: vec4<f32>,
+    // Brightness of stars, 0 during the day.
+    stars: f32,
+    // Rotation of stars around z axis, they turn with the sun.
+    star_angle: f32,
+};
 
 @group(2) @binding(0)
-var sky_texture: texture_cube<f32>;
-@group(2) @binding(1)
-var sky_sampler: sampler;
+var<uniform> sky: Sky;
+
+// Number of star cells across a face of the sky, more cells make smaller stars.
+const STAR_CELLS: f32 = 150.0;
+// Part of cells that have a star.
+const STAR_DENSITY: f32 = 0.004;
+
+fn hash(p: vec3<f32>) -> f32 {
+    let q = fract(p * vec3<f32>(0.1031, 0.1030, 0.0973));
+    let r = q + dot(q, q.yxz + 33.33);
+    return fract((r.x + r.y) * r.z);
+}
+
+fn stars(dir: vec3<f32>) -> f32 {
+    let c = cos(sky.star_angle);
+    let s = sin(sky.star_angle);
+    let rotated = vec3<f32>(c * dir.x - s * dir.y, s * dir.x + c * dir.y, dir.z);
+    let cell = floor(rotated * STAR_CELLS);
+    return step(1.0 - STAR_DENSITY, hash(cell)) * hash(cell + 17.0);
+}
 
 @fragment
 fn fragment(
     in: VertexOutput
 ) -> @location(0) vec4<f32> {
-    let color = textureSample(sky_texture, sky_sampler, in.world_normal).xyz;
+    let dir = normalize(in.world_position.xyz - view.world_position);
+    let height = clamp(dir.y, 0.0, 1.0);
+    var color = mix(sky.horizon_color.rgb, sky.zenith_color.rgb, sqrt(height));
+
+    let sun_edge = sky.sun.w;
+    let sun = smoothstep(sun_edge, mix(sun_edge, 1.0, 0.1), dot(dir, sky.sun.xyz));
+    color += sky.sun_color.rgb * sun;
+    color += vec3<f32>(stars(dir) * sky.stars * smoothstep(0.0, 0.2, dir.y));
+
 #ifdef DITHER
     return vec4<f32>(color + dither(in.position.xy), 1f);
 #else
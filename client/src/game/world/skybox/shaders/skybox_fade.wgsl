@@ -0,0 +1,27 @@
+#import bevy_pbr::forward_io::VertexOutput
+#import bevy_pbr::mesh_view_bindings::view
+
+@group(2) @binding(0)
+var from_texture: texture_cube<f32>;
+@group(2) @binding(1)
+var from_sampler: sampler;
+@group(2) @binding(2)
+var to_texture: texture_cube<f32>;
+@group(2) @binding(3)
+var to_sampler: sampler;
+// How far the fade went, from 0 showing only old skybox to 1 showing only the new one.
+@group(2) @binding(4)
+var<uniform> progress: f32;
+@group(2) @binding(5)
+var<uniform> brightness: f32;
+
+@fragment
+fn fragment(
+    in: VertexOutput
+) -> @location(0) vec4<f32> {
+    // Cube maps are left-handed, so z is flipped the same way as in bevy skybox.
+    let dir = normalize(in.world_position.xyz - view.world_position) * vec3<f32>(1.0, 1.0, -1.0);
+    let old_color = textureSample(from_texture, from_sampler, dir).rgb;
+    let new_color = textureSample(to_texture, to_sampler, dir).rgb;
+    return vec4<f32>(mix(old_color, new_color, progress) * brightness * view.exposure, 1f);
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+use librecraft_shared::block::Block;
+use librecraft_shared::chunk::BlockPos;
+
+use super::chunk::ChunkManager;
+use super::render::{BlockShapes, FaceDirection};
+use crate::game::player::Player;
+
+/// How far blocks can be reached in survival, in blocks.
+pub const SURVIVAL_REACH: f32 = 4.5;
+/// How far blocks can be reached in creative, in blocks.
+pub const CREATIVE_REACH: f32 = 5.;
+/// Outline is drawn slightly bigger than the block, so that it isn't hidden by its faces.
+const OUTLINE_MARGIN: f32 = 0.002;
+
+/// Block hit by a ray.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockHit {
+    pub pos: BlockPos,
+    pub block: Block,
+    /// Face of the block the ray entered through.
+    pub face: FaceDirection,
+    /// Distance from the ray origin to the hit point.
+    pub distance: f32,
+}
+
+impl BlockHit {
+    /// Position of the block next to the hit face, where a new block would be placed.
+    pub fn adjacent(&self) -> BlockPos {
+        let [dx, dy, dz] = self.face.offset();
+        self.pos.offset(dx, dy, dz)
+    }
+}
+
+/// Block the camera looks at within reach of the player, if any.
+#[derive(Resource, Default, Debug, Deref)]
+pub struct TargetedBlock(pub Option<BlockHit>);
+
+/// Whether ray stops at `block`. Liquids can be looked through, like air.
+pub fn is_targetable(block: Block) -> bool {
+    !block.is_air() && !block.is_liquid()
+}
+
+/// Reach of the player depending on game mode.
+pub fn player_reach(player: &Player) -> f32 {
+    if player.is_creative() {
+        CREATIVE_REACH
+    } else {
+        SURVIVAL_REACH
+    }
+}
+
+/// Walks blocks along the ray from `origin` in `direction`, the way DDA does, and returns the
+/// first targetable block closer than `max_distance`.
+///
+/// `block_at` returns `None` for blocks that are not loaded, which stop the ray.
+pub fn raycast(
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+    block_at: impl Fn(BlockPos) -> Option<Block>,
+) -> Option<BlockHit> {
+    let direction = direction.try_normalize()?;
+    let mut cell = origin.floor().as_ivec3();
+    let step = direction.signum().as_ivec3();
+
+    // Distance along the ray to cross one block on each axis, and to the first crossing.
+    let delta = direction.abs().recip();
+    let mut next = Vec3::ZERO;
+    for axis in 0..3 {
+        next[axis] = if direction[axis] > 0. {
+            (cell[axis] as f32 + 1. - origin[axis]) * delta[axis]
+        } else if direction[axis] < 0. {
+            (origin[axis] - cell[axis] as f32) * delta[axis]
+        } else {
+            f32::INFINITY
+        };
+    }
+
+    // Ray starting inside a block hits it from the side it looks from.
+    let abs = direction.abs();
+    let main_axis = (0..3).fold(0, |max, axis| if abs[axis] > abs[max] { axis } else { max });
+    let mut offset = [0; 3];
+    offset[main_axis] = -step[main_axis];
+    let mut face = FaceDirection::from_offset(offset)?;
+    let mut distance = 0.;
+
+    loop {
+        let pos = BlockPos::new(cell.x, cell.y, cell.z);
+        let block = block_at(pos)?;
+        if is_targetable(block) {
+            return Some(BlockHit {
+                pos,
+                block,
+                face,
+                distance,
+            });
+        }
+
+        let axis = (0..3).fold(
+            0,
+            |min, axis| if next[axis] < next[min] { axis } else { min },
+        );
+        distance = next[axis];
+        if distance > max_distance {
+            return None;
+        }
+        cell[axis] += step[axis];
+        next[axis] += delta[axis];
+
+        let mut offset = [0; 3];
+        offset[axis] = -step[axis];
+        face = FaceDirection::from_offset(offset)?;
+    }
+}
+
+/// Casts a ray from the camera and updates [`TargetedBlock`].
+pub fn update_targeted_block(
+    mut targeted: ResMut<TargetedBlock>,
+    manager: Res<ChunkManager>,
+    player: Res<Player>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let hit = camera_q.single().ok().and_then(|camera| {
+        let block_at = |pos: BlockPos| {
+            // Nothing can be targeted below the world, above it there is only air.
+            if pos.y < 0 { None } else { manager.block(pos) }
+        };
+        raycast(
+            camera.translation(),
+            *camera.forward(),
+            player_reach(&player),
+            block_at,
+        )
+    });
+
+    if targeted.0 != hit {
+        targeted.0 = hit;
+    }
+}
+
+/// Draws wireframe outline around the shape of targeted block.
+pub fn draw_targeted_block_outline(
+    mut gizmos: Gizmos,
+    targeted: Res<TargetedBlock>,
+    shapes: Option<Res<BlockShapes>>,
+) {
+    let Some(hit) = targeted.0 else {
+        return;
+    };
+
+    let (min, max) = shapes
+        .and_then(|shapes| shapes.get(hit.block).bounds())
+        .unwrap_or((Vec3::ZERO, Vec3::ONE));
+    let origin = Vec3::new(hit.pos.x as f32, hit.pos.y as f32, hit.pos.z as f32);
+    let size = max - min + Vec3::splat(OUTLINE_MARGIN * 2.);
+    gizmos.cuboid(
+        Transform::from_translation(origin + (min + max) / 2.).with_scale(size),
+        Color::BLACK,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Floor at `y = 0` with a single pillar block at `(3, 1, 0)`.
+    fn world(pos: BlockPos) -> Option<Block> {
+        match (pos.x, pos.y, pos.z) {
+            (_, ..0, _) => None,
+            (_, 0, _) | (3, 1, 0) => Some(Block::Stone),
+            (0, 1, 1) => Some(Block::Water),
+            _ => Some(Block::Air),
+        }
+    }
+
+    #[test]
+    fn ray_hits_first_block_and_its_face() {
+        let origin = Vec3::new(0.5, 1.5, 0.5);
+
+        let hit = raycast(origin, Vec3::X, 5., world).unwrap();
+        assert_eq!(hit.pos, BlockPos::new(3, 1, 0));
+        assert_eq!(hit.face, FaceDirection::West);
+        assert_eq!(hit.distance, 2.5);
+        assert_eq!(hit.adjacent(), BlockPos::new(2, 1, 0));
+
+        let hit = raycast(origin, Vec3::new(0., -1., 0.), 5., world).unwrap();
+        assert_eq!(hit.pos, BlockPos::new(0, 0, 0));
+        assert_eq!(hit.face, FaceDirection::Top);
+        assert_eq!(hit.distance, 0.5);
+    }
+
+    #[test]
+    fn ray_is_limited_by_reach() {
+        let origin = Vec3::new(0.5, 1.5, 0.5);
+        assert!(raycast(origin, Vec3::X, 2., world).is_none());
+        assert!(raycast(origin, Vec3::Y, 100., world).is_none());
+        assert!(raycast(origin, Vec3::ZERO, 5., world).is_none());
+    }
+
+    #[test]
+    fn ray_passes_through_liquids_diagonally() {
+        // Goes over water at (0, 1, 1) and hits the floor under it.
+        let origin = Vec3::new(0.5, 1.9, 0.5);
+        let hit = raycast(origin, Vec3::new(0., -1., 1.), 5., world).unwrap();
+        assert_eq!(hit.pos, BlockPos::new(0, 0, 1));
+        assert_eq!(hit.face, FaceDirection::Top);
+    }
+}
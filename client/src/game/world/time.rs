@@ -0,0 +1,312 @@
+use std::error::Error;
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use librecraft_shared::time::{self, DayPhase, TICKS_PER_SECOND, parse_day_time};
+use serde::Deserialize;
+
+use crate::game::player::read_compound;
+use crate::gui::command::CommandEntered;
+
+/// Path to level data, not in assets because it's read by fs.
+pub const LEVEL_DATA_PATH: &str = "./assets/level.dat";
+
+/// Illuminance of the sun at noon, in lux.
+const SUN_ILLUMINANCE: f32 = 32000.;
+/// Illuminance of the moon at midnight, in lux.
+const MOON_ILLUMINANCE: f32 = 2000.;
+/// Ambient light brightness during the day and at night.
+const DAY_AMBIENT_BRIGHTNESS: f32 = 80.;
+const NIGHT_AMBIENT_BRIGHTNESS: f32 = 20.;
+/// Skybox brightness during the day, in nits.
+pub const SKYBOX_BRIGHTNESS: f32 = 1000.;
+/// Skybox never gets darker than this part of its day brightness, so that stars stay visible.
+const MIN_SKYBOX_BRIGHTNESS: f32 = 0.05;
+
+/// Time of the world, in game ticks.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct WorldTime {
+    /// Ticks since the world was created, it never stops.
+    pub age: i64,
+    /// Time of day in ticks since the first sunrise, days are [`time::TICKS_PER_DAY`] long.
+    pub day_time: i64,
+    /// Whether time of day advances, like `doDaylightCycle` game rule.
+    pub daylight_cycle: bool,
+    /// Part of the next tick that has passed, so that the sun moves smoothly.
+    pub partial_tick: f64,
+    /// How much rain and thunder darken the sky, from 0 in clear weather.
+    pub sky_darkness: f32,
+    /// Brightness of lightning flash, the sky is at least this bright.
+    pub lightning_flash: f32,
+}
+
+impl Default for WorldTime {
+    fn default() -> Self {
+        Self {
+            age: 0,
+            day_time: 0,
+            daylight_cycle: true,
+            partial_tick: 0.,
+            sky_darkness: 0.,
+            lightning_flash: 0.,
+        }
+    }
+}
+
+impl WorldTime {
+    /// Advances time by a number of `ticks`, which can be fractional.
+    pub fn advance(&mut self, ticks: f64) {
+        let ticks = self.partial_tick + ticks;
+        let whole = ticks.floor();
+        self.partial_tick = ticks - whole;
+        self.age += whole as i64;
+        if self.daylight_cycle {
+            self.day_time += whole as i64;
+        }
+    }
+
+    /// Position of the sun, see [`time::celestial_angle`].
+    pub fn celestial_angle(&self) -> f32 {
+        let partial_tick = if self.daylight_cycle {
+            self.partial_tick
+        } else {
+            0.
+        };
+        time::celestial_angle(self.day_time, partial_tick) as f32
+    }
+
+    /// How bright the sky is by time of day alone, from 0 at night to 1 during the day.
+    pub fn daylight(&self) -> f32 {
+        time::sky_brightness(self.celestial_angle() as f64) as f32
+    }
+
+    /// How bright the sky is, [`WorldTime::daylight`] darkened by weather and lit up by
+    /// lightning.
+    pub fn sky_brightness(&self) -> f32 {
+        (self.daylight() * (1. - self.sky_darkness)).max(self.lightning_flash)
+    }
+
+    pub fn phase(&self) -> DayPhase {
+        DayPhase::of(self.day_time)
+    }
+
+    /// Skybox brightness for the current time of day.
+    pub fn skybox_brightness(&self) -> f32 {
+        SKYBOX_BRIGHTNESS * MIN_SKYBOX_BRIGHTNESS.lerp(1., self.sky_brightness())
+    }
+}
+
+/// Level data as stored in level.dat, only the tags needed for time.
+#[derive(Deserialize, Debug)]
+struct LevelDat {
+    #[serde(rename = "Data")]
+    data: LevelData,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct LevelData {
+    time: i64,
+    day_time: i64,
+    #[serde(default)]
+    game_rules: GameRules,
+}
+
+#[derive(Deserialize, Default, Debug)]
+struct GameRules {
+    /// Game rules are stored as strings, like `"true"`.
+    #[serde(rename = "doDaylightCycle")]
+    do_daylight_cycle: Option<String>,
+}
+
+/// Reads world time from level data in `file` path.
+///
+/// Returns error if couldn't retrieve data.
+pub fn read_world_time(file: &str) -> Result<WorldTime, Box<dyn Error>> {
+    let level = LevelDat::deserialize(read_compound(file)?)?;
+    Ok(WorldTime {
+        age: level.data.time,
+        day_time: level.data.day_time,
+        daylight_cycle: level.data.game_rules.do_daylight_cycle.as_deref() != Some("false"),
+        ..WorldTime::default()
+    })
+}
+
+/// Setups world time from level data, a new world starts at sunrise.
+pub fn setup_world_time(mut commands: Commands) {
+    let time = match read_world_time(LEVEL_DATA_PATH) {
+        Ok(time) => {
+            info!("Loaded world time: {:?}", time);
+            time
+        },
+        Err(e) => {
+            warn!("Couldn't retrieve world time, starting a new day: {}", e);
+            WorldTime::default()
+        },
+    };
+    commands.insert_resource(time);
+}
+
+/// Fired with time from the server's Update Time packet.
+#[derive(Event, Debug)]
+pub struct TimeUpdated {
+    pub world_age: i64,
+    /// Time of day, negative if it doesn't advance.
+    pub time_of_day: i64,
+}
+
+/// Control of the time of day, like `/time` command.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TimeCommand {
+    /// Sets time of day to the given ticks.
+    Set(i64),
+    /// Adds ticks to the time of day.
+    Add(i64),
+    /// Logs the time of day.
+    Query,
+}
+
+impl TimeCommand {
+    /// Parses arguments of `/time`, such as `set noon` or `add 1000`.
+    pub fn parse(args: &str) -> Option<Self> {
+        match args.split_whitespace().collect::<Vec<_>>()[..] {
+            ["set", value] => parse_day_time(value).map(Self::Set),
+            ["add", ticks] => ticks.parse().ok().map(Self::Add),
+            ["query"] => Some(Self::Query),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, time: &mut WorldTime) {
+        match *self {
+            Self::Set(ticks) => time.day_time = ticks,
+            Self::Add(ticks) => time.day_time += ticks,
+            Self::Query => {},
+        }
+    }
+}
+
+/// Advances world time by 20 ticks per second.
+pub fn advance_world_time(time: Res<Time>, mut world_time: ResMut<WorldTime>) {
+    world_time.advance(time.delta_secs_f64() * TICKS_PER_SECOND);
+}
+
+/// Takes world time sent by the server.
+pub fn receive_time_updates(
+    mut time_reader: EventReader<TimeUpdated>,
+    mut world_time: ResMut<WorldTime>,
+) {
+    let Some(update) = time_reader.read().last() else {
+        return;
+    };
+
+    world_time.age = update.world_age;
+    world_time.day_time = update.time_of_day.abs();
+    world_time.daylight_cycle = update.time_of_day >= 0;
+    world_time.partial_tick = 0.;
+}
+
+/// Runs `/time` commands typed by the player.
+pub fn run_time_command(
+    mut command_reader: EventReader<CommandEntered>,
+    mut world_time: ResMut<WorldTime>,
+) {
+    for ev in command_reader.read() {
+        let Some(args) = ev.args("time") else {
+            continue;
+        };
+
+        match TimeCommand::parse(args) {
+            Some(command) => {
+                command.apply(&mut world_time);
+                info!(
+                    "Time of day is {} ({}).",
+                    world_time.day_time,
+                    world_time.phase().name()
+                );
+            },
+            None => warn!("Usage: /time set <day|noon|night|midnight|ticks>, /time add <ticks>."),
+        }
+    }
+}
+
+/// Marker of the directional light of the sun.
+#[derive(Component)]
+pub struct Sun;
+
+/// Marker of the directional light of the moon.
+#[derive(Component)]
+pub struct Moon;
+
+/// Direction from the world to the sun at `celestial_angle`. Sun rises in the east (+X).
+pub fn sun_direction(celestial_angle: f32) -> Vec3 {
+    let angle = celestial_angle * TAU;
+    Vec3::new(-angle.sin(), angle.cos(), 0.)
+}
+
+/// Moves the sun and moon across the sky and scales light by time of day.
+pub fn update_daylight(
+    world_time: Res<WorldTime>,
+    mut ambient: ResMut<AmbientLight>,
+    mut sun_q: Query<(&mut Transform, &mut DirectionalLight), (With<Sun>, Without<Moon>)>,
+    mut moon_q: Query<(&mut Transform, &mut DirectionalLight), (With<Moon>, Without<Sun>)>,
+) {
+    if !world_time.is_changed() {
+        return;
+    }
+
+    let sun = sun_direction(world_time.celestial_angle());
+    let brightness = world_time.sky_brightness();
+    for (mut transform, mut light) in sun_q.iter_mut() {
+        *transform = Transform::default().looking_to(-sun, Vec3::Z);
+        light.illuminance = SUN_ILLUMINANCE * brightness;
+    }
+    for (mut transform, mut light) in moon_q.iter_mut() {
+        *transform = Transform::default().looking_to(sun, Vec3::Z);
+        light.illuminance = MOON_ILLUMINANCE * (1. - brightness);
+    }
+    ambient.brightness = NIGHT_AMBIENT_BRIGHTNESS.lerp(DAY_AMBIENT_BRIGHTNESS, brightness);
+}
+
+#[cfg(test)]
+mod tests {
+    use librecraft_shared::time::{NIGHT, NOON};
+
+    use super::*;
+
+    #[test]
+    fn time_advances_by_whole_ticks() {
+        let mut time = WorldTime::default();
+        time.advance(2.5);
+        time.advance(0.75);
+        assert_eq!((time.age, time.day_time), (3, 3));
+        assert!((time.partial_tick - 0.25).abs() < 1e-9);
+
+        time.daylight_cycle = false;
+        time.advance(10.);
+        assert_eq!((time.age, time.day_time), (13, 3));
+    }
+
+    #[test]
+    fn time_commands_change_time_of_day() {
+        let mut time = WorldTime::default();
+        TimeCommand::parse(" set night").unwrap().apply(&mut time);
+        assert_eq!(time.day_time, NIGHT);
+        TimeCommand::parse("add 500").unwrap().apply(&mut time);
+        assert_eq!(time.day_time, NIGHT + 500);
+        assert_eq!(TimeCommand::parse("set"), None);
+        assert_eq!(TimeCommand::parse("add soon"), None);
+    }
+
+    #[test]
+    fn sun_is_up_at_noon() {
+        assert!(sun_direction(0.).abs_diff_eq(Vec3::Y, 1e-6));
+        assert!(sun_direction(0.75).x > 0.99);
+
+        let time = WorldTime {
+            day_time: NOON,
+            ..default()
+        };
+        assert_eq!(time.skybox_brightness(), SKYBOX_BRIGHTNESS);
+    }
+}
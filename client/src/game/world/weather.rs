@@ -0,0 +1,510 @@
+use std::error::Error;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use librecraft_shared::chunk::ChunkPos;
+use librecraft_shared::noise::{SplitMix64, hash_position};
+use librecraft_shared::weather::{Precipitation, Weather, WeatherState};
+use serde::Deserialize;
+
+use crate::game::player::read_compound;
+use crate::game::world::chunk::ChunkManager;
+use crate::game::world::time::{LEVEL_DATA_PATH, WorldTime};
+use crate::gui::command::CommandEntered;
+use crate::settings::Settings;
+
+/// Reasons of the server's Change Game State packet that change weather.
+pub const END_RAINING: u8 = 1;
+pub const BEGIN_RAINING: u8 = 2;
+pub const RAIN_LEVEL_CHANGE: u8 = 7;
+pub const THUNDER_LEVEL_CHANGE: u8 = 8;
+
+/// Rain and thunder levels follow weather by this much per tick, so weather changes in 5 seconds.
+const LEVEL_STEP: f32 = 0.01;
+/// How much full rain and full thunder darken the sky, each of them.
+const RAIN_DARKNESS: f32 = 5. / 16.;
+/// One in this many ticks of thunderstorm has lightning.
+const LIGHTNING_CHANCE: u64 = 300;
+/// Lightning flash fades by this much per tick.
+const LIGHTNING_FADE: f32 = 0.25;
+/// Most ticks weather catches up with in a frame, so that a long pause doesn't freeze the game.
+const MAX_TICKS_PER_FRAME: i64 = 100;
+
+/// Radius of columns with rain or snow around the player, in blocks.
+pub const PRECIPITATION_RADIUS: i32 = 10;
+/// Rain falls this many texture heights per tick, snow slowly drifts down.
+const RAIN_SPEED: f32 = 0.15;
+const SNOW_SPEED: f32 = 0.01;
+/// Texture repeats every this many blocks of a column.
+const TEXTURE_BLOCKS: f32 = 4.;
+const TEXTURE_WIDTH: u32 = 8;
+const TEXTURE_HEIGHT: u32 = 32;
+
+/// Weather of the world and how strong rain and thunder are at the moment.
+#[derive(Resource, Clone, Debug)]
+pub struct WorldWeather {
+    pub state: WeatherState,
+    /// Whether weather changes by itself, like `doWeatherCycle` game rule.
+    pub weather_cycle: bool,
+    /// Strength of rain from 0 to 1, it follows `state.raining` smoothly.
+    pub rain_level: f32,
+    /// Strength of thunderstorm from 0 to 1, only while it rains.
+    pub thunder_level: f32,
+    /// World age weather was advanced to, `None` before the first tick.
+    age: Option<i64>,
+    rng: SplitMix64,
+}
+
+impl WorldWeather {
+    pub fn new(state: WeatherState, weather_cycle: bool, seed: u64) -> Self {
+        let rain_level = if state.raining { 1. } else { 0. };
+        let thunder_level = if state.raining && state.thundering {
+            1.
+        } else {
+            0.
+        };
+        Self {
+            state,
+            weather_cycle,
+            rain_level,
+            thunder_level,
+            age: None,
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    /// Advances weather by one tick and returns whether lightning struck.
+    pub fn tick(&mut self) -> bool {
+        if self.weather_cycle {
+            self.state.tick(&mut self.rng);
+        }
+
+        let rain = if self.state.raining { 1. } else { 0. };
+        let thunder = if self.state.thundering { rain } else { 0. };
+        self.rain_level = step_towards(self.rain_level, rain);
+        self.thunder_level = step_towards(self.thunder_level, thunder);
+
+        self.thunder_level > 0.5 && self.rng.next_u64().is_multiple_of(LIGHTNING_CHANCE)
+    }
+
+    /// Sets weather like `/weather`, for `duration` ticks or for a random time.
+    pub fn set(&mut self, weather: Weather, duration: Option<i32>) {
+        self.state.set(weather, duration, &mut self.rng);
+    }
+
+    /// How much rain and thunder darken the sky, see [`WorldTime::sky_darkness`].
+    pub fn sky_darkness(&self) -> f32 {
+        1. - (1. - self.rain_level * RAIN_DARKNESS) * (1. - self.thunder_level * RAIN_DARKNESS)
+    }
+}
+
+fn step_towards(level: f32, target: f32) -> f32 {
+    level + (target - level).clamp(-LEVEL_STEP, LEVEL_STEP)
+}
+
+/// Level data as stored in level.dat, only the tags needed for weather.
+#[derive(Deserialize, Debug)]
+struct LevelDat {
+    #[serde(rename = "Data")]
+    data: LevelData,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LevelData {
+    clear_weather_time: i32,
+    rain_time: i32,
+    thunder_time: i32,
+    raining: i8,
+    thundering: i8,
+    #[serde(default, rename = "GameRules")]
+    game_rules: GameRules,
+}
+
+#[derive(Deserialize, Default, Debug)]
+struct GameRules {
+    /// Game rules are stored as strings, like `"true"`.
+    #[serde(rename = "doWeatherCycle")]
+    do_weather_cycle: Option<String>,
+}
+
+/// Reads weather timers from level data in `file` path and whether weather cycle is on.
+///
+/// Returns error if couldn't retrieve data.
+pub fn read_weather(file: &str) -> Result<(WeatherState, bool), Box<dyn Error>> {
+    let level = LevelDat::deserialize(read_compound(file)?)?;
+    let state = WeatherState {
+        clear_time: level.data.clear_weather_time,
+        rain_time: level.data.rain_time,
+        thunder_time: level.data.thunder_time,
+        raining: level.data.raining != 0,
+        thundering: level.data.thundering != 0,
+    };
+    let cycle = level.data.game_rules.do_weather_cycle.as_deref() != Some("false");
+    Ok((state, cycle))
+}
+
+/// Setups weather from level data, a new world starts with clear weather.
+pub fn setup_weather(mut commands: Commands, settings: Res<Settings>) {
+    let (state, cycle) = match read_weather(LEVEL_DATA_PATH) {
+        Ok(weather) => {
+            info!("Loaded weather: {:?}", weather);
+            weather
+        },
+        Err(e) => {
+            warn!("Couldn't retrieve weather, starting with clear sky: {}", e);
+            (WeatherState::default(), true)
+        },
+    };
+    commands.insert_resource(WorldWeather::new(state, cycle, settings.seed));
+}
+
+/// Fired with the server's Change Game State packet, `reason` is one of weather reasons
+/// like [`BEGIN_RAINING`].
+#[derive(Event, Debug)]
+pub struct GameStateChanged {
+    pub reason: u8,
+    pub value: f32,
+}
+
+/// Control of the weather, like `/weather` command.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WeatherCommand {
+    /// Sets weather for the given ticks, or for a random time.
+    Set(Weather, Option<i32>),
+    /// Logs the weather.
+    Query,
+}
+
+impl WeatherCommand {
+    /// Parses arguments of `/weather`, such as `rain` or `thunder 6000`.
+    pub fn parse(args: &str) -> Option<Self> {
+        match args.split_whitespace().collect::<Vec<_>>()[..] {
+            ["query"] => Some(Self::Query),
+            [weather] => Weather::from_name(weather).map(|weather| Self::Set(weather, None)),
+            [weather, ticks] => {
+                let ticks = ticks.parse().ok().filter(|ticks| *ticks > 0)?;
+                Weather::from_name(weather).map(|weather| Self::Set(weather, Some(ticks)))
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Takes weather sent by the server.
+pub fn receive_weather_updates(
+    mut state_reader: EventReader<GameStateChanged>,
+    mut weather: ResMut<WorldWeather>,
+) {
+    for ev in state_reader.read() {
+        match ev.reason {
+            END_RAINING => weather.state.raining = false,
+            BEGIN_RAINING => weather.state.raining = true,
+            RAIN_LEVEL_CHANGE => weather.rain_level = ev.value.clamp(0., 1.),
+            THUNDER_LEVEL_CHANGE => weather.thunder_level = ev.value.clamp(0., 1.),
+            _ => {},
+        }
+    }
+}
+
+/// Runs `/weather` commands typed by the player.
+pub fn run_weather_command(
+    mut command_reader: EventReader<CommandEntered>,
+    mut weather: ResMut<WorldWeather>,
+) {
+    for ev in command_reader.read() {
+        let Some(args) = ev.args("weather") else {
+            continue;
+        };
+
+        match WeatherCommand::parse(args) {
+            Some(WeatherCommand::Set(wanted, duration)) => {
+                weather.set(wanted, duration);
+                info!("Weather is set to {}.", wanted.name());
+            },
+            Some(WeatherCommand::Query) => {
+                info!("Weather is {}.", weather.state.weather().name())
+            },
+            None => warn!("Usage: /weather <clear|rain|thunder> [ticks], /weather query."),
+        }
+    }
+}
+
+/// Advances weather by ticks of world time and darkens the sky by it.
+pub fn advance_weather(mut weather: ResMut<WorldWeather>, mut world_time: ResMut<WorldTime>) {
+    let age = world_time.age;
+    let ticks = weather.age.map_or(0, |last| age - last);
+    weather.age = Some(age);
+
+    for _ in 0..ticks.clamp(0, MAX_TICKS_PER_FRAME) {
+        world_time.lightning_flash = (world_time.lightning_flash - LIGHTNING_FADE).max(0.);
+        if weather.tick() {
+            world_time.lightning_flash = 1.;
+        }
+    }
+    world_time.sky_darkness = weather.sky_darkness();
+}
+
+/// Mesh of rain or snow columns.
+#[derive(Component)]
+pub struct PrecipitationMesh(pub Precipitation);
+
+/// A column where rain or snow falls, from `bottom` to `top`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct PrecipitationColumn {
+    pub x: i32,
+    pub z: i32,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+/// Texture of falling rain drops or snowflakes, repeated along columns.
+pub fn precipitation_texture(kind: Precipitation) -> Image {
+    let mut rng = SplitMix64::new(kind as u64);
+    let mut data = vec![0; (TEXTURE_WIDTH * TEXTURE_HEIGHT * 4) as usize];
+    let (color, length, count) = match kind {
+        Precipitation::Snow => ([255, 255, 255, 230], 2, 10),
+        _ => ([150, 180, 255, 160], 5, 6),
+    };
+    for _ in 0..count {
+        let x = (rng.next_u64() % TEXTURE_WIDTH as u64) as u32;
+        let y = (rng.next_u64() % TEXTURE_HEIGHT as u64) as u32;
+        for dy in 0..length {
+            let i = (((y + dy) % TEXTURE_HEIGHT * TEXTURE_WIDTH + x) * 4) as usize;
+            data[i..i + 4].copy_from_slice(&color);
+        }
+    }
+
+    let mut image = Image::new(
+        Extent3d {
+            width: TEXTURE_WIDTH,
+            height: TEXTURE_HEIGHT,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::nearest()
+    });
+    image
+}
+
+/// Builds quads of `columns` that face `camera`, with texture scrolled down by `scroll` and
+/// faded by distance and `level` of rain.
+pub fn build_precipitation_mesh(
+    columns: &[PrecipitationColumn],
+    camera: Vec3,
+    scroll: f32,
+    level: f32,
+) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut colors: Vec<[f32; 4]> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+
+    for column in columns {
+        let center = Vec2::new(column.x as f32 + 0.5, column.z as f32 + 0.5);
+        let to_camera = camera.xz() - center;
+        let side = to_camera.perp().normalize_or(Vec2::X) * 0.5;
+        let alpha = level * (1. - to_camera.length() / PRECIPITATION_RADIUS as f32).max(0.);
+        // Each column starts at a different point of the texture.
+        let offset = (hash_position(0, column.x, 0, column.z) % 64) as f32 / 64.;
+        let v = |y: f32| offset + scroll - y / TEXTURE_BLOCKS;
+
+        let start = positions.len() as u32;
+        for (corner, y, u) in [
+            (center - side, column.bottom, 0.),
+            (center + side, column.bottom, 1.),
+            (center + side, column.top, 1.),
+            (center - side, column.top, 0.),
+        ] {
+            positions.push([corner.x, y, corner.y]);
+            uvs.push([u, v(y)]);
+            colors.push([1., 1., 1., alpha]);
+        }
+        indices.extend([0, 1, 2, 0, 2, 3].map(|i| start + i));
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Spawns hidden meshes of rain and snow with their textures.
+pub fn setup_precipitation(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for kind in [Precipitation::Rain, Precipitation::Snow] {
+        commands.spawn((
+            PrecipitationMesh(kind),
+            Mesh3d(meshes.add(Mesh::new(
+                PrimitiveTopology::TriangleList,
+                RenderAssetUsages::default(),
+            ))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color_texture: Some(images.add(precipitation_texture(kind))),
+                unlit: true,
+                alpha_mode: AlphaMode::Blend,
+                cull_mode: None,
+                double_sided: true,
+                ..default()
+            })),
+            Transform::default(),
+            Visibility::Hidden,
+            NotShadowCaster,
+            NotShadowReceiver,
+        ));
+    }
+}
+
+/// Rebuilds rain and snow around the camera. They fall down to the highest block of each
+/// column, so that they don't fall indoors.
+pub fn update_precipitation(
+    weather: Res<WorldWeather>,
+    world_time: Res<WorldTime>,
+    manager: Res<ChunkManager>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    camera_q: Query<&GlobalTransform, With<Camera3d>>,
+    mut mesh_q: Query<(&PrecipitationMesh, &Mesh3d, &mut Visibility)>,
+) {
+    let Ok(camera) = camera_q.single() else {
+        return;
+    };
+
+    if weather.rain_level <= 0. {
+        for (_, _, mut visibility) in mesh_q.iter_mut() {
+            visibility.set_if_neq(Visibility::Hidden);
+        }
+        return;
+    }
+
+    let camera = camera.translation();
+    let (rain, snow) = precipitation_columns(&manager, camera);
+    let ticks = world_time.age as f32 + world_time.partial_tick as f32;
+    for (kind, mesh, mut visibility) in mesh_q.iter_mut() {
+        let (columns, speed) = match kind.0 {
+            Precipitation::Snow => (&snow, SNOW_SPEED),
+            _ => (&rain, RAIN_SPEED),
+        };
+        visibility.set_if_neq(if columns.is_empty() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        });
+        if let Some(mesh) = meshes.get_mut(&mesh.0) {
+            let scroll = (ticks * speed).fract();
+            *mesh = build_precipitation_mesh(columns, camera, scroll, weather.rain_level);
+        }
+    }
+}
+
+/// Columns with rain and with snow around `camera`, each ends at the heightmap.
+fn precipitation_columns(
+    manager: &ChunkManager,
+    camera: Vec3,
+) -> (Vec<PrecipitationColumn>, Vec<PrecipitationColumn>) {
+    let (mut rain, mut snow) = (Vec::new(), Vec::new());
+    let (cx, cz) = (camera.x.floor() as i32, camera.z.floor() as i32);
+    let top = camera.y + PRECIPITATION_RADIUS as f32;
+    for dz in -PRECIPITATION_RADIUS..=PRECIPITATION_RADIUS {
+        for dx in -PRECIPITATION_RADIUS..=PRECIPITATION_RADIUS {
+            if dx * dx + dz * dz > PRECIPITATION_RADIUS * PRECIPITATION_RADIUS {
+                continue;
+            }
+            let (x, z) = (cx + dx, cz + dz);
+            let pos = ChunkPos::from_block(x, z);
+            let Some(chunk) = manager.chunk(pos) else {
+                continue;
+            };
+
+            let (lx, lz) = (
+                (x - pos.min_block_x()) as usize,
+                (z - pos.min_block_z()) as usize,
+            );
+            let height = chunk.height(lx, lz);
+            let bottom = (height as f32).max(camera.y - PRECIPITATION_RADIUS as f32);
+            if bottom >= top {
+                continue;
+            }
+            let column = PrecipitationColumn { x, z, bottom, top };
+            match Precipitation::at(chunk.biome(lx, height, lz), height) {
+                Precipitation::Rain => rain.push(column),
+                Precipitation::Snow => snow.push(column),
+                Precipitation::None => {},
+            }
+        }
+    }
+    (rain, snow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weather_commands_are_parsed() {
+        assert_eq!(
+            WeatherCommand::parse("rain"),
+            Some(WeatherCommand::Set(Weather::Rain, None))
+        );
+        assert_eq!(
+            WeatherCommand::parse(" thunder 600"),
+            Some(WeatherCommand::Set(Weather::Thunder, Some(600)))
+        );
+        assert_eq!(WeatherCommand::parse("query"), Some(WeatherCommand::Query));
+        assert_eq!(WeatherCommand::parse("snow"), None);
+        assert_eq!(WeatherCommand::parse("rain -5"), None);
+    }
+
+    #[test]
+    fn rain_darkens_sky_gradually() {
+        let mut weather = WorldWeather::new(WeatherState::default(), false, 0);
+        assert_eq!(weather.sky_darkness(), 0.);
+
+        weather.set(Weather::Thunder, Some(1000));
+        weather.tick();
+        assert!(weather.rain_level > 0. && weather.rain_level < 1.);
+        for _ in 0..100 {
+            weather.tick();
+        }
+        assert_eq!((weather.rain_level, weather.thunder_level), (1., 1.));
+        assert!((weather.sky_darkness() - (1. - (11. / 16_f32).powi(2))).abs() < 1e-6);
+    }
+
+    #[test]
+    fn precipitation_fades_with_distance() {
+        let column = |x| PrecipitationColumn {
+            x,
+            z: 0,
+            bottom: 64.,
+            top: 80.,
+        };
+        let mesh =
+            build_precipitation_mesh(&[column(0), column(8)], Vec3::new(0.5, 70., 0.5), 0., 1.);
+        assert_eq!(mesh.count_vertices(), 8);
+        let Some(bevy::render::mesh::VertexAttributeValues::Float32x4(colors)) =
+            mesh.attribute(Mesh::ATTRIBUTE_COLOR)
+        else {
+            panic!("Precipitation mesh has no colors");
+        };
+        assert_eq!(colors[0][3], 1.);
+        assert!(colors[4][3] < 0.5);
+    }
+}
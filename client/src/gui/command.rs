@@ -0,0 +1,152 @@
+use bevy::input::ButtonState;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::assets::RuntimeAsset;
+use crate::gui::GUIState;
+
+/// Longest command that can be typed, the same as chat limit in Minecraft.
+const MAX_COMMAND_LENGTH: usize = 256;
+
+/// Marker to find command line box entity.
+#[derive(Component)]
+pub struct CommandLine;
+
+/// Marker to find command line text entity.
+#[derive(Component)]
+pub struct CommandLineText;
+
+/// Fired when player entered a command, like `/time set day`.
+#[derive(Event, Debug)]
+pub struct CommandEntered {
+    pub command: String,
+}
+
+impl CommandEntered {
+    /// Arguments of the command if it's `/name`, `None` for other commands.
+    pub fn args(&self, name: &str) -> Option<&str> {
+        let (command, args) = self
+            .command
+            .split_once(char::is_whitespace)
+            .unwrap_or((&self.command, ""));
+        (command.strip_prefix('/') == Some(name)).then_some(args)
+    }
+}
+
+/// Setups hidden command line at the bottom of the screen.
+pub fn setup_command_line(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    runtime_asset: Res<RuntimeAsset>,
+) {
+    commands
+        .spawn((
+            CommandLine,
+            BackgroundColor(Color::BLACK.with_alpha(0.5)),
+            GlobalZIndex(10),
+            Visibility::Hidden,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(1.),
+                right: Val::Percent(1.),
+                bottom: Val::Percent(1.),
+                padding: UiRect::all(Val::Px(4.0)),
+                ..default()
+            },
+        ))
+        .with_child((
+            CommandLineText,
+            Text::new(""),
+            TextFont {
+                font: asset_server.load(runtime_asset.font_path.clone()),
+                font_size: 16.,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+}
+
+/// Opens command line by pressing `/` in game, enters typed command by `Enter` and closes it
+/// by `Escape`.
+pub fn update_command_line(
+    mut input_reader: EventReader<KeyboardInput>,
+    gui_state: Res<State<GUIState>>,
+    mut next_gui_state: ResMut<NextState<GUIState>>,
+    mut command_writer: EventWriter<CommandEntered>,
+    mut line_q: Query<&mut Visibility, With<CommandLine>>,
+    mut text_q: Query<&mut Text, With<CommandLineText>>,
+) {
+    let (Ok(mut visibility), Ok(mut text)) = (line_q.single_mut(), text_q.single_mut()) else {
+        warn_once!("Can't get command line.");
+        return;
+    };
+
+    let mut typing = *gui_state.get() == GUIState::Typing;
+    for ev in input_reader.read() {
+        if ev.state != ButtonState::Pressed {
+            continue;
+        }
+
+        if !typing {
+            if *gui_state.get() == GUIState::Closed && ev.text.as_deref() == Some("/") {
+                typing = true;
+                text.0 = "/".to_owned();
+                next_gui_state.set(GUIState::Typing);
+            }
+            continue;
+        }
+
+        match &ev.logical_key {
+            Key::Enter => {
+                let command = text.0.trim();
+                if !command.is_empty() {
+                    info!("Entered command: {}", command);
+                    command_writer.write(CommandEntered {
+                        command: command.to_owned(),
+                    });
+                }
+                typing = false;
+                next_gui_state.set(GUIState::Closed);
+            },
+            Key::Escape => {
+                typing = false;
+                next_gui_state.set(GUIState::Closed);
+            },
+            Key::Backspace => {
+                text.0.pop();
+            },
+            _ => {
+                if let Some(typed) = &ev.text {
+                    let room = MAX_COMMAND_LENGTH - text.0.chars().count();
+                    let typed = typed.chars().filter(|c| !c.is_control()).take(room);
+                    text.0.extend(typed);
+                }
+            },
+        }
+    }
+
+    let wanted = if typing {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != wanted {
+        *visibility = wanted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_args_are_split_from_name() {
+        let entered = |command: &str| CommandEntered {
+            command: command.to_owned(),
+        };
+        assert_eq!(entered("/time set day").args("time"), Some("set day"));
+        assert_eq!(entered("/time").args("time"), Some(""));
+        assert_eq!(entered("/timeset day").args("time"), None);
+        assert_eq!(entered("time set day").args("time"), None);
+    }
+}
@@ -2,14 +2,23 @@
 pub mod display;
 /// fps module.
 pub mod fps;
+/// sections module.
+pub mod sections;
 /// setup module.
 pub mod setup;
+/// target module.
+pub mod target;
+/// time module.
+pub mod time;
 
 use bevy::state::state::States;
 // Scope is small enough.
 pub use display::*;
 pub use fps::*;
+pub use sections::*;
 pub use setup::*;
+pub use target::*;
+pub use time::*;
 
 #[derive(States, Clone, Copy, Eq, PartialEq, Debug, Hash)]
 pub enum DebugGUIState {
@@ -0,0 +1,21 @@
+use bevy::prelude::*;
+
+use crate::game::world::SectionCulling;
+
+/// Marker to find section count text entity.
+#[derive(Component)]
+pub struct SectionCountText;
+
+/// Shows how many chunk sections are drawn and how many are hidden by culling.
+pub fn update_section_count_text(
+    mut query: Query<&mut TextSpan, With<SectionCountText>>,
+    culling: Option<Res<SectionCulling>>,
+) {
+    let Some(culling) = culling.filter(|culling| culling.is_changed()) else {
+        return;
+    };
+
+    for mut span in query.iter_mut() {
+        **span = format!("{} drawn, {} culled", culling.drawn, culling.culled);
+    }
+}
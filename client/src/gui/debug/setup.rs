@@ -4,7 +4,7 @@ use bevy::render::renderer::RenderAdapterInfo;
 use bevy::window::{Monitor, PrimaryMonitor};
 use wgpu_types::DeviceType;
 
-use super::{DisplayText, FocusText, FpsText};
+use super::{DisplayText, FocusText, FpsText, SectionCountText, TargetedBlockText, WorldTimeText};
 use crate::assets::RuntimeAsset;
 use crate::gui::debug::DebugGUIState;
 /// Marker to find debug's hud box entity.
@@ -190,12 +190,57 @@ pub fn setup_debug_hud(
 
     let adapter_info_text = adapter_text.id();
 
+    let section_count_text = commands
+        .spawn((
+            Text::new("Sections: "),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .with_child((
+            SectionCountText,
+            TextSpan::new("N/A"),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .id();
+
+    let targeted_block_text = commands
+        .spawn((
+            Text::new("Targeted Block: "),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .with_child((
+            TargetedBlockText,
+            TextSpan::new("none"),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .id();
+
+    let world_time_text = commands
+        .spawn((
+            Text::new("Time: "),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .with_child((
+            WorldTimeText,
+            TextSpan::new("N/A"),
+            text_font.clone(),
+            TextColor(Color::WHITE),
+        ))
+        .id();
+
     commands.entity(hud_root).add_children(&[
         fps_text,
         monitor_info_text,
         display_info_text,
         system_info_text,
         adapter_info_text,
+        section_count_text,
+        targeted_block_text,
+        world_time_text,
     ]);
 }
 
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+use crate::game::world::TargetedBlock;
+
+/// Marker to find targeted block text entity.
+#[derive(Component)]
+pub struct TargetedBlockText;
+
+/// Shows position, face and name of the block player looks at.
+pub fn update_targeted_block_text(
+    mut query: Query<&mut TextSpan, With<TargetedBlockText>>,
+    targeted: Res<TargetedBlock>,
+) {
+    if !targeted.is_changed() {
+        return;
+    }
+
+    let text = match targeted.0 {
+        Some(hit) => format!(
+            "{}, {}, {} ({:?}), {}",
+            hit.pos.x,
+            hit.pos.y,
+            hit.pos.z,
+            hit.face,
+            hit.block.name()
+        ),
+        None => "none".to_owned(),
+    };
+    for mut span in query.iter_mut() {
+        **span = text.clone();
+    }
+}
@@ -0,0 +1,30 @@
+use bevy::prelude::*;
+use librecraft_shared::time::TICKS_PER_DAY;
+
+use crate::game::world::WorldTime;
+
+/// Marker to find world time text entity.
+#[derive(Component)]
+pub struct WorldTimeText;
+
+/// Shows time of day in ticks and the part of the day it's in.
+pub fn update_world_time_text(
+    mut query: Query<&mut TextSpan, With<WorldTimeText>>,
+    world_time: Option<Res<WorldTime>>,
+) {
+    let Some(world_time) = world_time else {
+        return;
+    };
+
+    let text = format!(
+        "{} ({}), day {}",
+        world_time.day_time.rem_euclid(TICKS_PER_DAY),
+        world_time.phase().name(),
+        world_time.day_time.div_euclid(TICKS_PER_DAY),
+    );
+    for mut span in query.iter_mut() {
+        if **span != text {
+            **span = text.clone();
+        }
+    }
+}
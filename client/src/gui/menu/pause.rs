@@ -1,8 +1,12 @@
 use bevy::prelude::*;
 use bevy::ui::FocusPolicy;
 
+#[cfg(feature = "fast-skybox")]
+use crate::assets;
 use crate::assets::RuntimeAsset;
 use crate::game::player::Player;
+#[cfg(feature = "fast-skybox")]
+use crate::game::world::SetSkybox;
 use crate::gui::GUIState;
 use crate::settings::Settings;
 use crate::{GameState, consts};
@@ -15,9 +19,21 @@ pub struct PauseMenu;
 pub enum PauseButtonAction {
     Resume,
     Options,
+    /// Previews the next sky of [`SKY_PREVIEWS`].
+    #[cfg(feature = "fast-skybox")]
+    NextSky,
     Exit,
 }
 
+/// Skies that can be previewed from pause menu, in order. `None` is procedural sky.
+#[cfg(feature = "fast-skybox")]
+const SKY_PREVIEWS: [Option<&str>; 4] = [
+    Some(assets::SKYBOX_TEST_PATH),
+    Some(assets::SKYBOX_UNEDITED_PATH),
+    Some(assets::SKYBOX_TEXTURE_PATH),
+    None,
+];
+
 /// Creates and setups pause menu.
 pub fn setup_pause_menu(
     mut commands: Commands,
@@ -197,6 +213,8 @@ pub fn setup_pause_menu(
         for (msg, action) in [
             ("Resume", PauseButtonAction::Resume),
             ("Options", PauseButtonAction::Options),
+            #[cfg(feature = "fast-skybox")]
+            ("Next sky", PauseButtonAction::NextSky),
             ("Exit", PauseButtonAction::Exit),
         ] {
             wrapper
@@ -242,10 +260,11 @@ pub fn render_pause_menu(
     match visibility_q.single_mut() {
         Ok(mut vis) => {
             for ev in gui_state_reader.read() {
-                if GUIState::Closed == ev.entered.unwrap() {
-                    *vis = Visibility::Hidden;
-                } else {
+                // Command line is typed over the game, not over the pause menu.
+                if ev.entered == Some(GUIState::Opened) {
                     *vis = Visibility::Visible;
+                } else {
+                    *vis = Visibility::Hidden;
                 }
             }
 
@@ -279,6 +298,8 @@ pub fn render_pause_menu(
                 PauseButtonAction::Options => {
                     info!("todo!(options)");
                 },
+                #[cfg(feature = "fast-skybox")]
+                PauseButtonAction::NextSky => {},
                 PauseButtonAction::Exit => {
                     exit.write(AppExit::Success);
                 },
@@ -292,3 +313,22 @@ pub fn render_pause_menu(
         }
     }
 }
+
+/// Switches to the next sky of [`SKY_PREVIEWS`] when its button is pressed, without restarting.
+///
+/// Game starts with the first one.
+#[cfg(feature = "fast-skybox")]
+pub fn preview_next_sky(
+    button_q: Query<(&PauseButtonAction, &Interaction), Changed<Interaction>>,
+    mut skybox_writer: EventWriter<SetSkybox>,
+    mut index: Local<usize>,
+) {
+    for (action, interaction) in button_q.iter() {
+        if let (PauseButtonAction::NextSky, Interaction::Pressed) = (action, interaction) {
+            *index = (*index + 1) % SKY_PREVIEWS.len();
+            skybox_writer.write(SetSkybox {
+                image: SKY_PREVIEWS[*index].map(str::to_owned),
+            });
+        }
+    }
+}
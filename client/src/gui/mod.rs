@@ -4,6 +4,8 @@ use bevy::window::{CursorGrabMode, PrimaryWindow, WindowFocused, WindowResized};
 use crate::consts::{MIN_HEIGHT, MIN_WIDTH};
 use crate::settings::{Settings, SettingsUpdated};
 
+/// Command line opened by `/`.
+pub mod command;
 /// All that includes debug GUI
 pub mod debug;
 /// HUD systems
@@ -36,7 +38,6 @@ pub enum GUIState {
     #[default]
     Opened,
     Closed,
-    #[allow(dead_code)]
     Typing,
 }
 
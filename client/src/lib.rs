@@ -0,0 +1,8 @@
+//! Parts of librecraft that are shared by the game and tools in `src/bin`.
+
+// Unsafe code violates one of design goals and used only in crates.
+#![forbid(unsafe_code)]
+
+/// Conversion of skybox images to cubemaps. (fast-skybox)
+#[cfg(feature = "fast-skybox")]
+pub mod skybox_image;
@@ -45,9 +45,11 @@ use std::time::Duration;
 use consts::*;
 #[cfg(not(debug_assertions))]
 use dirs::config_dir;
+use game::GamePlugin;
+use game::player::{PLAYER_DATA_FILE, PlayerDataPath};
 #[cfg(feature = "fast-skybox")]
 use game::world::SkyboxCamera;
-use game::GamePlugin;
+use game::world::{Moon, Sun};
 use settings::SettingsPath;
 use splash::SplashPlugin;
 
@@ -144,6 +146,7 @@ pub fn main() {
         }
     }
 
+    let player_data_path = settings_path.join(PLAYER_DATA_FILE);
     settings_path.push("settings");
     settings_path.set_extension("toml");
 
@@ -159,6 +162,9 @@ pub fn main() {
             path: settings_path,
             ..default()
         })
+        .insert_resource(PlayerDataPath {
+            path: player_data_path,
+        })
         .insert_resource(Time::<Fixed>::from_hz(FIXED_TIME_CLOCK))
         .init_state::<GameState>()
         .enable_state_scoped_entities::<GameState>()
@@ -176,14 +182,9 @@ pub fn main() {
 
 /// Setups camera for [`App`] to use.
 fn setup_camera(mut commands: Commands) {
-    commands.spawn((
-        DirectionalLight {
-            illuminance: 32000.0,
-            ..default()
-        },
-        Transform::from_xyz(0.0, 2.0, 0.0)
-            .with_rotation(Quat::from_rotation_x(-std::f32::consts::PI / 4.)),
-    ));
+    // Both are moved and lit by time of day.
+    commands.spawn((Sun, DirectionalLight::default()));
+    commands.spawn((Moon, DirectionalLight::default()));
 
     commands.spawn((
         Camera3d::default(),
@@ -201,13 +202,13 @@ fn setup_camera(mut commands: Commands) {
     ));
 }
 
-/// Limits fps. Switches via `Space` in order: ["refresh rate", "off", "30 fps"].
+/// Limits fps. Switches via `F10` in order: ["refresh rate", "off", "30 fps"].
 fn limit_fps(
     mut settings: ResMut<bevy_framepace::FramepaceSettings>,
     monitor_q: Query<&Monitor, With<PrimaryMonitor>>,
     input: Res<ButtonInput<KeyCode>>,
 ) {
-    if input.just_pressed(KeyCode::Space) {
+    if input.just_pressed(KeyCode::F10) {
         use bevy_framepace::Limiter;
 
         let hz: f64 = match monitor_q.single() {
@@ -12,8 +12,11 @@ use crate::consts::DEBUG_MODE;
 use crate::gui::GUIScale;
 
 /// -1 for default values on startup (pos - centered).
+///
+/// Missing fields are filled with defaults, so older settings files stay readable.
 #[allow(dead_code)]
 #[derive(Deserialize, Serialize, Clone, Resource, Debug)]
+#[serde(default)]
 pub struct Settings {
     pub player_name: String,
     pub fullscreen: bool,
@@ -23,7 +26,27 @@ pub struct Settings {
     pub size_y: f32,
     pub maximized: bool,
     pub seed: u64,
+    /// Render distance in chunks.
+    pub render_distance: u32,
+    /// Smooth shadows in corners where blocks meet.
+    pub ambient_occlusion: bool,
+    /// How the sky is drawn.
+    pub sky_mode: SkyMode,
+    /// How clouds are drawn.
+    pub clouds: CloudMode,
+    /// Cloud render distance in chunks.
+    pub cloud_distance: u32,
+    /// Height of the bottom of clouds in blocks.
+    pub cloud_height: f32,
+    /// Fog that hides chunks at render distance, fog in liquids is always on.
+    pub fog: bool,
+    /// Part of render distance where fog starts, from 0 to 1.
+    pub fog_start: f32,
     pub gui_scale: f32,
+    /// Mouse look speed from 0 to 1, 0.5 turns the camera by 0.15 degrees per pixel.
+    pub mouse_sensitivity: f32,
+    /// Moving the mouse forward looks down.
+    pub invert_y: bool,
     pub pause_on_lost_focus: bool,
     pub mute_on_lost_focus: bool,
     /// Controversial change: replace fonts to be minecraft-like.
@@ -44,7 +67,51 @@ impl Default for Settings {
             size_y: -1.,
             maximized: false,
             seed: 0,
+            render_distance: 8,
+            ambient_occlusion: true,
+            sky_mode: SkyMode::Image,
+            clouds: CloudMode::Fancy,
+            cloud_distance: 12,
+            cloud_height: 192.,
+            fog: true,
+            fog_start: 0.75,
             gui_scale: 0.,
+            mouse_sensitivity: 0.5,
+            invert_y: false,
+        }
+    }
+}
+
+/// How the sky is drawn.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SkyMode {
+    /// Skybox image, procedural sky is used if it can't be loaded.
+    #[default]
+    Image,
+    /// Gradient sky with sun and stars, made by shader.
+    Procedural,
+}
+
+/// How clouds are drawn.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudMode {
+    /// No clouds.
+    Off,
+    /// Flat layer, one quad per cloud cell.
+    Fast,
+    /// Clouds extruded into boxes, with shaded sides.
+    #[default]
+    Fancy,
+}
+
+impl CloudMode {
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Off => Self::Fast,
+            Self::Fast => Self::Fancy,
+            Self::Fancy => Self::Off,
         }
     }
 }
@@ -0,0 +1,769 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use image::imageops::rotate180;
+use image::{DynamicImage, GenericImage, GenericImageView, ImageBuffer, Rgba, RgbaImage};
+use itertools::Itertools;
+
+/// Names of separate face files, in the order of cube faces: +X, -X, +Y, -Y, +Z, -Z.
+pub const FACE_NAMES: [&str; 6] = ["px", "nx", "py", "ny", "pz", "nz"];
+
+/// Path of a face file made from `pattern`, by replacing `{}` with name of the `face`.
+pub fn face_path(pattern: &str, face: &str) -> String {
+    pattern.replace("{}", face)
+}
+
+/// How cube faces are laid out in skybox image.
+///
+/// Faces in rows and columns go in the order of cube faces: +X, -X, +Y, -Y, +Z, -Z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkyboxLayout {
+    /// Net in shape of a horizontal cross, can be surrounded by background.
+    HorizontalCross,
+    /// Net in shape of a vertical cross, -Z face is upside down at the bottom.
+    VerticalCross,
+    /// Six faces in a row.
+    Strip,
+    /// Six faces one above the other, the way bevy expects them.
+    Stack,
+    /// Three faces in each of two rows.
+    Grid,
+    /// Six separate face files, see [`FACE_NAMES`].
+    Faces,
+    /// Equirectangular panorama, reprojected to a cube. North (-Z) is in the middle of it.
+    Equirect,
+}
+
+impl SkyboxLayout {
+    /// Layouts that can be told by aspect ratio, with their width to height ratios.
+    const ASPECT_RATIOS: [(SkyboxLayout, f32); 6] = [
+        (Self::HorizontalCross, 4. / 3.),
+        (Self::VerticalCross, 3. / 4.),
+        (Self::Strip, 6.),
+        (Self::Stack, 1. / 6.),
+        (Self::Grid, 3. / 2.),
+        (Self::Equirect, 2.),
+    ];
+
+    /// Every layout, in the order they are listed to artists.
+    pub const ALL: [SkyboxLayout; 7] = [
+        Self::HorizontalCross,
+        Self::VerticalCross,
+        Self::Strip,
+        Self::Stack,
+        Self::Grid,
+        Self::Faces,
+        Self::Equirect,
+    ];
+
+    /// Name of layout as it's written in command line, like `horizontal-cross`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HorizontalCross => "horizontal-cross",
+            Self::VerticalCross => "vertical-cross",
+            Self::Strip => "strip",
+            Self::Stack => "stack",
+            Self::Grid => "grid",
+            Self::Faces => "faces",
+            Self::Equirect => "equirect",
+        }
+    }
+
+    /// Layout by its [`SkyboxLayout::name`].
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.name() == name)
+    }
+
+    /// Guesses layout of image by its aspect ratio, `None` if it doesn't look like any.
+    pub fn detect(width: u32, height: u32) -> Option<Self> {
+        if width == 0 || height == 0 {
+            return None;
+        }
+        // Ratios are compared on log scale, so that 2:1 and 1:2 are equally far from 1:1.
+        let ratio = (width as f32 / height as f32).ln();
+        let (layout, ratio) = Self::ASPECT_RATIOS
+            .iter()
+            .map(|(layout, layout_ratio)| (*layout, (ratio - layout_ratio.ln()).abs()))
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+        (ratio < 0.1).then_some(layout)
+    }
+
+    /// Columns and rows of faces and cells of faces in order, for layouts that are a grid of
+    /// squares. The flag tells that face is upside down.
+    fn cells(&self) -> Option<(u32, u32, [(u32, u32, bool); 6])> {
+        let (columns, rows) = match self {
+            Self::Strip => (6, 1),
+            Self::Stack => (1, 6),
+            Self::Grid => (3, 2),
+            Self::VerticalCross => {
+                return Some((3, 4, [
+                    (2, 1, false),
+                    (0, 1, false),
+                    (1, 0, false),
+                    (1, 2, false),
+                    (1, 1, false),
+                    (1, 3, true),
+                ]));
+            },
+            _ => return None,
+        };
+        let cell = |i: u32| (i % columns, i / columns, false);
+        Some((columns, rows, [0, 1, 2, 3, 4, 5].map(cell)))
+    }
+}
+
+/// Converts skybox `sources` in `layout` to six faces one above the other.
+///
+/// If `layout` is `None`, it's detected by aspect ratio, six sources are always
+/// [`SkyboxLayout::Faces`].
+pub fn convert_skybox(
+    sources: &[DynamicImage],
+    layout: Option<SkyboxLayout>,
+) -> Result<RgbaImage, ImageError> {
+    if sources.len() == 6 {
+        return stack_faces(sources);
+    }
+    let [source] = sources else {
+        return Err(ImageError::UnknownLayout);
+    };
+
+    let rgba = DynamicImage::ImageRgba8(source.to_rgba8());
+    let layout = layout
+        .or_else(|| SkyboxLayout::detect(rgba.width(), rgba.height()))
+        .ok_or(ImageError::UnknownLayout)?;
+    match layout {
+        SkyboxLayout::HorizontalCross => {
+            ImageMeasurements::find_measurements(&rgba)?.to_rgba(&rgba)
+        },
+        SkyboxLayout::Equirect => Ok(equirect_to_cube(&rgba)),
+        SkyboxLayout::Faces => Err(ImageError::UnknownLayout),
+        layout => {
+            let (columns, rows, cells) = layout.cells().ok_or(ImageError::UnknownLayout)?;
+            copy_cells(&rgba, columns, rows, &cells)
+        },
+    }
+}
+
+/// Copies faces from a grid of `columns` and `rows` into a stack.
+fn copy_cells(
+    rgba: &DynamicImage,
+    columns: u32,
+    rows: u32,
+    cells: &[(u32, u32, bool); 6],
+) -> Result<RgbaImage, ImageError> {
+    let side = (rgba.width() / columns).min(rgba.height() / rows);
+    if side == 0 {
+        return Err(ImageError::NotAligned);
+    }
+
+    let mut image = RgbaImage::new(side, side * 6);
+    for (i, (column, row, upside_down)) in cells.iter().enumerate() {
+        let mut face = rgba.view(column * side, row * side, side, side).to_image();
+        if *upside_down {
+            face = rotate180(&face);
+        }
+        image
+            .copy_from(&face, 0, side * i as u32)
+            .map_err(|_| ImageError::CopyError)?;
+    }
+    Ok(image)
+}
+
+/// Puts six square faces of the same size one above the other.
+fn stack_faces(faces: &[DynamicImage]) -> Result<RgbaImage, ImageError> {
+    let side = faces[0].width();
+    if faces.iter().any(|face| face.dimensions() != (side, side)) {
+        return Err(ImageError::NotAligned);
+    }
+
+    let mut image = RgbaImage::new(side, side * 6);
+    for (i, face) in faces.iter().enumerate() {
+        image
+            .copy_from(&face.to_rgba8(), 0, side * i as u32)
+            .map_err(|_| ImageError::CopyError)?;
+    }
+    Ok(image)
+}
+
+/// Direction through point `(u, v)` of cube `face`, both from -1 to 1.
+fn cube_direction(face: usize, u: f32, v: f32) -> Vec3 {
+    match face {
+        0 => Vec3::new(1., -v, -u),
+        1 => Vec3::new(-1., -v, u),
+        2 => Vec3::new(u, 1., v),
+        3 => Vec3::new(u, -1., -v),
+        4 => Vec3::new(u, -v, 1.),
+        _ => Vec3::new(-u, -v, -1.),
+    }
+}
+
+/// Reprojects equirectangular panorama to cube faces, a quarter of its width each.
+pub fn equirect_to_cube(rgba: &DynamicImage) -> RgbaImage {
+    let (width, height) = rgba.dimensions();
+    let side = (width / 4).max(1);
+    RgbaImage::from_fn(side, side * 6, |x, y| {
+        let (face, y) = ((y / side) as usize, y % side);
+        let u = (x as f32 + 0.5) / side as f32 * 2. - 1.;
+        let v = (y as f32 + 0.5) / side as f32 * 2. - 1.;
+        // Skybox looks up cube textures with z flipped.
+        let dir = cube_direction(face, u, v).normalize() * Vec3::new(1., 1., -1.);
+
+        let longitude = dir.x.atan2(-dir.z);
+        let latitude = dir.y.asin();
+        let px = ((longitude / TAU + 0.5) * width as f32) as u32;
+        let py = ((0.5 - latitude / PI) * height as f32) as u32;
+        rgba.get_pixel(px.min(width - 1), py.min(height - 1))
+    })
+}
+
+/// Error that could happened while processing generic skybox image.
+#[derive(Debug, Clone, Copy)]
+pub enum ImageError {
+    /// Happens if image can't be converted to rgba8 standard.
+    DecodeFailed,
+    /// Happens if background color was not identified through `find_background` algorithm.
+    BackgroundNotDetermined,
+    /// Happens if background/non-background pixel was not found.
+    NetNotFound,
+    /// Happens if something in image is not aligned.
+    NotAligned,
+    /// Happens if something happened while copying a face.
+    CopyError,
+    /// Happens if asset can't be retrieved from `Handle`.
+    AssetNotFound,
+    /// Happens if layout of faces isn't given and can't be told by aspect ratio.
+    UnknownLayout,
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            Self::DecodeFailed => "image can't be converted to RGBA",
+            Self::BackgroundNotDetermined => "background color around the net is not uniform",
+            Self::NetNotFound => "a row or column of the net has only background pixels",
+            Self::NotAligned => "faces are not aligned or not of the same size",
+            Self::CopyError => "a face is out of the image bounds",
+            Self::AssetNotFound => "image asset is not loaded",
+            Self::UnknownLayout => "layout can't be told by aspect ratio",
+        };
+        write!(f, "{:?}: {}", self, message)
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+/// Vulkan format of 8-bit sRGB RGBA texels, `VK_FORMAT_R8G8B8A8_SRGB`.
+const VK_FORMAT_R8G8B8A8_SRGB: u32 = 43;
+
+/// Encodes six faces one above the other as an uncompressed KTX2 cubemap, which bevy loads
+/// as a cube texture without conversion.
+pub fn encode_ktx2_cubemap(stack: &RgbaImage) -> Vec<u8> {
+    const IDENTIFIER: [u8; 12] = [
+        0xab, b'K', b'T', b'X', b' ', b'2', b'0', 0xbb, b'\r', b'\n', 0x1a, b'\n',
+    ];
+    // Identifier, header, index and one level.
+    const DFD_OFFSET: u32 = 12 + 9 * 4 + 32 + 24;
+    // Total size and a basic descriptor block with four samples.
+    const DFD_LENGTH: u32 = 4 + 24 + 4 * 16;
+
+    let side = stack.width();
+    let data = stack.as_raw();
+    let data_offset = (DFD_OFFSET + DFD_LENGTH) as u64;
+    let mut out = Vec::with_capacity(data_offset as usize + data.len());
+    fn u32s(out: &mut Vec<u8>, values: &[u32]) {
+        values.iter().for_each(|v| out.extend(v.to_le_bytes()));
+    }
+
+    out.extend(IDENTIFIER);
+    // Format, type size, width, height, depth, layers, faces, levels, supercompression.
+    u32s(&mut out, &[
+        VK_FORMAT_R8G8B8A8_SRGB,
+        1,
+        side,
+        side,
+        0,
+        0,
+        6,
+        1,
+        0,
+    ]);
+    // Data format descriptor and key/value data offsets and lengths.
+    u32s(&mut out, &[DFD_OFFSET, DFD_LENGTH, 0, 0]);
+    // Supercompression global data offset and length.
+    out.extend(0u64.to_le_bytes());
+    out.extend(0u64.to_le_bytes());
+    // Level offset, length and uncompressed length, faces go one after another.
+    out.extend(data_offset.to_le_bytes());
+    out.extend((data.len() as u64).to_le_bytes());
+    out.extend((data.len() as u64).to_le_bytes());
+
+    // Khronos basic descriptor: RGBSDA color model, BT.709 primaries, sRGB transfer.
+    u32s(&mut out, &[DFD_LENGTH, 0, 2 | ((DFD_LENGTH - 4) << 16)]);
+    out.extend([1, 1, 2, 0]);
+    // Texel block dimensions and bytes in planes.
+    out.extend([0; 4]);
+    out.extend([4, 0, 0, 0, 0, 0, 0, 0]);
+    for (channel, kind) in [0u32, 1, 2, 15].into_iter().enumerate() {
+        // Alpha isn't sRGB encoded, so it's marked linear.
+        let kind = if kind == 15 { kind | 0x10 } else { kind };
+        u32s(&mut out, &[
+            (channel as u32 * 8) | (7 << 16) | (kind << 24),
+            0,
+            0,
+            255,
+        ]);
+    }
+
+    debug_assert_eq!(out.len() as u64, data_offset);
+    out.extend(data);
+    out
+}
+
+/// Gets the skybox mesh image in required format - six squares, one above the other.
+///
+/// Takes one image in `layout`, or six images of separate faces.
+pub fn get_skybox(
+    images: &Assets<Image>,
+    handles: &[Handle<Image>],
+    layout: Option<SkyboxLayout>,
+) -> Result<Image, ImageError> {
+    let sources = handles
+        .iter()
+        .map(|handle| {
+            images
+                .get(handle.id())
+                .ok_or(ImageError::AssetNotFound)?
+                .clone()
+                .try_into_dynamic()
+                .map_err(|_| ImageError::DecodeFailed)
+        })
+        .collect::<Result<Vec<DynamicImage>, ImageError>>()?;
+
+    let image = convert_skybox(&sources, layout)?;
+    Ok(Image::from_dynamic(
+        DynamicImage::from(image),
+        true,
+        bevy::asset::RenderAssetUsages::all(),
+    ))
+}
+
+/// Search horizontally from the left to find the first non-background pixel.
+pub fn search_from_left(rgba: &DynamicImage, bg: Rgba<u8>, y: u32) -> Result<u32, ImageError> {
+    for x in 0..rgba.width() {
+        if rgba.get_pixel(x, y) != bg {
+            return Ok(x);
+        }
+    }
+    Err(ImageError::NetNotFound)
+}
+
+/// Search horizontally from the right to find the last background pixel.
+pub fn search_from_right(rgba: &DynamicImage, bg: Rgba<u8>, y: u32) -> Result<u32, ImageError> {
+    for x in (0..rgba.width()).rev() {
+        if rgba.get_pixel(x, y) != bg {
+            return Ok(x + 1);
+        }
+    }
+    Err(ImageError::NetNotFound)
+}
+
+/// Search vertically from the top to find the first non-background pixel.
+pub fn search_from_top(rgba: &DynamicImage, bg: Rgba<u8>, x: u32) -> Result<u32, ImageError> {
+    for y in 0..rgba.height() {
+        if rgba.get_pixel(x, y) != bg {
+            return Ok(y);
+        }
+    }
+    Err(ImageError::NetNotFound)
+}
+
+/// Search vertically from the bottom to find the last background pixel.
+pub fn search_from_bottom(rgba: &DynamicImage, bg: Rgba<u8>, x: u32) -> Result<u32, ImageError> {
+    for y in (0..rgba.height()).rev() {
+        if rgba.get_pixel(x, y) != bg {
+            return Ok(y + 1);
+        }
+    }
+    Err(ImageError::NetNotFound)
+}
+
+/// Searches for 8 points in the extreme sectors of image where we except background to be.
+///
+/// This is over complicated and heavy, but may help to recover from image errors.
+pub fn find_background(rgba: &DynamicImage) -> Result<Rgba<u8>, ImageError> {
+    let samples: Vec<Rgba<u8>> = (0..4)
+        .cartesian_product(0..2)
+        .map(|(x, y)| {
+            rgba.get_pixel(
+                (x * 2 + 1) * rgba.width() / 8,
+                (y * 4 + 1) * rgba.height() / 6,
+            )
+        })
+        .collect::<Vec<Rgba<u8>>>();
+
+    // Find the most common background color.
+    let mut sample_freq = HashMap::<Rgba<u8>, usize>::new();
+    for sample in samples {
+        *sample_freq.entry(sample).or_insert(0) += 1;
+    }
+    let mut sample_hist = sample_freq.drain().collect::<Vec<(Rgba<u8>, usize)>>();
+    sample_hist.sort_by_key(|sample| sample.1);
+
+    if let Some(background) = sample_hist.iter().last() {
+        // At least half should be the background color.
+        if background.1 >= 4 {
+            Ok(background.0)
+        } else {
+            Err(ImageError::BackgroundNotDetermined)
+        }
+    } else {
+        Err(ImageError::BackgroundNotDetermined)
+    }
+}
+
+/// Measurements of positions in pixels.
+/// See `image` module docs for the explanation of the indices.
+pub struct ImageMeasurements {
+    vec_x: Vec<u32>,
+    vec_y: Vec<u32>,
+}
+
+impl ImageMeasurements {
+    /// Transforms `DynamicImage` to bevy `Image` format, by copying 6 faces.
+    pub fn to_image(&self, rgba: &DynamicImage) -> Result<Image, ImageError> {
+        let image = self.to_rgba(rgba)?;
+        Ok(Image::from_dynamic(
+            image::DynamicImage::from(image),
+            true,
+            bevy::asset::RenderAssetUsages::all(),
+        ))
+    }
+
+    /// Copies 6 faces one above the other.
+    pub fn to_rgba(&self, rgba: &DynamicImage) -> Result<RgbaImage, ImageError> {
+        let side = self.measure_side_length()?;
+        let mut image = RgbaImage::new(side, side * 6);
+
+        // +X
+        self.copy_face(rgba, &mut image, side, 3, 1, 0)?;
+        // -X
+        self.copy_face(rgba, &mut image, side, 1, 1, 1)?;
+        // +Y
+        self.copy_face(rgba, &mut image, side, 2, 0, 2)?;
+        // -Y
+        self.copy_face(rgba, &mut image, side, 2, 2, 3)?;
+        // +Z
+        self.copy_face(rgba, &mut image, side, 2, 1, 4)?;
+        // -Z
+        self.copy_face(rgba, &mut image, side, 0, 1, 5)?;
+
+        Ok(image)
+    }
+
+    /// Copies a face as part of new image creation.
+    fn copy_face(
+        &self,
+        rgba: &DynamicImage,
+        image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+        side: u32,
+        x_idx: usize,
+        y_idx: usize,
+        out_idx: usize,
+    ) -> Result<(), ImageError> {
+        let offset_x = (self.vec_x[x_idx + 1] - self.vec_x[x_idx] - side) / 2;
+        let offset_y = (self.vec_y[y_idx + 1] - self.vec_y[y_idx] - side) / 2;
+        image
+            .copy_from(
+                &rgba
+                    .view(
+                        self.vec_x[x_idx] + offset_x,
+                        self.vec_y[y_idx] + offset_y,
+                        side,
+                        side,
+                    )
+                    .to_image(),
+                0,
+                side * (out_idx as u32),
+            )
+            .map_err(|_| ImageError::CopyError)
+    }
+
+    /// Finds the dimensions of the skybox net in the image.
+    pub fn find_measurements(rgba: &DynamicImage) -> Result<Self, ImageError> {
+        // Find the background color.
+        let background = find_background(rgba)?;
+        // Measure the x values of the vertical edges of the net.
+        let dy = rgba.height() / 6;
+        let mid_x_min = search_from_left(rgba, background, dy * 3)?;
+        let mid_x_max = search_from_right(rgba, background, dy * 3)?;
+        let top_x_min = search_from_left(rgba, background, dy)?;
+        let top_x_max = search_from_right(rgba, background, dy)?;
+        let bot_x_min = search_from_left(rgba, background, dy * 5)?;
+        let bot_x_max = search_from_right(rgba, background, dy * 5)?;
+        if (top_x_min as i32 - bot_x_min as i32).abs() > 8 {
+            return Err(ImageError::NotAligned);
+        }
+        if (top_x_max as i32 - bot_x_max as i32).abs() > 8 {
+            return Err(ImageError::NotAligned);
+        }
+        let short_x_min = (top_x_min + bot_x_min) / 2;
+        let short_x_max = (top_x_max + bot_x_max) / 2;
+        // Assuming the shape, calculate the x values of the vertices and check them.
+        let vec_x = vec![
+            mid_x_min,
+            (short_x_min + mid_x_min) / 2,
+            short_x_min,
+            short_x_max,
+            mid_x_max,
+        ];
+        let mut diff_x = vec_x
+            .as_slice()
+            .windows(2)
+            .map(|w| w[1] as i32 - w[0] as i32)
+            .collect::<Vec<i32>>();
+        diff_x.sort_unstable();
+        if diff_x[3] - diff_x[0] > 16 {
+            return Err(ImageError::NotAligned);
+        }
+
+        // Measure the y values of the horizontal edges of the net.
+        let mid_y_min = search_from_top(rgba, background, (vec_x[2] + vec_x[3]) / 2)?;
+        let mid_y_max = search_from_bottom(rgba, background, (vec_x[2] + vec_x[3]) / 2)?;
+        let left_y_min = search_from_top(rgba, background, vec_x[1])?;
+        let left_y_max = search_from_bottom(rgba, background, vec_x[1])?;
+        let right_y_min = search_from_top(rgba, background, (vec_x[3] + vec_x[4]) / 2)?;
+        let right_y_max = search_from_bottom(rgba, background, (vec_x[3] + vec_x[4]) / 2)?;
+        if (left_y_min as i32 - right_y_min as i32).abs() > 8 {
+            return Err(ImageError::NotAligned);
+        }
+        if (left_y_max as i32 - right_y_max as i32).abs() > 8 {
+            return Err(ImageError::NotAligned);
+        }
+        let short_y_min = (left_y_min + right_y_min) / 2;
+        let short_y_max = (left_y_max + right_y_max) / 2;
+
+        // Assuming the shape, calculate the y values to return and check them.
+        let vec_y = vec![mid_y_min, short_y_min, short_y_max, mid_y_max];
+        let mut diff_y = vec_y
+            .as_slice()
+            .windows(2)
+            .map(|w| w[1] as i32 - w[0] as i32)
+            .collect::<Vec<i32>>();
+        diff_y.sort_unstable();
+        if diff_y[2] - diff_y[0] > 16 {
+            return Err(ImageError::NotAligned);
+        }
+
+        Ok(ImageMeasurements { vec_x, vec_y })
+    }
+
+    /// Determines the size of each image in the net, assuming that they all have to be the same
+    /// and are all square, so that we can copy pixel for pixel into the output without needing to
+    /// scale.
+    ///
+    /// Use minimums to avoid overlapping outside the net or even the source image.
+    fn measure_side_length(&self) -> Result<u32, ImageError> {
+        let min_x = self
+            .vec_x
+            .windows(2)
+            .map(|x| x[1] - x[0])
+            .min()
+            .expect("Four x intervals");
+
+        let min_y = self
+            .vec_y
+            .windows(2)
+            .map(|y| y[1] - y[0])
+            .min()
+            .expect("Three y intervals");
+        Ok(min_x.min(min_y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::imageops::FilterType;
+
+    use super::*;
+
+    fn asset(name: &str) -> DynamicImage {
+        let path = format!("{}/../assets/{name}", env!("CARGO_MANIFEST_DIR"));
+        image::open(&path).unwrap_or_else(|e| panic!("Can't open {path}: {e}"))
+    }
+
+    /// Faces of `skybox_texture.png`, made smaller so that tests are fast.
+    fn stacked_faces() -> RgbaImage {
+        asset("skybox_texture.png")
+            .resize_exact(32, 32 * 6, FilterType::Nearest)
+            .to_rgba8()
+    }
+
+    /// Lays out faces of `stack` in a grid of `columns` and `rows`, in `cells` that are written
+    /// by hand, so that they don't repeat [`SkyboxLayout::cells`].
+    fn lay_out(
+        stack: &RgbaImage,
+        columns: u32,
+        rows: u32,
+        cells: [(u32, u32, bool); 6],
+    ) -> DynamicImage {
+        let side = stack.width();
+        let mut image = RgbaImage::new(side * columns, side * rows);
+        for (i, (column, row, upside_down)) in cells.iter().enumerate() {
+            let mut face = stack.view(0, side * i as u32, side, side).to_image();
+            if *upside_down {
+                face = rotate180(&face);
+            }
+            image.copy_from(&face, column * side, row * side).unwrap();
+        }
+        DynamicImage::from(image)
+    }
+
+    #[test]
+    fn layout_is_detected_by_aspect_ratio() {
+        assert_eq!(
+            SkyboxLayout::detect(2100, 1600),
+            Some(SkyboxLayout::HorizontalCross)
+        );
+        assert_eq!(
+            SkyboxLayout::detect(300, 400),
+            Some(SkyboxLayout::VerticalCross)
+        );
+        assert_eq!(SkyboxLayout::detect(1024, 6144), Some(SkyboxLayout::Stack));
+        assert_eq!(SkyboxLayout::detect(600, 100), Some(SkyboxLayout::Strip));
+        assert_eq!(SkyboxLayout::detect(300, 200), Some(SkyboxLayout::Grid));
+        assert_eq!(
+            SkyboxLayout::detect(2048, 1024),
+            Some(SkyboxLayout::Equirect)
+        );
+        assert_eq!(SkyboxLayout::detect(1000, 1000), None);
+    }
+
+    #[test]
+    fn grid_layouts_match_stacked_asset() {
+        let stack = stacked_faces();
+        let row = |row| [0, 1, 2, 3, 4, 5].map(|i| (i, row, false));
+        let column = |column| [0, 1, 2, 3, 4, 5].map(|i| (column, i, false));
+        let layouts = [
+            (SkyboxLayout::Strip, 6, 1, row(0)),
+            (SkyboxLayout::Stack, 1, 6, column(0)),
+            (SkyboxLayout::Grid, 3, 2, [
+                (0, 0, false),
+                (1, 0, false),
+                (2, 0, false),
+                (0, 1, false),
+                (1, 1, false),
+                (2, 1, false),
+            ]),
+            (SkyboxLayout::VerticalCross, 3, 4, [
+                (2, 1, false),
+                (0, 1, false),
+                (1, 0, false),
+                (1, 2, false),
+                (1, 1, false),
+                (1, 3, true),
+            ]),
+        ];
+        for (layout, columns, rows, cells) in layouts {
+            let converted = convert_skybox(&[lay_out(&stack, columns, rows, cells)], None).unwrap();
+            assert!(converted == stack, "{layout:?} doesn't match");
+        }
+
+        let side = stack.width();
+        let faces: Vec<DynamicImage> = (0..6)
+            .map(|i| DynamicImage::from(stack.view(0, side * i, side, side).to_image()))
+            .collect();
+        assert!(convert_skybox(&faces, None).unwrap() == stack);
+    }
+
+    /// Average color of 32x32 pixels around `x` and `y`.
+    fn patch(image: &impl GenericImageView<Pixel = Rgba<u8>>, x: u32, y: u32) -> [u32; 3] {
+        let mut sum = [0; 3];
+        for (_, _, pixel) in image.view(x - 16, y - 16, 32, 32).pixels() {
+            for (sum, value) in sum.iter_mut().zip(pixel.0) {
+                *sum += value as u32;
+            }
+        }
+        sum.map(|sum| sum / (32 * 32))
+    }
+
+    #[test]
+    fn cross_assets_are_converted() {
+        // Sides of faces and centers of faces in the order of `FACE_NAMES`, measured by hand.
+        let assets = [
+            ("skybox_test.png", 490..=500, [
+                (1839, 830),
+                (842, 830),
+                (1341, 329),
+                (1341, 1329),
+                (1341, 830),
+                (344, 830),
+            ]),
+            ("skybox_unedited.png", 890..=903, [
+                (3160, 1347),
+                (1354, 1347),
+                (2257, 449),
+                (2257, 2245),
+                (2257, 1347),
+                (451, 1347),
+            ]),
+        ];
+        for (name, sides, centers) in assets {
+            let source = asset(name);
+            let image = convert_skybox(std::slice::from_ref(&source), None).unwrap();
+            let side = image.width();
+            assert!(sides.contains(&side), "{name} has side {side}");
+            assert_eq!(image.height(), side * 6, "{name}");
+
+            for (i, (x, y)) in centers.into_iter().enumerate() {
+                let expected = patch(&source, x, y);
+                let face = patch(&image, side / 2, side * i as u32 + side / 2);
+                let close = expected.iter().zip(face).all(|(a, b)| a.abs_diff(b) <= 6);
+                assert!(
+                    close,
+                    "{name} {}: {face:?} instead of {expected:?}",
+                    FACE_NAMES[i]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn equirect_is_reprojected_to_cube() {
+        let (red, blue) = (Rgba([255, 0, 0, 255]), Rgba([0, 0, 255, 255]));
+        let panorama = RgbaImage::from_fn(64, 32, |_, y| if y < 16 { red } else { blue });
+        let cube = convert_skybox(&[DynamicImage::from(panorama)], None).unwrap();
+
+        let side = cube.width();
+        assert_eq!(side, 16);
+        assert!((0..side * side).all(|i| *cube.get_pixel(i % side, side * 2 + i / side) == red));
+        assert!((0..side * side).all(|i| *cube.get_pixel(i % side, side * 3 + i / side) == blue));
+        // Sides are split by the horizon.
+        assert_eq!(*cube.get_pixel(8, 0), red);
+        assert_eq!(*cube.get_pixel(8, side - 1), blue);
+    }
+
+    #[test]
+    fn ktx2_cubemap_is_loaded_by_bevy() {
+        use bevy::image::{CompressedImageFormats, ktx2_buffer_to_image};
+        use bevy::render::render_resource::TextureViewDimension;
+
+        let stack = stacked_faces();
+        let image = ktx2_buffer_to_image(
+            &encode_ktx2_cubemap(&stack),
+            CompressedImageFormats::NONE,
+            true,
+        )
+        .unwrap();
+        assert_eq!(image.texture_descriptor.array_layer_count(), 6);
+        assert_eq!(image.width(), stack.width());
+        assert_eq!(
+            image
+                .texture_view_descriptor
+                .and_then(|view| view.dimension),
+            Some(TextureViewDimension::Cube)
+        );
+        assert_eq!(image.data.as_deref(), Some(stack.as_raw().as_slice()));
+        assert_eq!(SkyboxLayout::from_name("grid"), Some(SkyboxLayout::Grid));
+    }
+}
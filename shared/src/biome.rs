@@ -0,0 +1,54 @@
+/// Biome of a world column, chosen by the world generator.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Biome {
+    #[default]
+    Plains = 0,
+    Ocean = 1,
+    Beach = 2,
+    Forest = 3,
+    Desert = 4,
+    Mountains = 5,
+    SnowyPlains = 6,
+}
+
+impl Biome {
+    /// Namespaced name of the biome.
+    pub fn name(self) -> &'static str {
+        match self {
+            Biome::Plains => "minecraft:plains",
+            Biome::Ocean => "minecraft:ocean",
+            Biome::Beach => "minecraft:beach",
+            Biome::Forest => "minecraft:forest",
+            Biome::Desert => "minecraft:desert",
+            Biome::Mountains => "minecraft:windswept_hills",
+            Biome::SnowyPlains => "minecraft:snowy_plains",
+        }
+    }
+
+    /// Temperature of the biome, used for colormaps and precipitation.
+    pub fn temperature(self) -> f32 {
+        match self {
+            Biome::Plains => 0.8,
+            Biome::Ocean => 0.5,
+            Biome::Beach => 0.8,
+            Biome::Forest => 0.7,
+            Biome::Desert => 2.0,
+            Biome::Mountains => 0.2,
+            Biome::SnowyPlains => 0.0,
+        }
+    }
+
+    /// Downfall (humidity) of the biome, used for colormaps and precipitation.
+    pub fn downfall(self) -> f32 {
+        match self {
+            Biome::Plains => 0.4,
+            Biome::Ocean => 0.5,
+            Biome::Beach => 0.4,
+            Biome::Forest => 0.8,
+            Biome::Desert => 0.0,
+            Biome::Mountains => 0.3,
+            Biome::SnowyPlains => 0.5,
+        }
+    }
+}
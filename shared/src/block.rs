@@ -0,0 +1,162 @@
+/// Texture names of block faces, resolved by resource pack.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BlockTextures {
+    pub top: &'static str,
+    pub bottom: &'static str,
+    pub side: &'static str,
+}
+
+impl BlockTextures {
+    /// Same texture on every face.
+    pub const fn all(texture: &'static str) -> Self {
+        Self {
+            top: texture,
+            bottom: texture,
+            side: texture,
+        }
+    }
+}
+
+/// Block type stored in chunk sections.
+///
+/// Discriminants are network/storage ids, so never reorder existing variants.
+#[repr(u8)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum Block {
+    #[default]
+    Air = 0,
+    Stone = 1,
+    Dirt = 2,
+    Grass = 3,
+    Bedrock = 4,
+    Water = 5,
+    Sand = 6,
+    Gravel = 7,
+    Snow = 8,
+    Glowstone = 9,
+    Leaves = 10,
+    Glass = 11,
+}
+
+impl Block {
+    /// Every registered block, indexed by id.
+    pub const ALL: [Block; 12] = [
+        Block::Air,
+        Block::Stone,
+        Block::Dirt,
+        Block::Grass,
+        Block::Bedrock,
+        Block::Water,
+        Block::Sand,
+        Block::Gravel,
+        Block::Snow,
+        Block::Glowstone,
+        Block::Leaves,
+        Block::Glass,
+    ];
+
+    /// Storage id of the block.
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    /// Returns block by its storage id, if it is registered.
+    pub fn from_id(id: u8) -> Option<Block> {
+        Block::ALL.get(id as usize).copied()
+    }
+
+    /// Namespaced name of the block, as used by resource packs.
+    pub fn name(self) -> &'static str {
+        match self {
+            Block::Air => "minecraft:air",
+            Block::Stone => "minecraft:stone",
+            Block::Dirt => "minecraft:dirt",
+            Block::Grass => "minecraft:grass_block",
+            Block::Bedrock => "minecraft:bedrock",
+            Block::Water => "minecraft:water",
+            Block::Sand => "minecraft:sand",
+            Block::Gravel => "minecraft:gravel",
+            Block::Snow => "minecraft:snow_block",
+            Block::Glowstone => "minecraft:glowstone",
+            Block::Leaves => "minecraft:oak_leaves",
+            Block::Glass => "minecraft:white_stained_glass",
+        }
+    }
+
+    /// Returns block by its namespaced name, as used by item ids in inventories.
+    pub fn from_name(name: &str) -> Option<Block> {
+        Block::ALL.into_iter().find(|block| block.name() == name)
+    }
+
+    pub fn is_air(self) -> bool {
+        self == Block::Air
+    }
+
+    /// Liquids are not solid and can be replaced by placing blocks.
+    pub fn is_liquid(self) -> bool {
+        self == Block::Water
+    }
+
+    /// Whether entities collide with the block.
+    pub fn is_solid(self) -> bool {
+        !self.is_air() && !self.is_liquid()
+    }
+
+    /// Whether block fully hides faces of its neighbours.
+    pub fn is_opaque(self) -> bool {
+        !self.is_air() && !self.is_liquid() && !matches!(self, Block::Leaves | Block::Glass)
+    }
+
+    /// How long the block resists breaking, `None` for blocks that can't be broken.
+    pub fn hardness(self) -> Option<f32> {
+        match self {
+            Block::Air | Block::Water | Block::Bedrock => None,
+            Block::Stone => Some(1.5),
+            Block::Dirt | Block::Sand => Some(0.5),
+            Block::Grass | Block::Gravel => Some(0.6),
+            Block::Snow | Block::Leaves => Some(0.2),
+            Block::Glowstone | Block::Glass => Some(0.3),
+        }
+    }
+
+    /// Block light level emitted by the block, from 0 to 15.
+    pub fn light_emission(self) -> u8 {
+        match self {
+            Block::Glowstone => 15,
+            _ => 0,
+        }
+    }
+
+    /// How much light is lost when passing through the block, 15 stops light completely.
+    pub fn light_opacity(self) -> u8 {
+        match self {
+            Block::Air => 0,
+            Block::Water => 2,
+            Block::Leaves => 1,
+            _ if self.is_opaque() => 15,
+            _ => 0,
+        }
+    }
+
+    /// Textures of block faces, `None` for blocks that are not rendered.
+    pub fn textures(self) -> Option<BlockTextures> {
+        match self {
+            Block::Air => None,
+            Block::Stone => Some(BlockTextures::all("stone")),
+            Block::Dirt => Some(BlockTextures::all("dirt")),
+            Block::Grass => Some(BlockTextures {
+                top: "grass_top",
+                bottom: "dirt",
+                side: "grass_side",
+            }),
+            Block::Bedrock => Some(BlockTextures::all("bedrock")),
+            Block::Water => Some(BlockTextures::all("water_still")),
+            Block::Sand => Some(BlockTextures::all("sand")),
+            Block::Gravel => Some(BlockTextures::all("gravel")),
+            Block::Snow => Some(BlockTextures::all("snow")),
+            Block::Glowstone => Some(BlockTextures::all("glowstone")),
+            Block::Leaves => Some(BlockTextures::all("oak_leaves")),
+            Block::Glass => Some(BlockTextures::all("white_stained_glass")),
+        }
+    }
+}
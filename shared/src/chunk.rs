@@ -0,0 +1,380 @@
+use crate::biome::Biome;
+use crate::block::Block;
+use crate::light::{LightKind, MAX_LIGHT};
+
+/// Width, depth and height of a chunk section in blocks.
+pub const SECTION_SIZE: usize = 16;
+/// Amount of blocks in a chunk section.
+pub const SECTION_VOLUME: usize = SECTION_SIZE * SECTION_SIZE * SECTION_SIZE;
+/// Amount of sections stacked in a chunk.
+pub const SECTION_COUNT: usize = 16;
+/// Height of a chunk in blocks, world spans `0..CHUNK_HEIGHT` on y axis.
+pub const CHUNK_HEIGHT: i32 = (SECTION_SIZE * SECTION_COUNT) as i32;
+/// Side of a biome cell in blocks (biomes are stored per 4x4x4 cell).
+pub const BIOME_CELL_SIZE: usize = 4;
+
+/// Position of a chunk column in chunk coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, PartialOrd, Ord)]
+pub struct ChunkPos {
+    pub x: i32,
+    pub z: i32,
+}
+
+impl ChunkPos {
+    pub const fn new(x: i32, z: i32) -> Self {
+        Self { x, z }
+    }
+
+    /// Chunk that contains block column at `x`, `z`.
+    pub fn from_block(x: i32, z: i32) -> Self {
+        Self {
+            x: x.div_euclid(SECTION_SIZE as i32),
+            z: z.div_euclid(SECTION_SIZE as i32),
+        }
+    }
+
+    /// World x coordinate of the first block column in this chunk.
+    pub fn min_block_x(self) -> i32 {
+        self.x * SECTION_SIZE as i32
+    }
+
+    /// World z coordinate of the first block column in this chunk.
+    pub fn min_block_z(self) -> i32 {
+        self.z * SECTION_SIZE as i32
+    }
+}
+
+/// Position of a block in world coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct BlockPos {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl BlockPos {
+    pub const fn new(x: i32, y: i32, z: i32) -> Self {
+        Self { x, y, z }
+    }
+
+    /// Chunk that contains this block.
+    pub fn chunk(self) -> ChunkPos {
+        ChunkPos::from_block(self.x, self.z)
+    }
+
+    /// Coordinates of this block inside its chunk.
+    pub fn local(self) -> (usize, i32, usize) {
+        (
+            self.x.rem_euclid(SECTION_SIZE as i32) as usize,
+            self.y,
+            self.z.rem_euclid(SECTION_SIZE as i32) as usize,
+        )
+    }
+
+    pub fn offset(self, dx: i32, dy: i32, dz: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy, self.z + dz)
+    }
+}
+
+/// Index of a block inside a section, stored in YZX order.
+pub fn section_index(x: usize, y: usize, z: usize) -> usize {
+    (y * SECTION_SIZE + z) * SECTION_SIZE + x
+}
+
+/// Array of 4-bit values, two per byte, used to store light levels.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct NibbleArray {
+    data: Box<[u8; SECTION_VOLUME / 2]>,
+}
+
+impl NibbleArray {
+    /// Creates array where every nibble equals `value`.
+    pub fn filled(value: u8) -> Self {
+        let value = value & 0xf;
+        Self {
+            data: Box::new([value | (value << 4); SECTION_VOLUME / 2]),
+        }
+    }
+
+    pub fn get(&self, index: usize) -> u8 {
+        let byte = self.data[index / 2];
+        if index & 1 == 0 {
+            byte & 0xf
+        } else {
+            byte >> 4
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: u8) {
+        let byte = &mut self.data[index / 2];
+        if index & 1 == 0 {
+            *byte = (*byte & 0xf0) | (value & 0xf);
+        } else {
+            *byte = (*byte & 0x0f) | ((value & 0xf) << 4);
+        }
+    }
+
+    /// Raw bytes, as sent over network.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..]
+    }
+}
+
+impl Default for NibbleArray {
+    fn default() -> Self {
+        Self::filled(0)
+    }
+}
+
+/// Amount of biome cells per section side.
+const BIOME_CELLS: usize = SECTION_SIZE / BIOME_CELL_SIZE;
+
+/// Palette of biomes of a section: one entry per 4x4x4 cell, pointing into `palette`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BiomePalette {
+    palette: Vec<Biome>,
+    cells: [u8; BIOME_CELLS * BIOME_CELLS * BIOME_CELLS],
+}
+
+impl BiomePalette {
+    pub fn single(biome: Biome) -> Self {
+        Self {
+            palette: vec![biome],
+            cells: [0; BIOME_CELLS * BIOME_CELLS * BIOME_CELLS],
+        }
+    }
+
+    /// Biome of cell that contains block `x`, `y`, `z` (section-local).
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Biome {
+        let cell = Self::cell_index(
+            x / BIOME_CELL_SIZE,
+            y / BIOME_CELL_SIZE,
+            z / BIOME_CELL_SIZE,
+        );
+        self.palette[self.cells[cell] as usize]
+    }
+
+    /// Sets biome of cell `cx`, `cy`, `cz` (in cell coordinates).
+    pub fn set_cell(&mut self, cx: usize, cy: usize, cz: usize, biome: Biome) {
+        let entry = match self.palette.iter().position(|b| *b == biome) {
+            Some(entry) => entry,
+            None => {
+                self.palette.push(biome);
+                self.palette.len() - 1
+            },
+        };
+        self.cells[Self::cell_index(cx, cy, cz)] = entry as u8;
+    }
+
+    /// Distinct biomes present in the section.
+    pub fn palette(&self) -> &[Biome] {
+        &self.palette
+    }
+
+    fn cell_index(cx: usize, cy: usize, cz: usize) -> usize {
+        (cy * BIOME_CELLS + cz) * BIOME_CELLS + cx
+    }
+}
+
+impl Default for BiomePalette {
+    fn default() -> Self {
+        Self::single(Biome::default())
+    }
+}
+
+/// 16x16x16 blocks of a chunk with their light and biomes.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Section {
+    blocks: Box<[Block; SECTION_VOLUME]>,
+    non_air_count: u16,
+    pub sky_light: NibbleArray,
+    pub block_light: NibbleArray,
+    pub biomes: BiomePalette,
+}
+
+impl Default for Section {
+    fn default() -> Self {
+        Self {
+            blocks: Box::new([Block::Air; SECTION_VOLUME]),
+            non_air_count: 0,
+            sky_light: NibbleArray::default(),
+            block_light: NibbleArray::default(),
+            biomes: BiomePalette::default(),
+        }
+    }
+}
+
+impl Section {
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Block {
+        self.blocks[section_index(x, y, z)]
+    }
+
+    /// Sets block and returns previous one.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, block: Block) -> Block {
+        let old = std::mem::replace(&mut self.blocks[section_index(x, y, z)], block);
+        if old.is_air() && !block.is_air() {
+            self.non_air_count += 1;
+        } else if !old.is_air() && block.is_air() {
+            self.non_air_count -= 1;
+        }
+        old
+    }
+
+    /// Section has only air in it.
+    pub fn is_empty(&self) -> bool {
+        self.non_air_count == 0
+    }
+
+    /// All blocks of the section in YZX order.
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks[..]
+    }
+}
+
+/// Column of [`SECTION_COUNT`] sections.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Chunk {
+    pub pos: ChunkPos,
+    sections: Vec<Section>,
+    /// Per-column y of the first air block above the highest non-air block, XZ order.
+    heightmap: [i32; SECTION_SIZE * SECTION_SIZE],
+}
+
+impl Chunk {
+    /// Creates chunk filled with air.
+    pub fn new(pos: ChunkPos) -> Self {
+        Self {
+            pos,
+            sections: vec![Section::default(); SECTION_COUNT],
+            heightmap: [0; SECTION_SIZE * SECTION_SIZE],
+        }
+    }
+
+    /// Returns block at chunk-local coordinates, air if `y` is out of the world.
+    pub fn get(&self, x: usize, y: i32, z: usize) -> Block {
+        if !(0..CHUNK_HEIGHT).contains(&y) {
+            return Block::Air;
+        }
+        let y = y as usize;
+        self.sections[y / SECTION_SIZE].get(x, y % SECTION_SIZE, z)
+    }
+
+    /// Sets block at chunk-local coordinates and returns previous one.
+    ///
+    /// Blocks out of the world are ignored.
+    pub fn set(&mut self, x: usize, y: i32, z: usize, block: Block) -> Block {
+        if !(0..CHUNK_HEIGHT).contains(&y) {
+            return Block::Air;
+        }
+        let uy = y as usize;
+        let old = self.sections[uy / SECTION_SIZE].set(x, uy % SECTION_SIZE, z, block);
+
+        let height = &mut self.heightmap[z * SECTION_SIZE + x];
+        if !block.is_air() && y >= *height {
+            *height = y + 1;
+        } else if block.is_air() && y + 1 == *height {
+            let mut top = y;
+            while top > 0 && self.get(x, top - 1, z).is_air() {
+                top -= 1;
+            }
+            self.heightmap[z * SECTION_SIZE + x] = top;
+        }
+        old
+    }
+
+    /// Y of the first air block above the highest non-air block in the column.
+    pub fn height(&self, x: usize, z: usize) -> i32 {
+        self.heightmap[z * SECTION_SIZE + x]
+    }
+
+    /// Light at chunk-local coordinates. Above the world there is full sky light.
+    pub fn light(&self, kind: LightKind, x: usize, y: i32, z: usize) -> u8 {
+        if !(0..CHUNK_HEIGHT).contains(&y) {
+            return match kind {
+                LightKind::Sky if y >= CHUNK_HEIGHT => MAX_LIGHT,
+                _ => 0,
+            };
+        }
+        let y = y as usize;
+        let section = &self.sections[y / SECTION_SIZE];
+        let index = section_index(x, y % SECTION_SIZE, z);
+        match kind {
+            LightKind::Sky => section.sky_light.get(index),
+            LightKind::Block => section.block_light.get(index),
+        }
+    }
+
+    /// Sets light at chunk-local coordinates, ignored out of the world.
+    pub fn set_light(&mut self, kind: LightKind, x: usize, y: i32, z: usize, value: u8) {
+        if !(0..CHUNK_HEIGHT).contains(&y) {
+            return;
+        }
+        let y = y as usize;
+        let section = &mut self.sections[y / SECTION_SIZE];
+        let index = section_index(x, y % SECTION_SIZE, z);
+        match kind {
+            LightKind::Sky => section.sky_light.set(index, value),
+            LightKind::Block => section.block_light.set(index, value),
+        }
+    }
+
+    /// Biome at chunk-local coordinates.
+    pub fn biome(&self, x: usize, y: i32, z: usize) -> Biome {
+        let y = y.clamp(0, CHUNK_HEIGHT - 1) as usize;
+        self.sections[y / SECTION_SIZE]
+            .biomes
+            .get(x, y % SECTION_SIZE, z)
+    }
+
+    pub fn section(&self, index: usize) -> &Section {
+        &self.sections[index]
+    }
+
+    pub fn section_mut(&mut self, index: usize) -> &mut Section {
+        &mut self.sections[index]
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// Stable FNV-1a hash of all blocks, used to compare chunks between client and server.
+    pub fn block_hash(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for section in &self.sections {
+            for block in section.blocks() {
+                hash ^= block.id() as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nibble_array_roundtrip() {
+        let mut nibbles = NibbleArray::filled(15);
+        nibbles.set(0, 3);
+        nibbles.set(1, 7);
+        assert_eq!(nibbles.get(0), 3);
+        assert_eq!(nibbles.get(1), 7);
+        assert_eq!(nibbles.get(2), 15);
+    }
+
+    #[test]
+    fn heightmap_follows_blocks() {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        chunk.set(1, 10, 2, Block::Stone);
+        chunk.set(1, 40, 2, Block::Dirt);
+        assert_eq!(chunk.height(1, 2), 41);
+
+        chunk.set(1, 40, 2, Block::Air);
+        assert_eq!(chunk.height(1, 2), 11);
+        assert!(!chunk.section(0).is_empty());
+        assert!(chunk.section(2).is_empty());
+    }
+}
@@ -1,3 +1,25 @@
+/// Biomes of the world.
+pub mod biome;
+/// Block registry.
+pub mod block;
+/// Chunk storage: sections, light nibbles and biome palettes.
+pub mod chunk;
+/// Sky and block light propagation.
+pub mod light;
+/// Deterministic noise functions.
+///
+/// Only basic float arithmetic is used (no `sin`, `powf`, etc.), so results are bit-identical on
+/// every platform.
+pub mod noise;
+/// Player movement and collision with blocks, shared so that server can validate it.
+pub mod physics;
+/// Day and night cycle in ticks, shared so that server advances it the same way.
+pub mod time;
+/// Weather cycle and precipitation, shared so that server advances it the same way.
+pub mod weather;
+/// Seeded procedural terrain generation.
+pub mod worldgen;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
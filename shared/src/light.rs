@@ -0,0 +1,366 @@
+use std::collections::VecDeque;
+
+use crate::block::Block;
+use crate::chunk::{BlockPos, CHUNK_HEIGHT, Chunk, ChunkPos, SECTION_SIZE};
+
+/// Brightest light level.
+pub const MAX_LIGHT: u8 = 15;
+
+/// Offsets to the six neighbours of a block.
+const NEIGHBOURS: [(i32, i32, i32); 6] = [
+    (0, -1, 0),
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+    (-1, 0, 0),
+    (1, 0, 0),
+];
+
+/// Light of a block comes either from the sky or from light-emitting blocks.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum LightKind {
+    Sky,
+    Block,
+}
+
+impl LightKind {
+    pub const ALL: [LightKind; 2] = [LightKind::Sky, LightKind::Block];
+}
+
+/// Blocks and light of loaded chunks, as seen by the light engine.
+///
+/// Positions are always inside the world height, the engine handles the rest.
+pub trait LightStorage {
+    /// Block at `pos`, `None` if its chunk is not loaded.
+    fn block(&self, pos: BlockPos) -> Option<Block>;
+    /// Light at `pos`, `None` if its chunk is not loaded.
+    fn light(&self, kind: LightKind, pos: BlockPos) -> Option<u8>;
+    /// Sets light at `pos`, ignored if its chunk is not loaded.
+    fn set_light(&mut self, kind: LightKind, pos: BlockPos, value: u8);
+}
+
+/// A single chunk knows nothing about its neighbours.
+impl LightStorage for Chunk {
+    fn block(&self, pos: BlockPos) -> Option<Block> {
+        let (x, y, z) = pos.local();
+        (pos.chunk() == self.pos).then(|| self.get(x, y, z))
+    }
+
+    fn light(&self, kind: LightKind, pos: BlockPos) -> Option<u8> {
+        let (x, y, z) = pos.local();
+        (pos.chunk() == self.pos).then(|| self.light(kind, x, y, z))
+    }
+
+    fn set_light(&mut self, kind: LightKind, pos: BlockPos, value: u8) {
+        let (x, y, z) = pos.local();
+        if pos.chunk() == self.pos {
+            Chunk::set_light(self, kind, x, y, z, value);
+        }
+    }
+}
+
+/// Block at `pos`, air above the world and nothing below it.
+fn block_at(storage: &impl LightStorage, pos: BlockPos) -> Option<Block> {
+    match pos.y {
+        ..0 => None,
+        CHUNK_HEIGHT.. => Some(Block::Air),
+        _ => storage.block(pos),
+    }
+}
+
+/// Light at `pos`. Above the world there is full sky light, below it nothing.
+fn light_at(storage: &impl LightStorage, kind: LightKind, pos: BlockPos) -> Option<u8> {
+    match (pos.y, kind) {
+        (..0, _) => None,
+        (CHUNK_HEIGHT.., LightKind::Sky) => Some(MAX_LIGHT),
+        (CHUNK_HEIGHT.., LightKind::Block) => Some(0),
+        _ => storage.light(kind, pos),
+    }
+}
+
+fn set_light_at(storage: &mut impl LightStorage, kind: LightKind, pos: BlockPos, value: u8) {
+    if (0..CHUNK_HEIGHT).contains(&pos.y) {
+        storage.set_light(kind, pos, value);
+    }
+}
+
+/// Light that comes from `level` into neighbour `block` along `dy`.
+///
+/// Full sky light goes down through transparent blocks without getting dimmer.
+fn light_through(kind: LightKind, level: u8, block: Block, dy: i32) -> u8 {
+    let opacity = block.light_opacity();
+    if kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT && opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(opacity.max(1))
+    }
+}
+
+/// Flood-fills light from every position of `queue` to neighbours that are darker.
+fn spread(storage: &mut impl LightStorage, kind: LightKind, mut queue: VecDeque<BlockPos>) {
+    while let Some(pos) = queue.pop_front() {
+        let Some(level) = light_at(storage, kind, pos) else {
+            continue;
+        };
+        if level <= 1 {
+            continue;
+        }
+
+        for (dx, dy, dz) in NEIGHBOURS {
+            let next = pos.offset(dx, dy, dz);
+            let Some(block) = block_at(storage, next) else {
+                continue;
+            };
+            if block.light_opacity() >= MAX_LIGHT {
+                continue;
+            }
+
+            let new = light_through(kind, level, block, dy);
+            if light_at(storage, kind, next).is_some_and(|current| current < new) {
+                set_light_at(storage, kind, next, new);
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+/// Darkens everything that was lit through `pos`, which had light `level` and is already dark.
+///
+/// Returns positions that are lit from elsewhere, light is spread from them again.
+fn remove(
+    storage: &mut impl LightStorage,
+    kind: LightKind,
+    pos: BlockPos,
+    level: u8,
+) -> VecDeque<BlockPos> {
+    let mut removal = VecDeque::from([(pos, level)]);
+    let mut relight = VecDeque::new();
+
+    while let Some((pos, level)) = removal.pop_front() {
+        for (dx, dy, dz) in NEIGHBOURS {
+            let next = pos.offset(dx, dy, dz);
+            let Some(current) = light_at(storage, kind, next) else {
+                continue;
+            };
+            if current == 0 {
+                continue;
+            }
+
+            let sky_column = kind == LightKind::Sky && dy == -1 && level == MAX_LIGHT;
+            if (current < level || sky_column) && next.y < CHUNK_HEIGHT {
+                set_light_at(storage, kind, next, 0);
+                removal.push_back((next, current));
+
+                // Light sources keep shining.
+                let emission = match kind {
+                    LightKind::Sky => 0,
+                    LightKind::Block => block_at(storage, next).map_or(0, |b| b.light_emission()),
+                };
+                if emission > 0 {
+                    set_light_at(storage, kind, next, emission);
+                    relight.push_back(next);
+                }
+            } else {
+                relight.push_back(next);
+            }
+        }
+    }
+    relight
+}
+
+/// Lights a freshly generated chunk on its own.
+///
+/// Light coming from neighbours is added later by [`propagate_borders`].
+pub fn light_chunk(chunk: &mut Chunk) {
+    let mut top = 0;
+    for z in 0..SECTION_SIZE {
+        for x in 0..SECTION_SIZE {
+            top = top.max(chunk.height(x, z));
+        }
+    }
+
+    let mut sky = VecDeque::new();
+    let mut block = VecDeque::new();
+    for z in 0..SECTION_SIZE {
+        for x in 0..SECTION_SIZE {
+            // Sky light falls straight down until something stops it.
+            let mut y = CHUNK_HEIGHT - 1;
+            while y >= 0 && chunk.get(x, y, z).light_opacity() == 0 {
+                chunk.set_light(LightKind::Sky, x, y, z, MAX_LIGHT);
+                // Above the terrain everything is lit already, there's nowhere to spread.
+                if y <= top {
+                    sky.push_back(block_pos(chunk.pos, x, y, z));
+                }
+                y -= 1;
+            }
+
+            for y in 0..chunk.height(x, z) {
+                let emission = chunk.get(x, y, z).light_emission();
+                if emission > 0 {
+                    chunk.set_light(LightKind::Block, x, y, z, emission);
+                    block.push_back(block_pos(chunk.pos, x, y, z));
+                }
+            }
+        }
+    }
+
+    spread(chunk, LightKind::Sky, sky);
+    spread(chunk, LightKind::Block, block);
+}
+
+fn block_pos(chunk: ChunkPos, x: usize, y: i32, z: usize) -> BlockPos {
+    BlockPos::new(
+        chunk.min_block_x() + x as i32,
+        y,
+        chunk.min_block_z() + z as i32,
+    )
+}
+
+/// Spreads light both ways across borders between chunk at `pos` and its loaded neighbours.
+pub fn propagate_borders(storage: &mut impl LightStorage, pos: ChunkPos) {
+    let last = SECTION_SIZE - 1;
+    let mut queue = VecDeque::new();
+    for y in 0..CHUNK_HEIGHT {
+        for i in 0..SECTION_SIZE {
+            // Border blocks of the chunk and the blocks next to them in neighbours.
+            for (x, z, dx, dz) in [
+                (i, 0, 0, -1),
+                (i, last, 0, 1),
+                (0, i, -1, 0),
+                (last, i, 1, 0),
+            ] {
+                let border = block_pos(pos, x, y, z);
+                queue.push_back(border);
+                queue.push_back(border.offset(dx, 0, dz));
+            }
+        }
+    }
+
+    for kind in LightKind::ALL {
+        spread(storage, kind, queue.clone());
+    }
+}
+
+/// Updates light after block at `pos` was placed or broken.
+///
+/// Light can change up to [`MAX_LIGHT`] blocks away, including neighbouring chunks.
+pub fn update_light(storage: &mut impl LightStorage, pos: BlockPos) {
+    let Some(block) = block_at(storage, pos) else {
+        return;
+    };
+
+    for kind in LightKind::ALL {
+        let Some(old) = light_at(storage, kind, pos) else {
+            continue;
+        };
+        set_light_at(storage, kind, pos, 0);
+        let mut relight = remove(storage, kind, pos, old);
+
+        if kind == LightKind::Block && block.light_emission() > 0 {
+            set_light_at(storage, kind, pos, block.light_emission());
+            relight.push_back(pos);
+        }
+        // Light can now come into the block, if it became transparent.
+        relight.extend(NEIGHBOURS.map(|(dx, dy, dz)| pos.offset(dx, dy, dz)));
+        spread(storage, kind, relight);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::worldgen::WorldGenerator;
+
+    fn lit(blocks: &[(usize, i32, usize, Block)]) -> Chunk {
+        let mut chunk = Chunk::new(ChunkPos::new(0, 0));
+        for x in 0..SECTION_SIZE {
+            for z in 0..SECTION_SIZE {
+                chunk.set(x, 0, z, Block::Stone);
+            }
+        }
+        for (x, y, z, block) in blocks {
+            chunk.set(*x, *y, *z, *block);
+        }
+        light_chunk(&mut chunk);
+        chunk
+    }
+
+    /// Light computed from scratch, to compare incremental updates with.
+    fn relit(chunk: &Chunk) -> Chunk {
+        let mut fresh = chunk.clone();
+        for y in 0..CHUNK_HEIGHT {
+            for z in 0..SECTION_SIZE {
+                for x in 0..SECTION_SIZE {
+                    for kind in LightKind::ALL {
+                        fresh.set_light(kind, x, y, z, 0);
+                    }
+                }
+            }
+        }
+        light_chunk(&mut fresh);
+        fresh
+    }
+
+    #[test]
+    fn sky_light_falls_down() {
+        let chunk = lit(&[]);
+        assert_eq!(chunk.light(LightKind::Sky, 3, 1, 3), MAX_LIGHT);
+        assert_eq!(chunk.light(LightKind::Sky, 3, 255, 3), MAX_LIGHT);
+        assert_eq!(chunk.light(LightKind::Sky, 3, 0, 3), 0);
+    }
+
+    #[test]
+    fn light_goes_around_roof() {
+        let mut blocks = Vec::new();
+        for x in 0..8 {
+            for z in 0..8 {
+                blocks.push((x, 5, z, Block::Stone));
+            }
+        }
+        let chunk = lit(&blocks);
+
+        // Under the corner of the roof light comes from the side.
+        assert_eq!(chunk.light(LightKind::Sky, 7, 4, 7), MAX_LIGHT - 1);
+        // Deeper under the roof it gets darker with distance to its edge.
+        assert_eq!(chunk.light(LightKind::Sky, 0, 4, 0), MAX_LIGHT - 8);
+        assert_eq!(chunk.light(LightKind::Sky, 0, 1, 0), MAX_LIGHT - 8);
+    }
+
+    #[test]
+    fn water_dims_sky_light() {
+        let chunk = lit(&[(4, 2, 4, Block::Water), (4, 1, 4, Block::Water)]);
+        assert_eq!(chunk.light(LightKind::Sky, 4, 2, 4), MAX_LIGHT - 2);
+        // Lit from the side as brightly as from above.
+        assert_eq!(chunk.light(LightKind::Sky, 4, 1, 4), MAX_LIGHT - 2);
+    }
+
+    #[test]
+    fn block_light_spreads_from_source() {
+        let chunk = lit(&[(8, 1, 8, Block::Glowstone)]);
+        assert_eq!(chunk.light(LightKind::Block, 8, 1, 8), 15);
+        assert_eq!(chunk.light(LightKind::Block, 8, 2, 8), 14);
+        assert_eq!(chunk.light(LightKind::Block, 11, 1, 8), 12);
+        assert_eq!(chunk.light(LightKind::Block, 10, 2, 9), 11);
+    }
+
+    #[test]
+    fn incremental_updates_match_full_lighting() {
+        let mut chunk = WorldGenerator::new(7).generate_chunk(ChunkPos::new(0, 0));
+        light_chunk(&mut chunk);
+
+        let height = chunk.height(5, 5);
+        let changes = [
+            (5, height, 5, Block::Glowstone),
+            (5, height + 1, 5, Block::Stone),
+            (6, height - 1, 5, Block::Air),
+            (5, height, 5, Block::Air),
+            (6, height - 1, 5, Block::Glowstone),
+            (6, height - 1, 5, Block::Air),
+        ];
+        for (x, y, z, block) in changes {
+            chunk.set(x, y, z, block);
+            update_light(&mut chunk, BlockPos::new(x as i32, y, z as i32));
+            assert_eq!(chunk, relit(&chunk));
+        }
+    }
+}
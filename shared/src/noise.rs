@@ -0,0 +1,189 @@
+/// SplitMix64 random generator, small and fully defined by its seed.
+#[derive(Clone, Debug)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Hashes a seed and integer coordinates into a pseudo-random value.
+pub fn hash_position(seed: u64, x: i32, y: i32, z: i32) -> u64 {
+    let mut rng = SplitMix64::new(
+        seed ^ (x as u32 as u64).wrapping_mul(0x9e37_79b9)
+            ^ ((y as u32 as u64).wrapping_mul(0x85eb_ca6b) << 16)
+            ^ ((z as u32 as u64).wrapping_mul(0xc2b2_ae35) << 32),
+    );
+    rng.next_u64()
+}
+
+/// Classic Perlin gradient noise with seeded permutation table.
+#[derive(Clone, Debug)]
+pub struct Perlin {
+    perm: [u8; 512],
+    /// Offsets break the lattice alignment between octaves.
+    offset: [f64; 3],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        let mut table: [u8; 256] = [0; 256];
+        for (i, v) in table.iter_mut().enumerate() {
+            *v = i as u8;
+        }
+        // Fisher-Yates shuffle.
+        for i in (1..256).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+
+        let mut perm = [0; 512];
+        for (i, v) in perm.iter_mut().enumerate() {
+            *v = table[i & 255];
+        }
+
+        let mut offset = [0.; 3];
+        for o in offset.iter_mut() {
+            *o = (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64 * 256.;
+        }
+
+        Self { perm, offset }
+    }
+
+    /// 2D noise in range of roughly `[-1, 1]`.
+    pub fn noise2(&self, x: f64, z: f64) -> f64 {
+        self.noise3(x, 0., z)
+    }
+
+    /// 3D noise in range of roughly `[-1, 1]`.
+    pub fn noise3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (x, y, z) = (x + self.offset[0], y + self.offset[1], z + self.offset[2]);
+        let (fx, fy, fz) = (x.floor(), y.floor(), z.floor());
+        let (xi, yi, zi) = (
+            (fx as i64 & 255) as usize,
+            (fy as i64 & 255) as usize,
+            (fz as i64 & 255) as usize,
+        );
+        let (x, y, z) = (x - fx, y - fy, z - fz);
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let p = &self.perm;
+        let a = p[xi] as usize + yi;
+        let aa = p[a] as usize + zi;
+        let ab = p[a + 1] as usize + zi;
+        let b = p[xi + 1] as usize + yi;
+        let ba = p[b] as usize + zi;
+        let bb = p[b + 1] as usize + zi;
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p[aa], x, y, z), grad(p[ba], x - 1., y, z)),
+                lerp(u, grad(p[ab], x, y - 1., z), grad(p[bb], x - 1., y - 1., z)),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p[aa + 1], x, y, z - 1.),
+                    grad(p[ba + 1], x - 1., y, z - 1.),
+                ),
+                lerp(
+                    u,
+                    grad(p[ab + 1], x, y - 1., z - 1.),
+                    grad(p[bb + 1], x - 1., y - 1., z - 1.),
+                ),
+            ),
+        )
+    }
+}
+
+/// Several octaves of [`Perlin`] noise summed together (fractal Brownian motion).
+#[derive(Clone, Debug)]
+pub struct OctaveNoise {
+    octaves: Vec<Perlin>,
+    /// Frequency of the first octave.
+    frequency: f64,
+    /// Amplitude multiplier per octave.
+    persistence: f64,
+    /// Frequency multiplier per octave.
+    lacunarity: f64,
+}
+
+impl OctaveNoise {
+    pub fn new(
+        seed: u64,
+        octave_count: usize,
+        frequency: f64,
+        persistence: f64,
+        lacunarity: f64,
+    ) -> Self {
+        let mut rng = SplitMix64::new(seed);
+        Self {
+            octaves: (0..octave_count)
+                .map(|_| Perlin::new(rng.next_u64()))
+                .collect(),
+            frequency,
+            persistence,
+            lacunarity,
+        }
+    }
+
+    /// Normalized 2D sample in range of roughly `[-1, 1]`.
+    pub fn sample2(&self, x: f64, z: f64) -> f64 {
+        self.sample3(x, 0., z)
+    }
+
+    /// Normalized 3D sample in range of roughly `[-1, 1]`.
+    pub fn sample3(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut total = 0.;
+        let mut amplitude = 1.;
+        let mut amplitude_sum = 0.;
+        let mut frequency = self.frequency;
+        for octave in &self.octaves {
+            total += octave.noise3(x * frequency, y * frequency, z * frequency) * amplitude;
+            amplitude_sum += amplitude;
+            amplitude *= self.persistence;
+            frequency *= self.lacunarity;
+        }
+        if amplitude_sum > 0. {
+            total / amplitude_sum
+        } else {
+            0.
+        }
+    }
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
@@ -0,0 +1,568 @@
+use crate::block::Block;
+use crate::chunk::BlockPos;
+
+/// Width of player's collision box.
+pub const PLAYER_WIDTH: f64 = 0.6;
+/// Height of player's collision box.
+pub const PLAYER_HEIGHT: f64 = 1.8;
+/// Height of player's eyes above feet.
+pub const EYE_HEIGHT: f64 = 1.62;
+/// Height of player's eyes above feet while sneaking.
+pub const SNEAK_EYE_HEIGHT: f64 = 1.27;
+/// Highest ledge that is walked onto without jumping, such as a slab.
+pub const STEP_HEIGHT: f64 = 0.6;
+
+/// Downward acceleration, in blocks per second squared.
+pub const GRAVITY: f64 = 32.;
+/// Fastest falling speed, in blocks per second.
+pub const TERMINAL_VELOCITY: f64 = 78.4;
+/// Upward speed of a jump, enough to jump on a block 1.25 blocks high.
+pub const JUMP_VELOCITY: f64 = 8.95;
+
+/// Walking speed in blocks per second is `walk_speed` ability multiplied by this.
+pub const WALK_SPEED_SCALE: f64 = 43.17;
+/// Flying speed in blocks per second is `fly_speed` ability multiplied by this.
+pub const FLY_SPEED_SCALE: f64 = 217.8;
+/// Vertical flying speed in blocks per second is `fly_speed` ability multiplied by this.
+pub const FLY_VERTICAL_SPEED_SCALE: f64 = 150.;
+/// Speed multiplier of sprinting.
+pub const SPRINT_MULTIPLIER: f64 = 1.3;
+/// Speed multiplier of sprinting while flying.
+pub const FLY_SPRINT_MULTIPLIER: f64 = 2.;
+/// Speed multiplier of sneaking.
+pub const SNEAK_MULTIPLIER: f64 = 0.3;
+
+/// How fast horizontal velocity reaches the wanted one on the ground, per second.
+const GROUND_ACCELERATION: f64 = 20.;
+/// How fast horizontal velocity reaches the wanted one in the air, per second.
+const AIR_ACCELERATION: f64 = 4.;
+/// How fast velocity reaches the wanted one while flying, per second.
+const FLY_ACCELERATION: f64 = 10.;
+/// Step by which movement is shortened to keep sneaking player on the edge.
+const EDGE_STEP: f64 = 0.05;
+/// Boxes closer than this are considered touching.
+const EPSILON: f64 = 1e-7;
+
+/// Axis-aligned box between `min` and `max` corners.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aabb {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Aabb {
+    /// Box of a full block, relative to its corner.
+    pub const FULL_BLOCK: Aabb = Aabb::new([0.; 3], [1.; 3]);
+
+    pub const fn new(min: [f64; 3], max: [f64; 3]) -> Self {
+        Self { min, max }
+    }
+
+    /// Collision box of a player standing with feet at `feet`.
+    pub fn player(feet: [f64; 3]) -> Self {
+        let half = PLAYER_WIDTH / 2.;
+        Self::new([feet[0] - half, feet[1], feet[2] - half], [
+            feet[0] + half,
+            feet[1] + PLAYER_HEIGHT,
+            feet[2] + half,
+        ])
+    }
+
+    /// Box moved by `delta`.
+    pub fn offset(&self, delta: [f64; 3]) -> Self {
+        Self::new(
+            [0, 1, 2].map(|i| self.min[i] + delta[i]),
+            [0, 1, 2].map(|i| self.max[i] + delta[i]),
+        )
+    }
+
+    /// Box that covers every position of this one while it moves by `delta`.
+    pub fn expand_towards(&self, delta: [f64; 3]) -> Self {
+        Self::new(
+            [0, 1, 2].map(|i| self.min[i] + delta[i].min(0.)),
+            [0, 1, 2].map(|i| self.max[i] + delta[i].max(0.)),
+        )
+    }
+
+    /// Whether boxes overlap, touching ones don't.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        (0..3).all(|i| self.min[i] < other.max[i] - EPSILON && self.max[i] > other.min[i] + EPSILON)
+    }
+
+    /// Shortens movement by `delta` along `axis`, so that this box stops when it touches `other`.
+    ///
+    /// Boxes that already overlap don't stop each other, so that it's possible to get out.
+    pub fn clip(&self, other: &Aabb, axis: usize, delta: f64) -> f64 {
+        let overlaps = (0..3)
+            .filter(|i| *i != axis)
+            .all(|i| self.min[i] < other.max[i] - EPSILON && self.max[i] > other.min[i] + EPSILON);
+        if !overlaps {
+            delta
+        } else if delta > 0. && self.max[axis] <= other.min[axis] + EPSILON {
+            delta.min(other.min[axis] - self.max[axis])
+        } else if delta < 0. && self.min[axis] >= other.max[axis] - EPSILON {
+            delta.max(other.max[axis] - self.min[axis])
+        } else {
+            delta
+        }
+    }
+}
+
+/// Collision boxes of `block`, relative to its corner.
+///
+/// Solid blocks are always full cubes here, partial shapes like slabs are not supported:
+/// every registered block is a full cube, and block models are only known to the client.
+/// Such blocks would need their boxes listed here, [`move_aabb`] already handles them.
+pub fn block_collision_boxes(block: Block) -> &'static [Aabb] {
+    if block.is_solid() {
+        &[Aabb::FULL_BLOCK]
+    } else {
+        &[]
+    }
+}
+
+/// Collision shapes of blocks, as seen by physics.
+pub trait Collider {
+    /// Collision boxes of block at `pos` relative to its corner, `None` if its chunk is not
+    /// loaded. Blocks that are not loaded are solid, so that nothing falls out of the world.
+    fn collision_boxes(&self, pos: BlockPos) -> Option<&[Aabb]>;
+}
+
+/// [`Collider`] of blocks returned by a function, `None` for blocks that are not loaded.
+pub struct BlockCollider<F>(pub F);
+
+impl<F: Fn(BlockPos) -> Option<Block>> Collider for BlockCollider<F> {
+    fn collision_boxes(&self, pos: BlockPos) -> Option<&[Aabb]> {
+        (self.0)(pos).map(block_collision_boxes)
+    }
+}
+
+/// Collision boxes of blocks in world coordinates that intersect `area`.
+fn boxes_in(collider: &impl Collider, area: &Aabb) -> Vec<Aabb> {
+    let min = area.min.map(|v| (v - EPSILON).floor() as i32);
+    let max = area.max.map(|v| (v + EPSILON).floor() as i32);
+
+    let mut boxes = Vec::new();
+    for y in min[1]..=max[1] {
+        for z in min[2]..=max[2] {
+            for x in min[0]..=max[0] {
+                let pos = BlockPos::new(x, y, z);
+                let corner = [x as f64, y as f64, z as f64];
+                let shapes = collider.collision_boxes(pos).unwrap_or(&[Aabb::FULL_BLOCK]);
+                boxes.extend(
+                    shapes
+                        .iter()
+                        .map(|shape| shape.offset(corner))
+                        .filter(|shape| shape.intersects(area)),
+                );
+            }
+        }
+    }
+    boxes
+}
+
+/// Movement of `aabb` by `delta` shortened by `boxes`.
+///
+/// Axes are resolved one by one: vertical first, then the faster horizontal one, so that sliding
+/// along walls works.
+fn collide(aabb: &Aabb, boxes: &[Aabb], delta: [f64; 3]) -> [f64; 3] {
+    let order = if delta[0].abs() < delta[2].abs() {
+        [1, 2, 0]
+    } else {
+        [1, 0, 2]
+    };
+
+    let mut moved = *aabb;
+    let mut result = [0.; 3];
+    for axis in order {
+        if delta[axis] == 0. {
+            continue;
+        }
+        result[axis] = boxes
+            .iter()
+            .fold(delta[axis], |d, other| moved.clip(other, axis, d));
+        let mut offset = [0.; 3];
+        offset[axis] = result[axis];
+        moved = moved.offset(offset);
+    }
+    result
+}
+
+/// Movement of `aabb` by `delta` shortened by blocks of `collider`, for things that don't step
+/// up ledges, like particles.
+pub fn move_aabb(aabb: &Aabb, delta: [f64; 3], collider: &impl Collider) -> [f64; 3] {
+    let boxes = boxes_in(collider, &aabb.expand_towards(delta));
+    collide(aabb, &boxes, delta)
+}
+
+/// Movement wanted by the player during one step.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct MovementInput {
+    /// 1 forward, -1 backward.
+    pub forward: f64,
+    /// 1 to the right, -1 to the left.
+    pub strafe: f64,
+    /// Rotation around vertical axis in radians, counter-clockwise from looking at -Z.
+    pub yaw: f64,
+    pub jump: bool,
+    pub sneak: bool,
+    pub sprint: bool,
+}
+
+impl MovementInput {
+    /// Normalized direction of horizontal movement, as `[x, z]`.
+    pub fn direction(&self) -> [f64; 2] {
+        let (sin, cos) = self.yaw.sin_cos();
+        let x = -sin * self.forward + cos * self.strafe;
+        let z = -cos * self.forward - sin * self.strafe;
+        let length = (x * x + z * z).sqrt();
+        if length > 1. {
+            [x / length, z / length]
+        } else {
+            [x, z]
+        }
+    }
+}
+
+/// Position and velocity of a player walking in the world.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct PlayerPhysics {
+    /// Center of the bottom of collision box.
+    pub position: [f64; 3],
+    /// Velocity in blocks per second.
+    pub velocity: [f64; 3],
+    pub on_ground: bool,
+    pub sneaking: bool,
+    /// Flying players move vertically by jump and sneak and don't fall. Flight stops on landing.
+    pub flying: bool,
+    /// Players without collisions always fly, through blocks too.
+    pub noclip: bool,
+}
+
+impl PlayerPhysics {
+    pub fn new(position: [f64; 3]) -> Self {
+        Self {
+            position,
+            ..Default::default()
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::player(self.position)
+    }
+
+    /// Height of the camera above feet.
+    pub fn eye_height(&self) -> f64 {
+        if self.sneaking {
+            SNEAK_EYE_HEIGHT
+        } else {
+            EYE_HEIGHT
+        }
+    }
+
+    /// Advances the player by `dt` seconds: applies input, gravity and moves with collisions.
+    ///
+    /// `walk_speed` and `fly_speed` are the abilities of the same names.
+    pub fn step(
+        &mut self,
+        input: &MovementInput,
+        walk_speed: f64,
+        fly_speed: f64,
+        dt: f64,
+        collider: &impl Collider,
+    ) {
+        self.flying |= self.noclip;
+        self.sneaking = input.sneak && !self.flying;
+
+        let [dx, dz] = input.direction();
+        let (speed, acceleration) = if self.flying {
+            let multiplier = if input.sprint {
+                FLY_SPRINT_MULTIPLIER
+            } else {
+                1.
+            };
+            (fly_speed * FLY_SPEED_SCALE * multiplier, FLY_ACCELERATION)
+        } else {
+            let multiplier = if input.sneak {
+                SNEAK_MULTIPLIER
+            } else if input.sprint && input.forward > 0. {
+                SPRINT_MULTIPLIER
+            } else {
+                1.
+            };
+            let acceleration = if self.on_ground {
+                GROUND_ACCELERATION
+            } else {
+                AIR_ACCELERATION
+            };
+            (walk_speed * WALK_SPEED_SCALE * multiplier, acceleration)
+        };
+        let blend = 1. - (-acceleration * dt).exp();
+        self.velocity[0] += (dx * speed - self.velocity[0]) * blend;
+        self.velocity[2] += (dz * speed - self.velocity[2]) * blend;
+
+        if self.flying {
+            let vertical = input.jump as i8 as f64 - input.sneak as i8 as f64;
+            let target = vertical * fly_speed * FLY_VERTICAL_SPEED_SCALE;
+            self.velocity[1] += (target - self.velocity[1]) * blend;
+        } else {
+            if input.jump && self.on_ground {
+                self.velocity[1] = JUMP_VELOCITY;
+            }
+            self.velocity[1] = (self.velocity[1] - GRAVITY * dt).max(-TERMINAL_VELOCITY);
+        }
+
+        let delta = self.velocity.map(|v| v * dt);
+        if self.noclip {
+            (0..3).for_each(|i| self.position[i] += delta[i]);
+            self.on_ground = false;
+            return;
+        }
+        self.move_by(delta, collider);
+        if self.flying && self.on_ground {
+            self.flying = false;
+        }
+    }
+
+    /// Moves the player by `delta` with collisions, stepping up on low ledges and keeping sneaking
+    /// player from falling off edges. Velocity is stopped along blocked axes.
+    pub fn move_by(&mut self, delta: [f64; 3], collider: &impl Collider) {
+        let aabb = self.aabb();
+        let wanted = delta;
+        let delta = if self.sneaking && self.on_ground {
+            self.keep_on_edge(delta, collider)
+        } else {
+            delta
+        };
+
+        let area = aabb
+            .expand_towards(delta)
+            .expand_towards([0., STEP_HEIGHT, 0.]);
+        let boxes = boxes_in(collider, &area);
+        let mut moved = collide(&aabb, &boxes, delta);
+
+        let blocked_horizontally = moved[0] != delta[0] || moved[2] != delta[2];
+        let landed = delta[1] < 0. && moved[1] != delta[1];
+        let mut stepped = false;
+        if blocked_horizontally && (self.on_ground || landed) {
+            let up = collide(&aabb, &boxes, [0., STEP_HEIGHT, 0.])[1];
+            let raised = aabb.offset([0., up, 0.]);
+            let mut step = collide(&raised, &boxes, [delta[0], 0., delta[2]]);
+            let forward = raised.offset(step);
+            step[1] = up + collide(&forward, &boxes, [0., delta[1].min(0.) - up, 0.])[1];
+
+            let distance = |d: [f64; 3]| d[0] * d[0] + d[2] * d[2];
+            if distance(step) > distance(moved) + EPSILON {
+                moved = step;
+                stepped = true;
+            }
+        }
+
+        for i in 0..3 {
+            self.position[i] += moved[i];
+            if moved[i] != wanted[i] {
+                self.velocity[i] = 0.;
+            }
+        }
+        self.on_ground = stepped || landed;
+    }
+
+    /// Shortens horizontal `delta`, so that the player keeps standing on something.
+    fn keep_on_edge(&self, delta: [f64; 3], collider: &impl Collider) -> [f64; 3] {
+        let aabb = self.aabb();
+        let falls = |dx: f64, dz: f64| {
+            let below = aabb.offset([dx, -STEP_HEIGHT, dz]);
+            boxes_in(collider, &below).is_empty()
+        };
+        let shorten = |d: f64| {
+            if d.abs() < EDGE_STEP {
+                0.
+            } else {
+                d - EDGE_STEP * d.signum()
+            }
+        };
+
+        let [mut dx, dy, mut dz] = delta;
+        while dx != 0. && falls(dx, 0.) {
+            dx = shorten(dx);
+        }
+        while dz != 0. && falls(0., dz) {
+            dz = shorten(dz);
+        }
+        while dx != 0. && dz != 0. && falls(dx, dz) {
+            dx = shorten(dx);
+            dz = shorten(dz);
+        }
+        [dx, dy, dz]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f64 = 0.02;
+    const SLAB: [Aabb; 1] = [Aabb::new([0.; 3], [1., 0.5, 1.])];
+
+    /// Floor with top at `y = 1`, ending at `x = 10`. A slab at `x = 3` and a wall at `x = 6`.
+    struct TestWorld;
+
+    impl Collider for TestWorld {
+        fn collision_boxes(&self, pos: BlockPos) -> Option<&[Aabb]> {
+            Some(match (pos.x, pos.y) {
+                (..10, ..1) | (6, 1 | 2) => &[Aabb::FULL_BLOCK],
+                (3, 1) => &SLAB,
+                _ => &[],
+            })
+        }
+    }
+
+    fn run(physics: &mut PlayerPhysics, input: MovementInput, steps: usize) {
+        for _ in 0..steps {
+            physics.step(&input, 0.1, 0.05, DT, &TestWorld);
+        }
+    }
+
+    /// Walking along +X.
+    const EAST: MovementInput = MovementInput {
+        forward: 1.,
+        strafe: 0.,
+        yaw: -std::f64::consts::FRAC_PI_2,
+        jump: false,
+        sneak: false,
+        sprint: false,
+    };
+
+    #[test]
+    fn player_falls_and_lands_on_floor() {
+        let mut physics = PlayerPhysics::new([0.5, 5., 0.5]);
+        run(&mut physics, MovementInput::default(), 100);
+        assert!(physics.on_ground);
+        assert!((physics.position[1] - 1.).abs() < 1e-6);
+        assert_eq!(physics.velocity[1], 0.);
+
+        // Terminal velocity is reached far above the floor.
+        let mut physics = PlayerPhysics::new([0.5, 1000., 0.5]);
+        run(&mut physics, MovementInput::default(), 150);
+        assert_eq!(physics.velocity[1], -TERMINAL_VELOCITY);
+    }
+
+    #[test]
+    fn jump_reaches_a_block_and_a_quarter() {
+        let mut physics = PlayerPhysics::new([0.5, 1., 0.5]);
+        run(&mut physics, MovementInput::default(), 1);
+        let jump = MovementInput {
+            jump: true,
+            ..Default::default()
+        };
+        let mut highest: f64 = 0.;
+        for _ in 0..50 {
+            physics.step(&jump, 0.1, 0.05, DT, &TestWorld);
+            highest = highest.max(physics.position[1]);
+        }
+        assert!((2.1..2.3).contains(&highest), "{highest}");
+    }
+
+    #[test]
+    fn player_steps_on_slab_but_not_on_wall() {
+        let mut physics = PlayerPhysics::new([0.5, 1., 0.5]);
+        while physics.position[0] < 3.5 {
+            run(&mut physics, EAST, 1);
+        }
+        assert!((physics.position[1] - 1.5).abs() < 1e-6);
+
+        run(&mut physics, EAST, 100);
+        assert!((physics.position[0] - (6. - PLAYER_WIDTH / 2.)).abs() < 1e-6);
+        assert_eq!(physics.velocity[0], 0.);
+    }
+
+    #[test]
+    fn sprinting_is_faster_and_sneaking_slower() {
+        let speed = |input: MovementInput| {
+            let mut physics = PlayerPhysics::new([0.5, 1., 0.5]);
+            run(&mut physics, input, 20);
+            physics.velocity[0]
+        };
+        let walk = speed(EAST);
+        assert!((walk - 4.317).abs() < 0.01, "{walk}");
+        assert!(
+            speed(MovementInput {
+                sprint: true,
+                ..EAST
+            }) > walk
+        );
+        assert!(
+            speed(MovementInput {
+                sneak: true,
+                ..EAST
+            }) < walk
+        );
+    }
+
+    #[test]
+    fn sneaking_player_stays_on_edge() {
+        let mut physics = PlayerPhysics::new([8.5, 1., 0.5]);
+        run(
+            &mut physics,
+            MovementInput {
+                sneak: true,
+                ..EAST
+            },
+            200,
+        );
+        assert!(physics.on_ground);
+        assert!(physics.position[0] < 10. + PLAYER_WIDTH / 2.);
+        assert_eq!(physics.position[1], 1.);
+
+        // Without sneaking it falls off.
+        run(&mut physics, EAST, 100);
+        assert!(physics.position[1] < 0.);
+    }
+
+    #[test]
+    fn box_stops_at_slab_without_stepping() {
+        let small = Aabb::new([2.5, 1.2, 0.4], [2.7, 1.4, 0.6]);
+        let fallen = move_aabb(&small, [0., -1., 0.], &TestWorld);
+        assert!((fallen[1] + 0.2).abs() < 1e-9);
+        let moved = move_aabb(&small, [1., 0., 0.], &TestWorld);
+        assert!((moved[0] - 0.3).abs() < 1e-9);
+        assert_eq!(moved[1], 0.);
+    }
+
+    #[test]
+    fn flying_player_does_not_fall_and_lands() {
+        let mut physics = PlayerPhysics::new([-20.5, 5., 0.5]);
+        physics.flying = true;
+        run(&mut physics, EAST, 50);
+        assert!((physics.position[1] - 5.).abs() < 1e-6);
+        assert!(physics.velocity[0] > 10.);
+
+        let up = MovementInput {
+            jump: true,
+            ..Default::default()
+        };
+        run(&mut physics, up, 50);
+        assert!(physics.position[1] > 10.);
+
+        let down = MovementInput {
+            sneak: true,
+            ..Default::default()
+        };
+        run(&mut physics, down, 200);
+        assert!(!physics.flying);
+        assert_eq!(physics.position[1], 1.);
+    }
+
+    #[test]
+    fn noclip_passes_through_blocks() {
+        let mut physics = PlayerPhysics::new([0.5, 1., 0.5]);
+        physics.noclip = true;
+        let down = MovementInput {
+            sneak: true,
+            ..Default::default()
+        };
+        run(&mut physics, down, 50);
+        assert!(physics.flying);
+        assert!(physics.position[1] < 0.);
+    }
+}
@@ -0,0 +1,106 @@
+use std::f64::consts::{PI, TAU};
+
+/// Game ticks per second of real time.
+pub const TICKS_PER_SECOND: f64 = 20.;
+/// Length of a full day and night in ticks.
+pub const TICKS_PER_DAY: i64 = 24000;
+
+/// Time of day set by `/time set day`.
+pub const DAY: i64 = 1000;
+/// Time of day set by `/time set noon`.
+pub const NOON: i64 = 6000;
+/// Time of day set by `/time set night`.
+pub const NIGHT: i64 = 13000;
+/// Time of day set by `/time set midnight`.
+pub const MIDNIGHT: i64 = 18000;
+
+/// Part of the day the sky goes through.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DayPhase {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl DayPhase {
+    /// Phase at `day_time`, in ticks since the start of the first day.
+    pub fn of(day_time: i64) -> Self {
+        match day_time.rem_euclid(TICKS_PER_DAY) {
+            0..12000 => Self::Day,
+            12000..13000 => Self::Dusk,
+            13000..23000 => Self::Night,
+            _ => Self::Dawn,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Dawn => "dawn",
+            Self::Day => "day",
+            Self::Dusk => "dusk",
+            Self::Night => "night",
+        }
+    }
+}
+
+/// Time of day for names accepted by `/time set`, such as `noon`, or for a number of ticks.
+pub fn parse_day_time(value: &str) -> Option<i64> {
+    match value {
+        "day" => Some(DAY),
+        "noon" => Some(NOON),
+        "night" => Some(NIGHT),
+        "midnight" => Some(MIDNIGHT),
+        ticks => ticks.parse().ok(),
+    }
+}
+
+/// Position of the sun in the sky at `day_time`, the same way as in Minecraft.
+///
+/// It's 0 at noon, 0.25 at sunset, 0.5 at midnight and 0.75 at sunrise. Sun moves faster
+/// around noon and midnight, so that days are longer than dawn and dusk.
+pub fn celestial_angle(day_time: i64, partial_tick: f64) -> f64 {
+    let day = (day_time.rem_euclid(TICKS_PER_DAY) as f64 + partial_tick) / TICKS_PER_DAY as f64;
+    let f = (day - 0.25).rem_euclid(1.);
+    let g = 0.5 - (f * PI).cos() / 2.;
+    (f * 2. + g) / 3.
+}
+
+/// How bright the sky is at `celestial_angle`, from 0 at night to 1 during the day.
+pub fn sky_brightness(celestial_angle: f64) -> f64 {
+    ((celestial_angle * TAU).cos() * 2. + 0.5).clamp(0., 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sun_goes_around_once_a_day() {
+        assert!(celestial_angle(NOON, 0.).abs() < 1e-9);
+        assert!((celestial_angle(MIDNIGHT, 0.) - 0.5).abs() < 1e-9);
+        assert!(celestial_angle(NOON - 1, 0.) > 0.99);
+        assert_eq!(
+            celestial_angle(NOON + TICKS_PER_DAY * 3, 0.),
+            celestial_angle(NOON, 0.)
+        );
+    }
+
+    #[test]
+    fn sky_darkens_through_dusk() {
+        let brightness = |time| sky_brightness(celestial_angle(time, 0.));
+        assert_eq!(brightness(NOON), 1.);
+        assert_eq!(brightness(MIDNIGHT), 0.);
+        assert!(brightness(12500) > 0. && brightness(12500) < 1.);
+        assert!(brightness(12000) > brightness(12500));
+        assert_eq!(DayPhase::of(12500), DayPhase::Dusk);
+        assert_eq!(DayPhase::of(-500), DayPhase::Dawn);
+    }
+
+    #[test]
+    fn time_names_are_parsed() {
+        assert_eq!(parse_day_time("noon"), Some(NOON));
+        assert_eq!(parse_day_time("1234"), Some(1234));
+        assert_eq!(parse_day_time("evening"), None);
+    }
+}
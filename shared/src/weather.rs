@@ -0,0 +1,203 @@
+use std::ops::Range;
+
+use crate::biome::Biome;
+use crate::noise::SplitMix64;
+
+/// Ticks of clear weather before the next rain.
+pub const CLEAR_DURATION: Range<i32> = 12000..180000;
+/// Ticks of rain.
+pub const RAIN_DURATION: Range<i32> = 12000..24000;
+/// Ticks before a thunderstorm.
+pub const THUNDER_DELAY: Range<i32> = 12000..180000;
+/// Ticks of thunderstorm, it happens only while it rains.
+pub const THUNDER_DURATION: Range<i32> = 3600..15600;
+
+/// Biomes colder than this have snow instead of rain.
+const SNOW_TEMPERATURE: f32 = 0.15;
+/// Above this height it gets colder with every block.
+const TEMPERATURE_FALLOFF_HEIGHT: i32 = 80;
+const TEMPERATURE_FALLOFF: f32 = 0.00125;
+
+/// Weather as it's set by `/weather`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Clear => "clear",
+            Self::Rain => "rain",
+            Self::Thunder => "thunder",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Clear, Self::Rain, Self::Thunder]
+            .into_iter()
+            .find(|weather| weather.name() == name)
+    }
+}
+
+/// Weather timers, the same as in level.dat.
+///
+/// Each timer counts ticks down to the next change of its weather, rain and thunder are
+/// toggled when they run out. While `clear_time` runs, it's clear and other timers wait.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct WeatherState {
+    pub clear_time: i32,
+    pub rain_time: i32,
+    pub thunder_time: i32,
+    pub raining: bool,
+    pub thundering: bool,
+}
+
+fn random_duration(rng: &mut SplitMix64, range: Range<i32>) -> i32 {
+    range.start + (rng.next_u64() % (range.end - range.start) as u64) as i32
+}
+
+impl WeatherState {
+    pub fn weather(&self) -> Weather {
+        match (self.raining, self.thundering) {
+            (true, true) => Weather::Thunder,
+            (true, false) => Weather::Rain,
+            _ => Weather::Clear,
+        }
+    }
+
+    /// Sets `weather` for `duration` ticks, or for a random time like `/weather` without
+    /// duration.
+    pub fn set(&mut self, weather: Weather, duration: Option<i32>, rng: &mut SplitMix64) {
+        let duration = duration.unwrap_or_else(|| match weather {
+            Weather::Clear => random_duration(rng, CLEAR_DURATION),
+            Weather::Rain => random_duration(rng, RAIN_DURATION),
+            Weather::Thunder => random_duration(rng, THUNDER_DURATION),
+        });
+        *self = match weather {
+            Weather::Clear => Self {
+                clear_time: duration,
+                ..Self::default()
+            },
+            _ => Self {
+                clear_time: 0,
+                rain_time: duration,
+                thunder_time: duration,
+                raining: true,
+                thundering: weather == Weather::Thunder,
+            },
+        };
+    }
+
+    /// Advances weather by one tick, the same way as Minecraft server does.
+    pub fn tick(&mut self, rng: &mut SplitMix64) {
+        if self.clear_time > 0 {
+            self.clear_time -= 1;
+            // Rain and thunder start right after clear weather ends.
+            self.rain_time = if self.raining { 0 } else { 1 };
+            self.thunder_time = if self.thundering { 0 } else { 1 };
+            self.raining = false;
+            self.thundering = false;
+            return;
+        }
+
+        if self.thunder_time > 0 {
+            self.thunder_time -= 1;
+            if self.thunder_time == 0 {
+                self.thundering = !self.thundering;
+            }
+        } else if self.thundering {
+            self.thunder_time = random_duration(rng, THUNDER_DURATION);
+        } else {
+            self.thunder_time = random_duration(rng, THUNDER_DELAY);
+        }
+
+        if self.rain_time > 0 {
+            self.rain_time -= 1;
+            if self.rain_time == 0 {
+                self.raining = !self.raining;
+            }
+        } else if self.raining {
+            self.rain_time = random_duration(rng, RAIN_DURATION);
+        } else {
+            self.rain_time = random_duration(rng, CLEAR_DURATION);
+        }
+    }
+}
+
+/// What falls from the sky when it rains.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Precipitation {
+    None,
+    Rain,
+    Snow,
+}
+
+impl Precipitation {
+    /// Precipitation in `biome` at height `y`, it never rains in dry biomes and snows in cold
+    /// ones and high in the mountains.
+    pub fn at(biome: Biome, y: i32) -> Self {
+        if biome.downfall() <= 0. {
+            return Self::None;
+        }
+        let above = (y - TEMPERATURE_FALLOFF_HEIGHT).max(0) as f32;
+        if biome.temperature() - above * TEMPERATURE_FALLOFF < SNOW_TEMPERATURE {
+            Self::Snow
+        } else {
+            Self::Rain
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rain_starts_after_clear_weather() {
+        let mut rng = SplitMix64::new(0);
+        let mut state = WeatherState::default();
+        state.set(Weather::Clear, Some(2), &mut rng);
+        state.tick(&mut rng);
+        state.tick(&mut rng);
+        assert_eq!(state.weather(), Weather::Clear);
+        // Timers were left at 1 tick, so it starts raining and thundering.
+        state.tick(&mut rng);
+        assert_eq!(state.weather(), Weather::Thunder);
+        state.tick(&mut rng);
+        assert!(RAIN_DURATION.contains(&state.rain_time));
+    }
+
+    #[test]
+    fn weather_is_set_for_duration() {
+        let mut rng = SplitMix64::new(0);
+        let mut state = WeatherState::default();
+        state.set(Weather::Rain, Some(3), &mut rng);
+        assert_eq!(state.weather(), Weather::Rain);
+        for _ in 0..3 {
+            state.tick(&mut rng);
+        }
+        assert_eq!(state.weather(), Weather::Clear);
+
+        state.set(Weather::Thunder, None, &mut rng);
+        assert!(THUNDER_DURATION.contains(&state.rain_time));
+        assert_eq!(Weather::from_name("thunder"), Some(Weather::Thunder));
+    }
+
+    #[test]
+    fn snow_falls_in_cold_biomes() {
+        assert_eq!(Precipitation::at(Biome::Plains, 64), Precipitation::Rain);
+        assert_eq!(
+            Precipitation::at(Biome::SnowyPlains, 64),
+            Precipitation::Snow
+        );
+        assert_eq!(Precipitation::at(Biome::Desert, 64), Precipitation::None);
+        assert_eq!(Precipitation::at(Biome::Mountains, 70), Precipitation::Rain);
+        assert_eq!(
+            Precipitation::at(Biome::Mountains, 150),
+            Precipitation::Snow
+        );
+    }
+}